@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{sse::SseEvent, Completion, CompletionRequest, LlmProvider, StreamEvent, Usage};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const MAX_TOKENS: u32 = 4096;
+
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    system: &'a str,
+    messages: Vec<Message<'a>>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<MessagesUsage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessagesUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+impl From<MessagesUsage> for Usage {
+    fn from(usage: MessagesUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    usage: MessagesUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+// Streaming events are tagged by `type`; only text deltas, the end of the
+// message and errors matter to us.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamPayload {
+    // Carries the input token count
+    MessageStart { message: StreamMessage },
+    ContentBlockDelta { delta: StreamDelta },
+    // Carries the final output token count
+    MessageDelta {
+        #[serde(default)]
+        usage: MessagesUsage,
+    },
+    MessageStop,
+    Error { error: serde_json::Value },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// Anthropic Messages API.
+pub struct AnthropicMessages {
+    endpoint: String,
+}
+
+impl AnthropicMessages {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            endpoint: format!("{}/messages", base_url.trim_end_matches('/')),
+        }
+    }
+}
+
+impl LlmProvider for AnthropicMessages {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: Option<&str>,
+        request: &CompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        let body = MessagesRequest {
+            model: request.model,
+            max_tokens: MAX_TOKENS,
+            system: request.system_prompt,
+            messages: vec![Message {
+                role: "user",
+                content: request.text,
+            }],
+            temperature: request.temperature,
+            stream: request.stream,
+        };
+
+        let builder = client
+            .post(&self.endpoint)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body);
+        match api_key {
+            Some(key) => builder.header("x-api-key", key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &str) -> Result<Completion> {
+        let response: MessagesResponse = serde_json::from_str(body)?;
+        let usage = response.usage.map(Usage::from);
+        let text: String = response
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect();
+
+        if text.is_empty() {
+            Err(anyhow!("No response from AI"))
+        } else {
+            Ok(Completion { text, usage })
+        }
+    }
+
+    fn parse_stream_event(&self, event: &SseEvent) -> Result<StreamEvent> {
+        let payload: StreamPayload =
+            serde_json::from_str(&event.data).context("Failed to parse AI stream event")?;
+
+        Ok(match payload {
+            StreamPayload::ContentBlockDelta { delta } if delta.kind == "text_delta" => {
+                StreamEvent::Delta(delta.text)
+            }
+            StreamPayload::MessageStart { message } => StreamEvent::Usage(message.usage.into()),
+            StreamPayload::MessageDelta { usage } => StreamEvent::Usage(usage.into()),
+            StreamPayload::MessageStop => StreamEvent::Done,
+            StreamPayload::Error { error } => return Err(anyhow!("AI stream error: {}", error)),
+            _ => StreamEvent::Ignore,
+        })
+    }
+}
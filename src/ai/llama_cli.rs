@@ -0,0 +1,220 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+use super::{Completion, CompletionRequest, ProviderConfig};
+use crate::binaries::{expand_home, resolve_binary};
+
+/// Upper bound on generated tokens; a cleaned-up dictation is never longer.
+const MAX_TOKENS: u32 = 1024;
+
+/// Marker llama-cli prints to stdout when the model emits end-of-sequence.
+const END_OF_TEXT: &str = "[end of text]";
+
+/// Offline completions by running `llama-cli` on a local GGUF model, the same
+/// way transcription shells out to `whisper-cli`. For `llama-server`, use an
+/// OpenAI-compatible provider pointed at the server instead.
+pub struct LlamaCli {
+    cli_path: Option<String>,
+    model_path: Option<String>,
+    timeout: Duration,
+}
+
+impl LlamaCli {
+    /// `timeout` bounds the whole run, including loading the model.
+    pub fn new(config: &ProviderConfig, timeout: Duration) -> Self {
+        Self {
+            cli_path: config.cli_path.clone(),
+            model_path: config.model_path.clone(),
+            timeout,
+        }
+    }
+
+    pub async fn complete(&self, request: &CompletionRequest<'_>) -> Result<Completion> {
+        let binary = resolve_binary("llama-cli", self.cli_path.as_deref())?;
+        let model = self.model()?;
+        let text = run(&binary, &args(&model, request), self.timeout).await?;
+
+        if text.is_empty() {
+            bail!("No response from llama-cli");
+        }
+        // llama-cli reports timings on stderr in a version-specific format,
+        // so no usage is recorded; local runs cost nothing anyway.
+        Ok(Completion { text, usage: None })
+    }
+
+    fn model(&self) -> Result<PathBuf> {
+        let path = self
+            .model_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .context("No GGUF model configured for llama.cpp. Set model_path for the provider in settings.")?;
+
+        let model = expand_home(path);
+        if !model.exists() {
+            bail!("llama.cpp model not found: {}", model.display());
+        }
+        Ok(model)
+    }
+}
+
+/// Without conversation mode llama-cli doesn't apply the model's chat
+/// template, so the instruction and the text go into one plain prompt.
+fn render_prompt(system_prompt: &str, text: &str) -> String {
+    format!("{}\n\nText:\n{}\n\nRewritten text:\n", system_prompt.trim(), text.trim())
+}
+
+fn args(model: &Path, request: &CompletionRequest) -> Vec<OsString> {
+    vec![
+        "-m".into(),
+        model.into(),
+        "-p".into(),
+        render_prompt(request.system_prompt, request.text).into(),
+        "-n".into(),
+        MAX_TOKENS.to_string().into(),
+        "--temp".into(),
+        request.temperature.to_string().into(),
+        // One-shot completion: no interactive chat, and don't echo the prompt
+        "-no-cnv".into(),
+        "--no-display-prompt".into(),
+        "--log-disable".into(),
+    ]
+}
+
+async fn run(binary: &Path, args: &[OsString], timeout: Duration) -> Result<String> {
+    log::info!("Running {:?} for AI processing", binary);
+
+    let mut command = Command::new(binary);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| anyhow!("llama-cli timed out after {}s", timeout.as_secs()))?
+        .with_context(|| format!("Failed to run {}", binary.display()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("llama-cli failed ({}): {}", output.status, stderr.trim());
+    }
+
+    Ok(clean_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn clean_output(stdout: &str) -> String {
+    let text = stdout.trim();
+    text.strip_suffix(END_OF_TEXT)
+        .unwrap_or(text)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ProviderKind;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A stand-in `llama-cli` running `body`, next to an empty model file.
+    /// The prompt it was given is written to `prompt.txt` in the same dir.
+    fn fake_cli(name: &str, body: &str, timeout: Duration) -> (LlamaCli, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("convey-llama-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let script = dir.join("llama-cli");
+        let prompt = dir.join("prompt.txt");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nwhile [ $# -gt 0 ]; do\n  if [ \"$1\" = -p ]; then printf '%s' \"$2\" > '{}'; fi\n  shift\ndone\n{}\n",
+                prompt.display(),
+                body
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let model = dir.join("model.gguf");
+        fs::write(&model, b"").unwrap();
+
+        let mut config = ProviderConfig::new("llama_cli", "llama.cpp", ProviderKind::LlamaCli, "", false);
+        config.cli_path = Some(script.display().to_string());
+        config.model_path = Some(model.display().to_string());
+        (LlamaCli::new(&config, timeout), prompt)
+    }
+
+    fn request<'a>(text: &'a str) -> CompletionRequest<'a> {
+        CompletionRequest {
+            model: "local",
+            system_prompt: "Fix the grammar.",
+            text,
+            temperature: 0.2,
+            stream: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_stdout_without_the_end_marker() {
+        let (cli, prompt) = fake_cli(
+            "success",
+            "echo 'loading model' >&2\nprintf 'She went home.\\n [end of text]\\n'",
+            Duration::from_secs(10),
+        );
+        let completion = cli.complete(&request("she go home")).await.unwrap();
+
+        assert_eq!(completion.text, "She went home.");
+        assert!(completion.usage.is_none());
+        assert_eq!(
+            fs::read_to_string(prompt).unwrap(),
+            "Fix the grammar.\n\nText:\nshe go home\n\nRewritten text:\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_output_is_an_error() {
+        let (cli, _) = fake_cli("empty", "echo '[end of text]'", Duration::from_secs(10));
+        let err = cli.complete(&request("hello")).await.unwrap_err();
+        assert_eq!(err.to_string(), "No response from llama-cli");
+    }
+
+    #[tokio::test]
+    async fn failure_reports_the_exit_status_and_stderr() {
+        let (cli, _) = fake_cli(
+            "failure",
+            "echo 'partial output'\necho 'error: unknown model architecture' >&2\nexit 3",
+            Duration::from_secs(10),
+        );
+        let err = cli.complete(&request("hello")).await.unwrap_err().to_string();
+
+        assert!(err.starts_with("llama-cli failed"), "{}", err);
+        assert!(err.contains("exit status: 3"), "{}", err);
+        assert!(err.ends_with("error: unknown model architecture"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn slow_runs_time_out() {
+        let (cli, _) = fake_cli("timeout", "sleep 5\necho late", Duration::from_millis(300));
+        let started = std::time::Instant::now();
+        let err = cli.complete(&request("hello")).await.unwrap_err();
+
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn missing_model_is_reported_before_running() {
+        let (mut cli, _) = fake_cli("no-model", "exit 1", Duration::from_secs(10));
+        cli.model_path = Some("/nonexistent/model.gguf".to_string());
+        let err = cli.complete(&request("hello")).await.unwrap_err();
+        assert_eq!(err.to_string(), "llama.cpp model not found: /nonexistent/model.gguf");
+    }
+}
@@ -0,0 +1,882 @@
+mod anthropic;
+mod llama_cli;
+mod openai;
+mod sse;
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use anthropic::AnthropicMessages;
+use llama_cli::LlamaCli;
+use openai::OpenAiCompatible;
+use sse::{SseEvent, SseParser};
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant that cleans up and improves transcribed text. Fix grammar, punctuation, and formatting while preserving the original meaning.";
+
+/// Wire format spoken by a provider's endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    /// `POST {base_url}/chat/completions` - OpenAI, Ollama, LM Studio, gateways.
+    OpenAiCompatible,
+    /// `POST {base_url}/messages` - Anthropic Messages API.
+    Anthropic,
+    /// Local `llama-cli` subprocess; `base_url` is unused.
+    LlamaCli,
+}
+
+/// A configured LLM endpoint. The API key lives in the keychain under
+/// [`ProviderConfig::api_key_name`], never in the settings file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProviderConfig {
+    pub id: String,
+    pub name: String,
+    pub kind: ProviderKind,
+    pub base_url: String,
+    #[serde(default = "default_requires_api_key")]
+    pub requires_api_key: bool,
+    /// `llama-cli` binary override for [`ProviderKind::LlamaCli`].
+    #[serde(default)]
+    pub cli_path: Option<String>,
+    /// GGUF model file for [`ProviderKind::LlamaCli`].
+    #[serde(default)]
+    pub model_path: Option<String>,
+    /// Ask an [`ProviderKind::OpenAiCompatible`] stream for token usage
+    /// (`stream_options.include_usage`). Off unless the server is known to
+    /// accept it; stricter servers reject unknown fields.
+    #[serde(default)]
+    pub stream_usage: bool,
+}
+
+fn default_requires_api_key() -> bool {
+    true
+}
+
+impl ProviderConfig {
+    pub fn new(id: &str, name: &str, kind: ProviderKind, base_url: &str, requires_api_key: bool) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind,
+            base_url: base_url.to_string(),
+            requires_api_key,
+            cli_path: None,
+            model_path: None,
+            stream_usage: false,
+        }
+    }
+
+    /// Keychain entry for this provider's key. The OpenAI entry keeps the
+    /// name used before providers existed so stored keys keep working.
+    pub fn api_key_name(&self) -> String {
+        if self.id == "openai" {
+            "openai_api_key".to_string()
+        } else {
+            format!("{}_api_key", self.id)
+        }
+    }
+
+    pub fn defaults() -> Vec<ProviderConfig> {
+        vec![
+            ProviderConfig {
+                stream_usage: true,
+                ..ProviderConfig::new(
+                    "openai",
+                    "OpenAI",
+                    ProviderKind::OpenAiCompatible,
+                    "https://api.openai.com/v1",
+                    true,
+                )
+            },
+            ProviderConfig::new(
+                "anthropic",
+                "Anthropic",
+                ProviderKind::Anthropic,
+                "https://api.anthropic.com/v1",
+                true,
+            ),
+            ProviderConfig::new(
+                "ollama",
+                "Ollama",
+                ProviderKind::OpenAiCompatible,
+                "http://localhost:11434/v1",
+                false,
+            ),
+            ProviderConfig::new(
+                "lmstudio",
+                "LM Studio",
+                ProviderKind::OpenAiCompatible,
+                "http://localhost:1234/v1",
+                false,
+            ),
+            ProviderConfig::new(
+                "llama_server",
+                "llama.cpp server",
+                ProviderKind::OpenAiCompatible,
+                "http://localhost:8080/v1",
+                false,
+            ),
+            ProviderConfig::new("llama_cli", "llama.cpp (local)", ProviderKind::LlamaCli, "", false),
+        ]
+    }
+}
+
+impl std::fmt::Display for ProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Provider-neutral description of a single completion.
+pub struct CompletionRequest<'a> {
+    pub model: &'a str,
+    pub system_prompt: &'a str,
+    pub text: &'a str,
+    pub temperature: f32,
+    /// Ask for a server-sent event stream instead of a single JSON body.
+    pub stream: bool,
+}
+
+/// Token counts reported by the provider for one completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    /// Streams report usage in pieces (Anthropic sends input tokens first and
+    /// output tokens at the end), so keep the largest value seen per field.
+    fn merge(self, other: Usage) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_tokens.max(other.prompt_tokens),
+            completion_tokens: self.completion_tokens.max(other.completion_tokens),
+        }
+    }
+}
+
+/// Output of a completion: the text plus whatever usage the provider reported.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<Usage>,
+}
+
+/// Per-model prices in USD per million tokens.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ModelPrice {
+    pub model: String,
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl ModelPrice {
+    fn new(model: &str, prompt_per_million: f64, completion_per_million: f64) -> Self {
+        Self {
+            model: model.to_string(),
+            prompt_per_million,
+            completion_per_million,
+        }
+    }
+
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_million
+            + usage.completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+
+    /// Looks up the price for `model`, or `None` for models not in the table
+    /// (local models, or ones the user hasn't priced).
+    pub fn find<'a>(prices: &'a [ModelPrice], model: &str) -> Option<&'a ModelPrice> {
+        prices.iter().find(|p| p.model == model)
+    }
+
+    pub fn defaults() -> Vec<ModelPrice> {
+        vec![
+            ModelPrice::new("gpt-4o-mini", 0.15, 0.60),
+            ModelPrice::new("gpt-4o", 2.50, 10.00),
+            ModelPrice::new("claude-3-5-haiku-latest", 0.80, 4.00),
+            ModelPrice::new("claude-3-5-sonnet-latest", 3.00, 15.00),
+        ]
+    }
+}
+
+/// Meaning of one server-sent event in a streamed completion.
+pub enum StreamEvent {
+    Delta(String),
+    Usage(Usage),
+    Done,
+    Ignore,
+}
+
+/// Translates between `CompletionRequest` and a provider's HTTP API.
+/// `AIClient` owns the HTTP client, status handling and error context.
+pub trait LlmProvider: Send + Sync {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: Option<&str>,
+        request: &CompletionRequest,
+    ) -> reqwest::RequestBuilder;
+
+    fn parse_response(&self, body: &str) -> Result<Completion>;
+
+    fn parse_stream_event(&self, event: &SseEvent) -> Result<StreamEvent>;
+}
+
+/// Where completions come from: an HTTP API, or a local binary.
+enum Backend {
+    Http(Box<dyn LlmProvider>),
+    LlamaCli(LlamaCli),
+}
+
+fn backend_for(config: &ProviderConfig, timeout: Duration) -> Backend {
+    match config.kind {
+        ProviderKind::OpenAiCompatible => {
+            Backend::Http(Box::new(OpenAiCompatible::new(&config.base_url, config.stream_usage)))
+        }
+        ProviderKind::Anthropic => Backend::Http(Box::new(AnthropicMessages::new(&config.base_url))),
+        ProviderKind::LlamaCli => Backend::LlamaCli(LlamaCli::new(config, timeout)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AIConfig {
+    pub provider: ProviderConfig,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    pub temperature: f32,
+    /// Connect timeout, and the longest we wait for the next bytes of a
+    /// response. For `llama-cli` it bounds the whole run.
+    pub timeout: Duration,
+    /// Extra attempts after a 429, a 5xx or a network error.
+    pub max_retries: u32,
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+pub struct AIClient {
+    client: reqwest::Client,
+    backend: Backend,
+    config: AIConfig,
+}
+
+impl AIClient {
+    pub fn new(config: AIConfig) -> Self {
+        // A read timeout rather than a total one, so long streamed responses
+        // are fine as long as tokens keep arriving.
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.timeout)
+            .read_timeout(config.timeout)
+            .build()
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to build AI HTTP client with timeout: {}", e);
+                reqwest::Client::new()
+            });
+
+        Self {
+            client,
+            backend: backend_for(&config.provider, config.timeout),
+            config,
+        }
+    }
+
+    fn request<'a>(&'a self, text: &'a str, stream: bool) -> CompletionRequest<'a> {
+        CompletionRequest {
+            model: &self.config.model,
+            system_prompt: self
+                .config
+                .system_prompt
+                .as_deref()
+                .unwrap_or(DEFAULT_SYSTEM_PROMPT),
+            text,
+            temperature: self.config.temperature,
+            stream,
+        }
+    }
+
+    pub async fn process_text(&self, text: &str) -> Result<Completion> {
+        let provider = match &self.backend {
+            Backend::Http(provider) => provider.as_ref(),
+            Backend::LlamaCli(cli) => return cli.complete(&self.request(text, false)).await,
+        };
+
+        let response = self
+            .send_with_retries(provider, &self.request(text, false))
+            .await?;
+        let body = response
+            .text()
+            .await
+            .context("Failed to read AI response")?;
+
+        provider
+            .parse_response(&body)
+            .context("Failed to parse AI response")
+    }
+
+    /// Like [`AIClient::process_text`] but requests a server-sent event stream
+    /// and calls `on_delta` with each piece of text as it arrives. Returns the
+    /// full text, or an error if the stream ends without the provider's
+    /// terminal event. Retries only cover getting the stream started.
+    /// `llama-cli` output arrives as a single delta once the run finishes.
+    pub async fn process_text_streaming(
+        &self,
+        text: &str,
+        mut on_delta: impl FnMut(&str) + Send,
+    ) -> Result<Completion> {
+        let provider = match &self.backend {
+            Backend::Http(provider) => provider.as_ref(),
+            Backend::LlamaCli(cli) => {
+                let completion = cli.complete(&self.request(text, false)).await?;
+                on_delta(&completion.text);
+                return Ok(completion);
+            }
+        };
+
+        let mut response = self
+            .send_with_retries(provider, &self.request(text, true))
+            .await?;
+
+        let mut parser = SseParser::new();
+        let mut output = String::new();
+        let mut usage: Option<Usage> = None;
+
+        loop {
+            let chunk = response
+                .chunk()
+                .await
+                .context("AI response stream interrupted")?;
+            let finished = chunk.is_none();
+            let events = match chunk {
+                Some(bytes) => parser.feed(&bytes),
+                None => parser.finish().into_iter().collect(),
+            };
+
+            for event in events {
+                match provider.parse_stream_event(&event)? {
+                    StreamEvent::Delta(delta) => {
+                        on_delta(&delta);
+                        output.push_str(&delta);
+                    }
+                    StreamEvent::Usage(reported) => {
+                        usage = Some(usage.unwrap_or_default().merge(reported));
+                    }
+                    StreamEvent::Done => {
+                        return Ok(Completion {
+                            text: output,
+                            usage,
+                        })
+                    }
+                    StreamEvent::Ignore => {}
+                }
+            }
+
+            if finished {
+                break;
+            }
+        }
+
+        // Without a terminal event the text may be cut short; better to
+        // fall back to the transcript than to paste half a rewrite.
+        if output.is_empty() {
+            Err(anyhow!("No response from AI"))
+        } else {
+            Err(anyhow!("AI response stream ended before the completion was finished"))
+        }
+    }
+
+    async fn send_with_retries(
+        &self,
+        provider: &dyn LlmProvider,
+        request: &CompletionRequest<'_>,
+    ) -> Result<reqwest::Response> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let retry_after = match self.send_once(provider, request).await {
+                Ok(response) => return Ok(response),
+                Err(AttemptError::Fatal(e)) => return Err(e),
+                Err(AttemptError::Retryable(e, retry_after)) => {
+                    if attempt >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    log::warn!(
+                        "AI request attempt {} failed, retrying: {}",
+                        attempt + 1,
+                        e
+                    );
+                    retry_after
+                }
+            };
+
+            tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+
+    async fn send_once(
+        &self,
+        provider: &dyn LlmProvider,
+        request: &CompletionRequest<'_>,
+    ) -> Result<reqwest::Response, AttemptError> {
+        let response = provider
+            .build_request(&self.client, self.config.api_key.as_deref(), request)
+            .send()
+            .await
+            .map_err(|e| {
+                let retryable = e.is_timeout() || e.is_connect();
+                let error = anyhow!(e).context("Failed to send AI processing request");
+                if retryable {
+                    AttemptError::Retryable(error, None)
+                } else {
+                    AttemptError::Fatal(error)
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(|secs| Duration::from_secs(secs).min(MAX_BACKOFF));
+        let body = response.text().await.unwrap_or_default();
+        let error = anyhow!(
+            "{} API error ({}): {}",
+            self.config.provider.name,
+            status,
+            body
+        );
+
+        Err(
+            if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                AttemptError::Retryable(error, retry_after)
+            } else {
+                AttemptError::Fatal(error)
+            },
+        )
+    }
+}
+
+enum AttemptError {
+    /// Worth another try, optionally after the server's `Retry-After`.
+    Retryable(anyhow::Error, Option<Duration>),
+    Fatal(anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A canned reply; the body is written in `chunks`, with a pause
+    /// between them, and ends when the connection closes.
+    struct MockResponse {
+        status: u16,
+        headers: Vec<(&'static str, &'static str)>,
+        chunks: Vec<Vec<u8>>,
+    }
+
+    impl MockResponse {
+        fn json(status: u16, body: serde_json::Value) -> Self {
+            Self {
+                status,
+                headers: vec![("content-type", "application/json")],
+                chunks: vec![body.to_string().into_bytes()],
+            }
+        }
+
+        fn header(mut self, name: &'static str, value: &'static str) -> Self {
+            self.headers.push((name, value));
+            self
+        }
+    }
+
+    #[derive(Debug)]
+    struct RecordedRequest {
+        path: String,
+        headers: Vec<(String, String)>,
+        body: serde_json::Value,
+    }
+
+    impl RecordedRequest {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// Serves `responses` to successive connections on a local port and
+    /// records the requests. Returns the base URL.
+    async fn mock_server(
+        responses: Vec<MockResponse>,
+    ) -> (String, Arc<Mutex<Vec<RecordedRequest>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let log = recorded.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut socket).await;
+                log.lock().unwrap().push(request);
+
+                let mut head = format!("HTTP/1.1 {} Mock\r\nconnection: close\r\n", response.status);
+                for (name, value) in &response.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str("\r\n");
+                socket.write_all(head.as_bytes()).await.unwrap();
+                for chunk in &response.chunks {
+                    socket.write_all(chunk).await.unwrap();
+                    socket.flush().await.unwrap();
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+                let _ = socket.shutdown().await;
+            }
+        });
+        (base_url, recorded)
+    }
+
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> RecordedRequest {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let header_end = loop {
+            let read = socket.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..read]);
+            if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+        let mut lines = head.lines();
+        let path = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or_default()
+            .to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+        let length: usize = headers
+            .iter()
+            .find(|(key, _)| key == "content-length")
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0);
+        while data.len() < header_end + length {
+            let read = socket.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..read]);
+        }
+        RecordedRequest {
+            path,
+            headers,
+            body: serde_json::from_slice(&data[header_end..header_end + length])
+                .unwrap_or(serde_json::Value::Null),
+        }
+    }
+
+    fn client(kind: ProviderKind, base_url: &str, max_retries: u32) -> AIClient {
+        client_for(ProviderConfig::new("mock", "Mock", kind, base_url, true), max_retries)
+    }
+
+    fn client_for(provider: ProviderConfig, max_retries: u32) -> AIClient {
+        AIClient::new(AIConfig {
+            provider,
+            api_key: Some("secret".to_string()),
+            model: "test-model".to_string(),
+            system_prompt: Some("Be brief.".to_string()),
+            temperature: 0.2,
+            timeout: Duration::from_secs(5),
+            max_retries,
+        })
+    }
+
+    fn openai_reply(text: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            serde_json::json!({
+                "choices": [{ "message": { "role": "assistant", "content": text } }],
+                "usage": { "prompt_tokens": 12, "completion_tokens": 3 },
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_completion() {
+        let (base_url, requests) = mock_server(vec![openai_reply("Hello.")]).await;
+        let completion = client(ProviderKind::OpenAiCompatible, &base_url, 0)
+            .process_text("hello")
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Hello.");
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 12,
+                completion_tokens: 3
+            })
+        );
+        let requests = requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert_eq!(request.body["model"], "test-model");
+        assert_eq!(request.body["messages"][0]["role"], "system");
+        assert_eq!(request.body["messages"][0]["content"], "Be brief.");
+        assert_eq!(request.body["messages"][1]["content"], "hello");
+        assert!(request.body.get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn anthropic_completion() {
+        let reply = MockResponse::json(
+            200,
+            serde_json::json!({
+                "content": [
+                    { "type": "text", "text": "Hello" },
+                    { "type": "text", "text": " there." },
+                ],
+                "usage": { "input_tokens": 20, "output_tokens": 4 },
+            }),
+        );
+        let (base_url, requests) = mock_server(vec![reply]).await;
+        let completion = client(ProviderKind::Anthropic, &base_url, 0)
+            .process_text("hello there")
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Hello there.");
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 20,
+                completion_tokens: 4
+            })
+        );
+        let requests = requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(request.path, "/v1/messages");
+        assert_eq!(request.header("x-api-key"), Some("secret"));
+        assert_eq!(request.header("anthropic-version"), Some("2023-06-01"));
+        assert_eq!(request.body["system"], "Be brief.");
+        assert_eq!(request.body["messages"][0]["role"], "user");
+        assert_eq!(request.body["messages"][0]["content"], "hello there");
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let error = MockResponse::json(401, serde_json::json!({ "error": "bad key" }));
+        let (base_url, requests) = mock_server(vec![error, openai_reply("unused")]).await;
+        let err = client(ProviderKind::OpenAiCompatible, &base_url, 2)
+            .process_text("hello")
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("Mock API error (401 Unauthorized)"), "{}", err);
+        assert!(err.contains("bad key"), "{}", err);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_and_server_errors_are_retried() {
+        let responses = vec![
+            MockResponse::json(429, serde_json::json!({ "error": "slow down" }))
+                .header("retry-after", "0"),
+            MockResponse::json(503, serde_json::json!({ "error": "overloaded" })),
+            openai_reply("Third time."),
+        ];
+        let (base_url, requests) = mock_server(responses).await;
+        let completion = client(ProviderKind::OpenAiCompatible, &base_url, 2)
+            .process_text("hello")
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Third time.");
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_give_up_with_the_last_error() {
+        let responses = vec![
+            MockResponse::json(500, serde_json::json!({ "error": "first" }))
+                .header("retry-after", "0"),
+            MockResponse::json(502, serde_json::json!({ "error": "second" })),
+        ];
+        let (base_url, requests) = mock_server(responses).await;
+        let err = client(ProviderKind::Anthropic, &base_url, 1)
+            .process_text("hello")
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("502 Bad Gateway"), "{}", err);
+        assert!(err.contains("second"), "{}", err);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    /// An event stream cut into pieces at the given byte offsets.
+    fn sse_reply(body: &str, cuts: &[usize]) -> MockResponse {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for &cut in cuts.iter().chain([&body.len()]) {
+            chunks.push(body.as_bytes()[start..cut].to_vec());
+            start = cut;
+        }
+        MockResponse {
+            status: 200,
+            headers: vec![("content-type", "text/event-stream")],
+            chunks,
+        }
+    }
+
+    fn stream_events(provider: &dyn LlmProvider, stream: &str) -> Vec<StreamEvent> {
+        let mut parser = SseParser::new();
+        parser
+            .feed(stream.as_bytes())
+            .iter()
+            .map(|event| provider.parse_stream_event(event).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn openai_stream_events() {
+        let events = stream_events(
+            &OpenAiCompatible::new("http://localhost", false),
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+             data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":1}}\n\n\
+             data: [DONE]\n\n",
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::Ignore,
+                StreamEvent::Delta(text),
+                StreamEvent::Usage(Usage { prompt_tokens: 5, completion_tokens: 1 }),
+                StreamEvent::Done,
+            ] if text == "Hi"
+        ));
+    }
+
+    #[test]
+    fn anthropic_stream_events() {
+        let events = stream_events(
+            &AnthropicMessages::new("http://localhost"),
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":9}}}\n\n\
+             event: ping\ndata: {\"type\":\"ping\"}\n\n\
+             event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n\
+             event: message_delta\ndata: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":2}}\n\n\
+             event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [
+                StreamEvent::Usage(Usage { prompt_tokens: 9, completion_tokens: 0 }),
+                StreamEvent::Ignore,
+                StreamEvent::Delta(text),
+                StreamEvent::Usage(Usage { prompt_tokens: 0, completion_tokens: 2 }),
+                StreamEvent::Done,
+            ] if text == "Hi"
+        ));
+    }
+
+    #[test]
+    fn stream_errors_are_reported() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\"}}\n\n");
+        let err = AnthropicMessages::new("http://localhost")
+            .parse_stream_event(&events[0])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("overloaded_error"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn streams_deltas_from_a_local_server() {
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Caf\"}}]}\n\n\
+                    data: {\"choices\":[{\"delta\":{\"content\":\"é ok\"}}]}\n\n\
+                    : keep-alive\n\n\
+                    data: {\"choices\":[],\"usage\":{\"prompt_tokens\":7,\"completion_tokens\":2}}\n\n\
+                    data: [DONE]\n\n";
+        // Cut mid-line and inside the two bytes of "é"
+        let cuts = [10, body.find('é').unwrap() + 1];
+        let (base_url, requests) = mock_server(vec![sse_reply(body, &cuts)]).await;
+
+        let provider = ProviderConfig {
+            stream_usage: true,
+            ..ProviderConfig::new("mock", "Mock", ProviderKind::OpenAiCompatible, &base_url, true)
+        };
+        let mut deltas = Vec::new();
+        let completion = client_for(provider, 0)
+            .process_text_streaming("cafe ok", |delta| deltas.push(delta.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(deltas, ["Caf", "é ok"]);
+        assert_eq!(completion.text, "Café ok");
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 7,
+                completion_tokens: 2
+            })
+        );
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].body["stream"], true);
+        assert_eq!(requests[0].body["stream_options"]["include_usage"], true);
+    }
+
+    #[tokio::test]
+    async fn usage_is_only_requested_where_enabled() {
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\n";
+        let (base_url, requests) = mock_server(vec![sse_reply(body, &[])]).await;
+
+        let completion = client(ProviderKind::OpenAiCompatible, &base_url, 0)
+            .process_text_streaming("hi", |_| {})
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "Hi");
+        assert_eq!(completion.usage, None);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].body["stream"], true);
+        assert!(requests[0].body.get("stream_options").is_none());
+    }
+
+    #[tokio::test]
+    async fn stream_without_terminal_event_is_an_error() {
+        let body = "event: content_block_delta\n\
+                    data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Cut short\"}}";
+        let (base_url, _) = mock_server(vec![sse_reply(body, &[])]).await;
+
+        let mut deltas = String::new();
+        let err = client(ProviderKind::Anthropic, &base_url, 0)
+            .process_text_streaming("cut short", |delta| deltas.push_str(delta))
+            .await
+            .unwrap_err();
+        assert_eq!(deltas, "Cut short");
+        assert!(err.to_string().contains("ended before"), "{}", err);
+
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Cut\"}}]}\n\n";
+        let (base_url, _) = mock_server(vec![sse_reply(body, &[])]).await;
+        assert!(client(ProviderKind::OpenAiCompatible, &base_url, 0)
+            .process_text_streaming("cut", |_| {})
+            .await
+            .is_err());
+    }
+}
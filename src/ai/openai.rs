@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{sse::SseEvent, Completion, CompletionRequest, LlmProvider, StreamEvent, Usage};
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<ChatUsage> for Usage {
+    fn from(usage: ChatUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    // Only on the final chunk, and only when `include_usage` was requested
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Delta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// OpenAI chat completions, also spoken by Ollama, LM Studio and most gateways.
+pub struct OpenAiCompatible {
+    endpoint: String,
+    stream_usage: bool,
+}
+
+impl OpenAiCompatible {
+    pub fn new(base_url: &str, stream_usage: bool) -> Self {
+        Self {
+            endpoint: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            stream_usage,
+        }
+    }
+}
+
+impl LlmProvider for OpenAiCompatible {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: Option<&str>,
+        request: &CompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        let body = ChatRequest {
+            model: request.model,
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: request.system_prompt,
+                },
+                ChatMessage {
+                    role: "user",
+                    content: request.text,
+                },
+            ],
+            temperature: request.temperature,
+            stream: request.stream,
+            stream_options: (request.stream && self.stream_usage).then_some(StreamOptions {
+                include_usage: true,
+            }),
+        };
+
+        let builder = client.post(&self.endpoint).json(&body);
+        match api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+
+    fn parse_response(&self, body: &str) -> Result<Completion> {
+        let response: ChatResponse = serde_json::from_str(body)?;
+        let text = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content)
+            .context("No response from AI")?;
+
+        Ok(Completion {
+            text,
+            usage: response.usage.map(Usage::from),
+        })
+    }
+
+    fn parse_stream_event(&self, event: &SseEvent) -> Result<StreamEvent> {
+        if event.data.trim() == "[DONE]" {
+            return Ok(StreamEvent::Done);
+        }
+
+        let value: serde_json::Value = serde_json::from_str(&event.data)
+            .context("Failed to parse AI stream event")?;
+        if let Some(error) = value.get("error") {
+            return Err(anyhow!("AI stream error: {}", error));
+        }
+
+        let chunk: ChatChunk = serde_json::from_value(value)?;
+        if let Some(usage) = chunk.usage {
+            return Ok(StreamEvent::Usage(usage.into()));
+        }
+        Ok(chunk
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.delta.content)
+            .filter(|content| !content.is_empty())
+            .map(StreamEvent::Delta)
+            .unwrap_or(StreamEvent::Ignore))
+    }
+}
@@ -0,0 +1,152 @@
+/// A server-sent event: the optional `event:` name and the joined `data:` lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` parser. Feed it raw chunks as they arrive
+/// from the network; complete events come out as soon as their blank-line
+/// terminator has been seen.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        // Chunks can split lines (and UTF-8 sequences), so only consume
+        // complete lines and keep the remainder buffered.
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flushes a trailing event when the stream ends without a blank line.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            if let Some(event) = self.process_line(line.trim_end_matches('\r')) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None; // comment / keep-alive
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {} // id, retry: not needed here
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            self.event = None;
+            return None;
+        }
+        Some(SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.map(str::to_string),
+            data: data.to_string(),
+        }
+    }
+
+    fn feed_all(parser: &mut SseParser, chunks: &[&[u8]]) -> Vec<SseEvent> {
+        chunks.iter().flat_map(|chunk| parser.feed(chunk)).collect()
+    }
+
+    #[test]
+    fn events_end_at_a_blank_line() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"event: ping\ndata: one\n\ndata: two\n\n");
+        assert_eq!(events, [event(Some("ping"), "one"), event(None, "two")]);
+    }
+
+    #[test]
+    fn lines_split_across_chunks() {
+        let mut parser = SseParser::new();
+        let events = feed_all(&mut parser, &[b"da", b"ta: {\"a\":", b"1}\r", b"\n", b"\r\n"]);
+        assert_eq!(events, [event(None, "{\"a\":1}")]);
+    }
+
+    #[test]
+    fn utf8_split_across_chunks() {
+        let bytes = "data: naïve café ✓\n\n".as_bytes();
+        // Split inside the two-byte "ï" and the three-byte "✓"
+        let i = bytes.iter().position(|&b| b == 0xC3).unwrap() + 1;
+        let check = bytes.iter().position(|&b| b == 0xE2).unwrap() + 2;
+        let mut parser = SseParser::new();
+        let events = feed_all(&mut parser, &[&bytes[..i], &bytes[i..check], &bytes[check..]]);
+        assert_eq!(events, [event(None, "naïve café ✓")]);
+    }
+
+    #[test]
+    fn data_lines_are_joined_and_comments_skipped() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b": keep-alive\ndata: first\ndata:second\nid: 7\n\n");
+        assert_eq!(events, [event(None, "first\nsecond")]);
+    }
+
+    #[test]
+    fn done_marker_is_an_ordinary_event() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(b"data: {\"x\":1}\n\ndata: [DONE]\n\n");
+        assert_eq!(events, [event(None, "{\"x\":1}"), event(None, "[DONE]")]);
+    }
+
+    #[test]
+    fn finish_flushes_an_unterminated_event() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: message_stop\ndata: {}").is_empty());
+        assert_eq!(parser.finish(), Some(event(Some("message_stop"), "{}")));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn blank_lines_without_data_dispatch_nothing() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: ping\n\n\n").is_empty());
+        // The unused event name doesn't leak into the next event
+        assert_eq!(parser.feed(b"data: x\n\n"), [event(None, "x")]);
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
-use std::path::PathBuf;
+use hound::{WavReader, WavSpec, WavWriter};
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicU32, Ordering},
     Arc, Mutex,
@@ -165,3 +165,10 @@ impl AudioRecorder {
         Arc::clone(&self.meter)
     }
 }
+
+/// Length of a recording in milliseconds, read from its WAV header.
+pub fn wav_duration_ms(path: &Path) -> Result<i64> {
+    let reader = WavReader::open(path).context("Failed to open recording")?;
+    let sample_rate = reader.spec().sample_rate.max(1);
+    Ok(i64::from(reader.duration()) * 1000 / i64::from(sample_rate))
+}
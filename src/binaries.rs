@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use which::which;
+
+/// Finds an external tool such as `whisper-cli` or `llama-cli`. An override
+/// from settings wins (a path, `~/` allowed, or a bare command name looked up
+/// on `PATH`); otherwise `PATH` is searched, then the usual Homebrew and
+/// system locations, since apps launched from Finder get a minimal `PATH`.
+pub fn resolve_binary(name: &str, cli_override: Option<&str>) -> Result<PathBuf> {
+    if let Some(value) = cli_override {
+        let candidate = expand_home(value.trim());
+        if candidate.exists() {
+            return Ok(candidate);
+        }
+        if !value.contains(std::path::MAIN_SEPARATOR) && !value.contains('/') {
+            if let Ok(found) = which(value) {
+                return Ok(found);
+            }
+        }
+        return Err(anyhow!(
+            "Configured {} path not found: {}",
+            name,
+            candidate.display()
+        ));
+    }
+
+    if let Ok(found) = which(name) {
+        return Ok(found);
+    }
+
+    for dir in ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"] {
+        let candidate = PathBuf::from(dir).join(name);
+        if candidate.exists() {
+            return Ok(candidate);
+        }
+    }
+
+    Err(anyhow!(
+        "Unable to locate {}. Please install it or set the binary path in Settings.",
+        name
+    ))
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        if let Ok(home) = std::env::var("HOME") {
+            return PathBuf::from(home).join(stripped);
+        }
+    } else if path == "~" {
+        if let Ok(home) = std::env::var("HOME") {
+            return PathBuf::from(home);
+        }
+    }
+    PathBuf::from(path)
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Days, NaiveDate, NaiveTime};
+
+use crate::database::{Database, HistoryFilter};
+use crate::export::{self, ExportFormat};
+use crate::services::history::{local_to_utc, HistoryService};
+
+const EXPORT_USAGE: &str = "Usage: convey export [options]
+
+Writes history to a file, or to stdout without --output.
+
+Options:
+  --format <json|csv|markdown|srt|vtt>   Default: from the output extension, else json
+  --output <path>                        File to write
+  --from <YYYY-MM-DD>                    Entries from this day on (local time)
+  --until <YYYY-MM-DD>                   Entries up to and including this day
+  --language <code>                      Only this language
+  --mode <id>                            Only this processing mode
+  --app <name>                           Only dictations into this app
+  --tag <name>                           Only entries with this tag
+  --pinned                               Only pinned entries
+  --ai-processed                         Only entries with AI-processed text";
+
+/// `convey export`: history matching the given filters in one format.
+pub fn export(args: &[String], db_path: &Path) -> Result<()> {
+    let mut format = None;
+    let mut output: Option<PathBuf> = None;
+    let mut filter = HistoryFilter::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| anyhow!("{} needs a value\n\n{}", arg, EXPORT_USAGE))
+        };
+        match arg.as_str() {
+            "--format" => {
+                let name = value()?;
+                format = Some(
+                    ExportFormat::parse(&name)
+                        .ok_or_else(|| anyhow!("Unknown export format '{}'", name))?,
+                );
+            }
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--from" => filter.from = Some(local_to_utc(parse_day(&value()?)?.and_time(NaiveTime::MIN))),
+            "--until" => {
+                let day = parse_day(&value()?)?;
+                let next = day.checked_add_days(Days::new(1)).unwrap_or(day);
+                filter.until = Some(local_to_utc(next.and_time(NaiveTime::MIN)));
+            }
+            "--language" => filter.language = Some(value()?),
+            "--mode" => filter.mode = Some(value()?),
+            "--app" => filter.app = Some(value()?),
+            "--tag" => filter.tag = Some(value()?),
+            "--pinned" => filter.pinned_only = true,
+            "--ai-processed" => filter.ai_processed = Some(true),
+            "-h" | "--help" => {
+                println!("{}", EXPORT_USAGE);
+                return Ok(());
+            }
+            other => bail!("Unknown option '{}'\n\n{}", other, EXPORT_USAGE),
+        }
+    }
+
+    let format = format
+        .or_else(|| {
+            output
+                .as_ref()
+                .and_then(|path| path.extension())
+                .and_then(|ext| ExportFormat::parse(&ext.to_string_lossy()))
+        })
+        .unwrap_or(ExportFormat::Json);
+
+    let history = HistoryService::new(Database::new(db_path.to_path_buf())?);
+    let entries = history.all(&filter)?;
+    let rendered = export::render(&entries, format)?;
+
+    match output {
+        Some(path) => {
+            fs::write(&path, rendered).with_context(|| format!("Failed to write {:?}", path))?;
+            eprintln!("Exported {} entries to {}", entries.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("'{}' is not a date like 2024-03-05", value))
+}
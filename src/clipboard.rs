@@ -1,8 +1,11 @@
 use anyhow::Result;
+use enigo::{Enigo, Keyboard, Settings};
 use std::process::Command;
 use std::thread;
 use std::time::Duration;
 
+use crate::commands::CommandKey;
+
 pub struct ClipboardManager;
 
 impl ClipboardManager {
@@ -66,6 +69,50 @@ impl ClipboardManager {
         }
     }
 
+    /// Types text into the focused app as keystrokes, without touching the
+    /// clipboard. Used for streamed AI output.
+    pub fn type_text(&self, text: &str) -> Result<()> {
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| anyhow::anyhow!("Failed to initialize keyboard input: {}", e))?;
+        enigo
+            .text(text)
+            .map_err(|e| anyhow::anyhow!("Failed to type text: {}", e))
+    }
+
+    pub fn press_key(&self, key: CommandKey) -> Result<()> {
+        use enigo::{Direction, Key};
+
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| anyhow::anyhow!("Failed to initialize keyboard input: {}", e))?;
+        let result = match key {
+            CommandKey::Enter => enigo.key(Key::Return, Direction::Click),
+            CommandKey::Tab => enigo.key(Key::Tab, Direction::Click),
+            CommandKey::SelectAll => {
+                #[cfg(target_os = "macos")]
+                let modifier = Key::Meta;
+                #[cfg(not(target_os = "macos"))]
+                let modifier = Key::Control;
+
+                enigo
+                    .key(modifier, Direction::Press)
+                    .and_then(|()| enigo.key(Key::Unicode('a'), Direction::Click))
+                    .and_then(|()| enigo.key(modifier, Direction::Release))
+            }
+        };
+        result.map_err(|e| anyhow::anyhow!("Failed to press {:?}: {}", key, e))
+    }
+
+    pub fn press_backspace(&self, count: usize) -> Result<()> {
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| anyhow::anyhow!("Failed to initialize keyboard input: {}", e))?;
+        for _ in 0..count {
+            enigo
+                .key(enigo::Key::Backspace, enigo::Direction::Click)
+                .map_err(|e| anyhow::anyhow!("Failed to press Backspace: {}", e))?;
+        }
+        Ok(())
+    }
+
     pub fn paste_text_and_enter(&self, text: &str) -> Result<()> {
         // First, copy to clipboard using arboard
         let mut clipboard = arboard::Clipboard::new()?;
@@ -0,0 +1,575 @@
+use serde::{Deserialize, Serialize};
+
+/// What a spoken command does.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAction {
+    PressEnter,
+    PressTab,
+    NewLine,
+    NewParagraph,
+    SelectAll,
+}
+
+/// Where in a dictation a command is recognized. Consecutive commands at the
+/// very beginning or end all count as start or end; `Alone` means the command
+/// is all that was said.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    Start,
+    End,
+    Inline,
+    Alone,
+}
+
+/// One entry of the grammar file: any of `phrases` triggers `action`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CommandRule {
+    pub phrases: Vec<String>,
+    pub action: CommandAction,
+    #[serde(default = "all_placements")]
+    pub placement: Vec<Placement>,
+    /// Only recognized directly after a connector, so "send it and press
+    /// enter" submits but "press enter to continue" stays text.
+    #[serde(default)]
+    pub after_connector: bool,
+}
+
+fn all_placements() -> Vec<Placement> {
+    vec![Placement::Start, Placement::End, Placement::Inline]
+}
+
+fn default_connectors() -> Vec<String> {
+    vec!["and".to_string(), "then".to_string()]
+}
+
+/// The spoken command grammar, stored as `commands.json` next to the
+/// settings file so users can add phrases and actions.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Grammar {
+    pub rules: Vec<CommandRule>,
+    /// Words swallowed along with a command they directly precede, so
+    /// "send it and press enter" leaves "send it".
+    #[serde(default = "default_connectors")]
+    pub connectors: Vec<String>,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        let rule = |phrases: &[&str], action, placement: &[Placement]| CommandRule {
+            phrases: phrases.iter().map(|p| p.to_string()).collect(),
+            action,
+            placement: placement.to_vec(),
+            after_connector: false,
+        };
+        let edges = [Placement::Start, Placement::End];
+
+        Self {
+            rules: vec![
+                // "press enter" mid-sentence or without "and"/"then" is
+                // usually just talk
+                CommandRule {
+                    after_connector: true,
+                    ..rule(
+                        &["press enter", "hit enter", "press return", "hit return"],
+                        CommandAction::PressEnter,
+                        &[Placement::End],
+                    )
+                },
+                // The plain phrases are ordinary words mid-sentence ("a new
+                // line of products"), so inline needs the explicit forms
+                rule(&["press tab", "hit tab"], CommandAction::PressTab, &edges),
+                rule(&["insert tab"], CommandAction::PressTab, &all_placements()),
+                rule(&["new line", "newline"], CommandAction::NewLine, &edges),
+                rule(
+                    &["insert new line", "insert line break"],
+                    CommandAction::NewLine,
+                    &all_placements(),
+                ),
+                rule(&["new paragraph"], CommandAction::NewParagraph, &edges),
+                rule(
+                    &["insert new paragraph", "insert paragraph break"],
+                    CommandAction::NewParagraph,
+                    &all_placements(),
+                ),
+                // The paste that follows would replace the whole field
+                rule(
+                    &["select all"],
+                    CommandAction::SelectAll,
+                    &[Placement::Alone],
+                ),
+            ],
+            connectors: default_connectors(),
+        }
+    }
+}
+
+/// A key press produced by a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKey {
+    Enter,
+    Tab,
+    SelectAll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Text(String),
+    Key(CommandKey),
+}
+
+/// A dictation split into text to insert and keys to press, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictation {
+    pub steps: Vec<Step>,
+}
+
+impl Dictation {
+    /// A dictation without commands.
+    pub fn plain(text: &str) -> Self {
+        let steps = if text.is_empty() {
+            Vec::new()
+        } else {
+            vec![Step::Text(text.to_string())]
+        };
+        Self { steps }
+    }
+
+    /// All inserted text, which is what gets copied and stored.
+    pub fn text(&self) -> String {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Text(text) => Some(text.as_str()),
+                Step::Key(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn has_keys(&self) -> bool {
+        self.steps.iter().any(|step| matches!(step, Step::Key(_)))
+    }
+
+    pub fn ends_with(&self, key: CommandKey) -> bool {
+        self.steps.last() == Some(&Step::Key(key))
+    }
+
+    /// Keys after the last piece of text.
+    pub fn trailing_keys(&self) -> Vec<CommandKey> {
+        let mut keys: Vec<CommandKey> = self
+            .steps
+            .iter()
+            .rev()
+            .map_while(|step| match step {
+                Step::Key(key) => Some(*key),
+                Step::Text(_) => None,
+            })
+            .collect();
+        keys.reverse();
+        keys
+    }
+
+    pub fn push_key(&mut self, key: CommandKey) {
+        self.steps.push(Step::Key(key));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.steps.last_mut() {
+            Some(Step::Text(existing)) => existing.push_str(text),
+            _ => self.steps.push(Step::Text(text.to_string())),
+        }
+    }
+}
+
+/// A whitespace-separated token of the transcript with its byte span.
+struct Word {
+    start: usize,
+    end: usize,
+    normalized: String,
+}
+
+/// A recognized command covering words `first..last` (connectors included).
+/// `misheard` matches only matched by sound.
+#[derive(Clone, Copy)]
+struct Match {
+    first: usize,
+    last: usize,
+    rule: usize,
+    misheard: bool,
+}
+
+impl Grammar {
+    /// Splits a transcript into text and command steps. Text between
+    /// commands keeps its original punctuation and casing.
+    pub fn parse(&self, text: &str) -> Dictation {
+        let words = tokenize(text);
+        let phrases: Vec<Vec<Vec<String>>> = self
+            .rules
+            .iter()
+            .map(|rule| rule.phrases.iter().map(|p| normalize_phrase(p)).collect())
+            .collect();
+
+        let mut matches = self.find_matches(&words, &phrases);
+        // Dropping a misplaced command turns it back into text, which can
+        // change whether its neighbours are at the start or end.
+        loop {
+            let before = matches.len();
+            let alone =
+                matches.len() == 1 && matches[0].first == 0 && matches[0].last == words.len();
+            let mut positions = positions(&matches, words.len()).into_iter();
+            matches.retain(|m| {
+                let (at_start, at_end) = positions.next().expect("one position per match");
+                let allowed = &self.rules[m.rule].placement;
+                // Matching by sound is only worth the risk at the end
+                if m.misheard {
+                    return at_end && allowed.contains(&Placement::End);
+                }
+                (alone && allowed.contains(&Placement::Alone))
+                    || (at_start && allowed.contains(&Placement::Start))
+                    || (at_end && allowed.contains(&Placement::End))
+                    || (!at_start && !at_end && allowed.contains(&Placement::Inline))
+            });
+            if matches.len() == before {
+                break;
+            }
+        }
+
+        if matches.is_empty() {
+            return Dictation::plain(text);
+        }
+
+        let mut dictation = Dictation { steps: Vec::new() };
+        let mut cursor = 0;
+        for m in &matches {
+            dictation.push_text(text[cursor..words[m.first].start].trim());
+            cursor = words[m.last - 1].end;
+
+            match self.rules[m.rule].action {
+                CommandAction::PressEnter => dictation.push_key(CommandKey::Enter),
+                CommandAction::PressTab => dictation.push_key(CommandKey::Tab),
+                CommandAction::SelectAll => dictation.push_key(CommandKey::SelectAll),
+                CommandAction::NewLine => dictation.push_text("\n"),
+                CommandAction::NewParagraph => dictation.push_text("\n\n"),
+            }
+        }
+        dictation.push_text(text[cursor..].trim());
+
+        dictation
+    }
+
+    /// Leftmost-longest phrase matches, each extended back over connectors.
+    fn find_matches(&self, words: &[Word], phrases: &[Vec<Vec<String>>]) -> Vec<Match> {
+        let mut matches: Vec<Match> = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            let floor = matches.last().map_or(0, |m| m.last);
+            let after_connector = i > floor && self.is_connector(&words[i - 1].normalized);
+            // Longest phrase first, and an exact match over a misheard one
+            let best = phrases
+                .iter()
+                .enumerate()
+                .flat_map(|(rule, alternatives)| alternatives.iter().map(move |p| (rule, p)))
+                .filter(|(rule, phrase)| {
+                    !phrase.is_empty() && (after_connector || !self.rules[*rule].after_connector)
+                })
+                .filter_map(|(rule, phrase)| {
+                    phrase_matches(words, i, phrase).map(|misheard| (rule, phrase.len(), misheard))
+                })
+                .max_by_key(|(_, len, misheard)| (*len, !misheard));
+
+            match best {
+                Some((rule, len, misheard)) => {
+                    let mut first = i;
+                    while first > floor && self.is_connector(&words[first - 1].normalized) {
+                        first -= 1;
+                    }
+                    matches.push(Match {
+                        first,
+                        last: i + len,
+                        rule,
+                        misheard,
+                    });
+                    i += len;
+                }
+                None => i += 1,
+            }
+        }
+        matches
+    }
+
+    fn is_connector(&self, word: &str) -> bool {
+        self.connectors.iter().any(|c| c.eq_ignore_ascii_case(word))
+    }
+}
+
+/// Whether each match sits in the run of commands at the very start and/or
+/// the very end of the dictation. A dictation of nothing but commands is both.
+fn positions(matches: &[Match], word_count: usize) -> Vec<(bool, bool)> {
+    let mut leading_end = 0;
+    for m in matches {
+        if m.first != leading_end {
+            break;
+        }
+        leading_end = m.last;
+    }
+    let mut trailing_start = word_count;
+    for m in matches.iter().rev() {
+        if m.last != trailing_start {
+            break;
+        }
+        trailing_start = m.first;
+    }
+
+    matches
+        .iter()
+        .map(|m| (m.last <= leading_end, m.first >= trailing_start))
+        .collect()
+}
+
+fn tokenize(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                let normalized = normalize_word(&text[s..i]);
+                if !normalized.is_empty() {
+                    words.push(Word {
+                        start: s,
+                        end: i,
+                        normalized,
+                    });
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn normalize_phrase(phrase: &str) -> Vec<String> {
+    phrase
+        .split_whitespace()
+        .map(normalize_word)
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// `Some(misheard)` if the phrase starts at word `at`, where `misheard` says
+/// some word only matched by sound.
+fn phrase_matches(words: &[Word], at: usize, phrase: &[String]) -> Option<bool> {
+    if words.len() < at + phrase.len() {
+        return None;
+    }
+    let mut misheard = false;
+    for (word, expected) in words[at..].iter().zip(phrase) {
+        if word.normalized != *expected {
+            if !sounds_like(&word.normalized, expected) {
+                return None;
+            }
+            misheard = true;
+        }
+    }
+    Some(misheard)
+}
+
+/// Whisper mishears command words ("press inter", "and prez enter"), so
+/// compare by sound rather than spelling. Short words must match exactly;
+/// "new" sounding like "no" is too loose. Only equal sounds count: a word with
+/// an extra syllable is usually a different real word ("pressed",
+/// "present", "entered").
+fn sounds_like(spoken: &str, expected: &str) -> bool {
+    expected.chars().count() > 3 && phonetic_key(spoken) == phonetic_key(expected)
+}
+
+/// Consonant skeleton in the spirit of Soundex: letters that sound alike
+/// share a class, vowels and h/w/y are dropped after the first letter, and
+/// repeats collapse. "press" -> "brk", "enter" and "inter" -> "antr".
+fn phonetic_key(word: &str) -> String {
+    let mut key = String::new();
+    for (i, c) in word.chars().enumerate() {
+        let class = match c {
+            'b' | 'f' | 'p' | 'v' => Some('b'),
+            'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some('k'),
+            'd' | 't' => Some('t'),
+            'l' => Some('l'),
+            'm' | 'n' => Some('n'),
+            'r' => Some('r'),
+            '0'..='9' => Some(c),
+            _ if i == 0 => Some('a'),
+            _ => None,
+        };
+        if let Some(class) = class {
+            if !key.ends_with(class) {
+                key.push(class);
+            }
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Step {
+        Step::Text(value.to_string())
+    }
+
+    #[test]
+    fn parses_commands_in_the_default_grammar() {
+        let grammar = Grammar::default();
+        let cases: &[(&str, Vec<Step>)] = &[
+            (
+                "Send it and press enter",
+                vec![text("Send it"), Step::Key(CommandKey::Enter)],
+            ),
+            (
+                "Send it, then hit return.",
+                vec![text("Send it,"), Step::Key(CommandKey::Enter)],
+            ),
+            (
+                "Send it and press inter",
+                vec![text("Send it"), Step::Key(CommandKey::Enter)],
+            ),
+            (
+                "Send it, then prez enter.",
+                vec![text("Send it,"), Step::Key(CommandKey::Enter)],
+            ),
+            (
+                "Name press tab",
+                vec![text("Name"), Step::Key(CommandKey::Tab)],
+            ),
+            (
+                "press tab Name",
+                vec![Step::Key(CommandKey::Tab), text("Name")],
+            ),
+            (
+                "Name insert tab Smith",
+                vec![text("Name"), Step::Key(CommandKey::Tab), text("Smith")],
+            ),
+            ("first insert new line second", vec![text("first\nsecond")]),
+            (
+                "Dear Bob, insert new paragraph. Thanks",
+                vec![text("Dear Bob,\n\nThanks")],
+            ),
+            ("Thanks, new paragraph.", vec![text("Thanks,\n\n")]),
+            ("New line. Second part", vec![text("\nSecond part")]),
+            ("Select all.", vec![Step::Key(CommandKey::SelectAll)]),
+            (
+                "Hello new line and press enter",
+                vec![text("Hello\n"), Step::Key(CommandKey::Enter)],
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(&grammar.parse(input).steps, expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn leaves_ordinary_sentences_alone() {
+        let grammar = Grammar::default();
+        let sentences = [
+            "Collect all the receipts by Friday.",
+            "Selected all the files.",
+            "Please select all the files.",
+            "select all of them",
+            "Select all and replace it.",
+            "I need a new loan for the house.",
+            "No line is too long.",
+            "A new parent joined the group.",
+            "We added a new line of products.",
+            "Start a new paragraph after the quote.",
+            "You need to press tab twice.",
+            "Press enter to continue.",
+            "To submit just press enter.",
+            "I'll press enter later, once it's ready.",
+            "Then I pressed enter.",
+            "I was tired and pressed enter.",
+            "Send it and present enter.",
+            "Send it, president enter.",
+            "The president entered the room.",
+            "Presents, enter the room.",
+            "Press inter the data, please.",
+            "And press center",
+            "Then hit enters",
+            "",
+        ];
+        for sentence in sentences {
+            assert_eq!(
+                grammar.parse(sentence),
+                Dictation::plain(sentence),
+                "input: {:?}",
+                sentence
+            );
+        }
+    }
+
+    #[test]
+    fn misheard_words_only_count_at_the_end() {
+        let mut grammar = Grammar::default();
+        grammar.rules.push(CommandRule {
+            phrases: vec!["press space".to_string()],
+            action: CommandAction::PressTab,
+            placement: all_placements(),
+            after_connector: false,
+        });
+        assert_eq!(
+            grammar.parse("Name prez space").steps,
+            vec![text("Name"), Step::Key(CommandKey::Tab)]
+        );
+        assert_eq!(
+            grammar.parse("prez space Name").steps,
+            vec![text("prez space Name")]
+        );
+        assert_eq!(
+            grammar.parse("Name prez space Smith").steps,
+            vec![text("Name prez space Smith")]
+        );
+        assert_eq!(
+            grammar.parse("Name press space Smith").steps,
+            vec![text("Name"), Step::Key(CommandKey::Tab), text("Smith")]
+        );
+    }
+
+    #[test]
+    fn phonetic_keys() {
+        assert_eq!(phonetic_key("press"), "brk");
+        assert_eq!(phonetic_key("prez"), "brk");
+        assert_eq!(phonetic_key("enter"), "antr");
+        assert_eq!(phonetic_key("inter"), "antr");
+        assert!(!sounds_like("pressed", "press"));
+        assert!(!sounds_like("present", "press"));
+        assert!(!sounds_like("entered", "enter"));
+        assert!(!sounds_like("no", "new"));
+    }
+
+    #[test]
+    fn trailing_keys_follow_the_last_text() {
+        let dictation = Grammar::default().parse("press tab Hello press tab and press enter");
+        assert_eq!(dictation.text(), "Hello");
+        assert_eq!(dictation.steps[0], Step::Key(CommandKey::Tab));
+        assert!(dictation.has_keys());
+        assert!(dictation.ends_with(CommandKey::Enter));
+        assert_eq!(
+            dictation.trailing_keys(),
+            vec![CommandKey::Tab, CommandKey::Enter]
+        );
+    }
+}
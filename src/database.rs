@@ -1,17 +1,279 @@
 use anyhow::{Context, Result};
-use chrono::Utc;
-use rusqlite::{params, Connection};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::migrations;
+use crate::replacements::{AppliedReplacement, Replacement};
+use crate::whisper::Segment;
+
+/// State of AI post-processing for an entry. `None` on the row means AI
+/// processing was not requested.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AiStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+impl AiStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiStatus::Pending => "pending",
+            AiStatus::Done => "done",
+            AiStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(AiStatus::Pending),
+            "done" => Some(AiStatus::Done),
+            "failed" => Some(AiStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transcription {
     pub id: i64,
+    /// The transcript after local processing, as it was given to AI.
     pub text: String,
     pub processed_text: Option<String>,
+    /// The user's latest revision of the text, if they edited it since it
+    /// was last produced.
+    pub edited_text: Option<String>,
     pub language: Option<String>,
     pub duration_ms: Option<i64>,
     pub created_at: String,
+    pub avg_confidence: Option<f32>,
+    pub segments: Vec<Segment>,
+    pub mode: Option<String>,
+    pub ai_status: Option<AiStatus>,
+    pub ai_error: Option<String>,
+    /// Application the text was dictated into.
+    pub app: Option<String>,
+    /// Replacement rules that changed the transcript.
+    pub applied_replacements: Vec<AppliedReplacement>,
+    /// Whisper's own words, when local cleanup, formatting or replacements
+    /// changed them before they were stored in `text`.
+    pub pre_cleanup_text: Option<String>,
+    pub whisper_model: Option<String>,
+    pub timings: Timings,
+    /// Kept by retention cleanup.
+    pub pinned: bool,
+    pub tags: Vec<String>,
+}
+
+impl Transcription {
+    /// The text Convey produced, before any edits.
+    pub fn produced_text(&self) -> &str {
+        self.processed_text.as_deref().unwrap_or(&self.text)
+    }
+
+    /// What copying or rewriting the entry uses: the latest revision, else
+    /// the produced text.
+    pub fn current_text(&self) -> &str {
+        self.edited_text.as_deref().unwrap_or_else(|| self.produced_text())
+    }
+}
+
+/// A saved edit of an entry's text.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub text: String,
+    pub created_at: String,
+}
+
+/// How long each stage of the pipeline took for an entry, in milliseconds.
+/// Stages that didn't run, or entries from before timings were recorded,
+/// have `None`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub whisper_ms: Option<i64>,
+    pub ai_ms: Option<i64>,
+    pub paste_ms: Option<i64>,
+    /// From releasing the hotkey until the text was in the target app (or
+    /// on the clipboard).
+    pub total_ms: Option<i64>,
+}
+
+/// An entry's timings with what they depend on, for the stats view.
+#[derive(Debug, Clone)]
+pub struct TimingSample {
+    pub created_at: String,
+    pub duration_ms: Option<i64>,
+    pub whisper_model: Option<String>,
+    /// Model of the entry's latest AI call, if usage was recorded for it.
+    pub ai_model: Option<String>,
+    pub timings: Timings,
+}
+
+/// Values for a new history row; `id` and `created_at` are assigned on insert.
+#[derive(Debug, Default, Clone)]
+pub struct NewTranscription {
+    pub text: String,
+    pub processed_text: Option<String>,
+    pub language: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub avg_confidence: Option<f32>,
+    pub segments: Vec<Segment>,
+    pub mode: Option<String>,
+    pub ai_status: Option<AiStatus>,
+    pub app: Option<String>,
+    pub applied_replacements: Vec<AppliedReplacement>,
+    pub pre_cleanup_text: Option<String>,
+    pub whisper_model: Option<String>,
+}
+
+/// Token usage of one AI call, recorded separately from the entry so totals
+/// survive deleting history.
+#[derive(Debug, Clone)]
+pub struct NewAiUsage<'a> {
+    pub transcription_id: Option<i64>,
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: Option<f64>,
+}
+
+/// Summed usage over a time range. Calls to unpriced models count towards the
+/// tokens but not the cost.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct UsageTotals {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Narrows a history query; `None` fields match everything.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HistoryFilter {
+    /// Entries created at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Entries created before this time.
+    pub until: Option<DateTime<Utc>>,
+    pub language: Option<String>,
+    pub mode: Option<String>,
+    pub app: Option<String>,
+    pub min_duration_ms: Option<i64>,
+    /// Only entries with (`true`) or without (`false`) AI-processed text.
+    pub ai_processed: Option<bool>,
+    /// Only entries with this tag (ignoring case).
+    pub tag: Option<String>,
+    pub pinned_only: bool,
+}
+
+/// Where the next page of a history query starts: just after the last entry
+/// of the previous page. Unlike an offset it stays correct while new
+/// dictations are added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryCursor {
+    created_at: String,
+    id: i64,
+}
+
+/// One page of history, newest first. `next` is `None` on the last page.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPage {
+    pub entries: Vec<Transcription>,
+    pub next: Option<HistoryCursor>,
+}
+
+/// A full-text search result. Matched terms in `snippet` are wrapped in
+/// `HIGHLIGHT_START` and `HIGHLIGHT_END`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub transcription: Transcription,
+    pub snippet: String,
+}
+
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+impl SearchHit {
+    /// The snippet's words, each flagged if it is (part of) a match.
+    pub fn snippet_words(&self) -> Vec<(String, bool)> {
+        let mut in_match = false;
+        self.snippet
+            .split_whitespace()
+            .map(|word| {
+                let mut highlighted = in_match;
+                for c in word.chars() {
+                    match c {
+                        HIGHLIGHT_START => {
+                            in_match = true;
+                            highlighted = true;
+                        }
+                        HIGHLIGHT_END => in_match = false,
+                        _ => {}
+                    }
+                }
+                (word.replace([HIGHLIGHT_START, HIGHLIGHT_END], ""), highlighted)
+            })
+            .collect()
+    }
+}
+
+const TRANSCRIPTION_COLUMNS: &str =
+    "id, text, processed_text, language, duration_ms, created_at, avg_confidence, segments, mode, \
+     ai_status, ai_error, app, applied_replacements, pre_cleanup_text, whisper_model, whisper_ms, \
+     ai_ms, paste_ms, total_ms, pinned, \
+     (SELECT group_concat(name, char(31)) FROM transcription_tags \
+      JOIN tags ON tags.id = transcription_tags.tag_id \
+      WHERE transcription_tags.transcription_id = transcriptions.id), \
+     edited_text";
+
+/// Separates tag names in the aggregated tags column.
+const TAG_SEPARATOR: char = '\u{1f}';
+
+fn transcription_from_row(row: &Row) -> rusqlite::Result<Transcription> {
+    let segments: Option<String> = row.get(7)?;
+    Ok(Transcription {
+        id: row.get(0)?,
+        text: row.get(1)?,
+        processed_text: row.get(2)?,
+        language: row.get(3)?,
+        duration_ms: row.get(4)?,
+        created_at: row.get(5)?,
+        avg_confidence: row.get(6)?,
+        segments: segments
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        mode: row.get(8)?,
+        ai_status: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|status| AiStatus::parse(&status)),
+        ai_error: row.get(10)?,
+        app: row.get(11)?,
+        applied_replacements: row
+            .get::<_, Option<String>>(12)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        pre_cleanup_text: row.get(13)?,
+        whisper_model: row.get(14)?,
+        timings: Timings {
+            whisper_ms: row.get(15)?,
+            ai_ms: row.get(16)?,
+            paste_ms: row.get(17)?,
+            total_ms: row.get(18)?,
+        },
+        pinned: row.get(19)?,
+        tags: row
+            .get::<_, Option<String>>(20)?
+            .map(|tags| {
+                let mut tags: Vec<String> = tags.split(TAG_SEPARATOR).map(str::to_string).collect();
+                tags.sort_by_key(|tag| tag.to_lowercase());
+                tags
+            })
+            .unwrap_or_default(),
+        edited_text: row.get(21)?,
+    })
 }
 
 pub struct Database {
@@ -20,89 +282,478 @@ pub struct Database {
 
 impl Database {
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path).context("Failed to open database")?;
+        let mut conn = Connection::open(&db_path).context("Failed to open database")?;
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS transcriptions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                text TEXT NOT NULL,
-                processed_text TEXT,
-                language TEXT,
-                duration_ms INTEGER,
-                created_at TEXT NOT NULL
-            )",
-            [],
-        )?;
+        migrations::migrate(&mut conn, &db_path)?;
 
         Ok(Self { conn })
     }
 
-    pub fn insert_transcription(
-        &self,
-        text: &str,
-        processed_text: Option<&str>,
-        language: Option<&str>,
-        duration_ms: Option<i64>,
-    ) -> Result<i64> {
+    pub fn insert_transcription(&self, entry: &NewTranscription) -> Result<i64> {
         let created_at = Utc::now().to_rfc3339();
+        let segments = if entry.segments.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&entry.segments).context("Failed to encode segments")?)
+        };
+        let applied_replacements = if entry.applied_replacements.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_string(&entry.applied_replacements)
+                    .context("Failed to encode applied replacements")?,
+            )
+        };
 
         self.conn.execute(
-            "INSERT INTO transcriptions (text, processed_text, language, duration_ms, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![text, processed_text, language, duration_ms, created_at],
+            "INSERT INTO transcriptions
+                (text, processed_text, language, duration_ms, created_at, avg_confidence, segments,
+                 mode, ai_status, app, applied_replacements, pre_cleanup_text, whisper_model)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                entry.text,
+                entry.processed_text,
+                entry.language,
+                entry.duration_ms,
+                created_at,
+                entry.avg_confidence,
+                segments,
+                entry.mode,
+                entry.ai_status.map(AiStatus::as_str),
+                entry.app,
+                applied_replacements,
+                entry.pre_cleanup_text,
+                entry.whisper_model
+            ],
         )?;
 
         Ok(self.conn.last_insert_rowid())
     }
 
     pub fn get_recent_transcriptions(&self, limit: usize) -> Result<Vec<Transcription>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TRANSCRIPTION_COLUMNS}
+             FROM transcriptions
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?1"
+        ))?;
+
+        let transcriptions = stmt
+            .query_map([limit], transcription_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(transcriptions)
+    }
+
+    /// Up to `limit` entries matching `filter`, newest first, starting after
+    /// `after` (or at the newest entry).
+    pub fn query_transcriptions(
+        &self,
+        filter: &HistoryFilter,
+        after: Option<&HistoryCursor>,
+        limit: usize,
+    ) -> Result<HistoryPage> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(from) = filter.from {
+            conditions.push("created_at >= ?");
+            values.push(Value::Text(from.to_rfc3339()));
+        }
+        if let Some(until) = filter.until {
+            conditions.push("created_at < ?");
+            values.push(Value::Text(until.to_rfc3339()));
+        }
+        if let Some(language) = &filter.language {
+            conditions.push("language = ?");
+            values.push(Value::Text(language.clone()));
+        }
+        if let Some(mode) = &filter.mode {
+            conditions.push("mode = ?");
+            values.push(Value::Text(mode.clone()));
+        }
+        if let Some(app) = &filter.app {
+            conditions.push("app = ?");
+            values.push(Value::Text(app.clone()));
+        }
+        if let Some(min_duration_ms) = filter.min_duration_ms {
+            conditions.push("duration_ms >= ?");
+            values.push(Value::Integer(min_duration_ms));
+        }
+        match filter.ai_processed {
+            Some(true) => conditions.push("processed_text IS NOT NULL"),
+            Some(false) => conditions.push("processed_text IS NULL"),
+            None => {}
+        }
+        if let Some(tag) = &filter.tag {
+            conditions.push(
+                "id IN (SELECT transcription_id FROM transcription_tags
+                        JOIN tags ON tags.id = transcription_tags.tag_id
+                        WHERE tags.name = ?)",
+            );
+            values.push(Value::Text(tag.clone()));
+        }
+        if filter.pinned_only {
+            conditions.push("pinned = 1");
+        }
+        if let Some(cursor) = after {
+            conditions.push("(created_at, id) < (?, ?)");
+            values.push(Value::Text(cursor.created_at.clone()));
+            values.push(Value::Integer(cursor.id));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        // One extra row tells whether there is another page
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TRANSCRIPTION_COLUMNS}
+             FROM transcriptions
+             {where_clause}
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?"
+        ))?;
+        let mut entries = stmt
+            .query_map(params_from_iter(values), transcription_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|last| HistoryCursor {
+                created_at: last.created_at.clone(),
+                id: last.id,
+            })
+        } else {
+            None
+        };
+        Ok(HistoryPage { entries, next })
+    }
+
+    pub fn get_transcription(&self, id: i64) -> Result<Option<Transcription>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TRANSCRIPTION_COLUMNS} FROM transcriptions WHERE id = ?1"
+        ))?;
+
+        let mut rows = stmt.query_map([id], transcription_from_row)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Stores a new AI result. On an entry the user has edited, the result
+    /// is also saved as its newest revision so it becomes the current text
+    /// while the edits stay in its versions.
+    pub fn update_processed_text(
+        &mut self,
+        id: i64,
+        processed_text: &str,
+        mode: Option<&str>,
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let edited: bool = tx
+            .query_row(
+                "SELECT edited_text IS NOT NULL
+                     OR EXISTS (SELECT 1 FROM transcription_revisions WHERE transcription_id = ?1)
+                 FROM transcriptions WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(false);
+        tx.execute(
+            "UPDATE transcriptions
+             SET processed_text = ?1, mode = ?2, ai_status = 'done', ai_error = NULL,
+                 edited_text = NULL
+             WHERE id = ?3",
+            params![processed_text, mode, id],
+        )?;
+        if edited {
+            tx.execute(
+                "INSERT INTO transcription_revisions (transcription_id, text, created_at)
+                 VALUES (?1, ?2, ?3)",
+                params![id, processed_text, Utc::now().to_rfc3339()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn record_timings(&self, id: i64, timings: &Timings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE transcriptions
+             SET whisper_ms = ?1, ai_ms = ?2, paste_ms = ?3, total_ms = ?4
+             WHERE id = ?5",
+            params![
+                timings.whisper_ms,
+                timings.ai_ms,
+                timings.paste_ms,
+                timings.total_ms,
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Timings of entries created at or after `since` (RFC 3339), oldest first.
+    pub fn timing_samples(&self, since: &str) -> Result<Vec<TimingSample>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, text, processed_text, language, duration_ms, created_at
+            "SELECT created_at, duration_ms, whisper_model,
+                    (SELECT model FROM ai_usage
+                     WHERE transcription_id = transcriptions.id
+                     ORDER BY id DESC LIMIT 1),
+                    whisper_ms, ai_ms, paste_ms, total_ms
              FROM transcriptions
-             ORDER BY created_at DESC
-             LIMIT ?1",
+             WHERE created_at >= ?1 AND total_ms IS NOT NULL
+             ORDER BY created_at, id",
         )?;
 
-        let transcriptions = stmt
-            .query_map([limit], |row| {
-                Ok(Transcription {
-                    id: row.get(0)?,
-                    text: row.get(1)?,
-                    processed_text: row.get(2)?,
-                    language: row.get(3)?,
-                    duration_ms: row.get(4)?,
-                    created_at: row.get(5)?,
+        let samples = stmt
+            .query_map([since], |row| {
+                Ok(TimingSample {
+                    created_at: row.get(0)?,
+                    duration_ms: row.get(1)?,
+                    whisper_model: row.get(2)?,
+                    ai_model: row.get(3)?,
+                    timings: Timings {
+                        whisper_ms: row.get(4)?,
+                        ai_ms: row.get(5)?,
+                        paste_ms: row.get(6)?,
+                        total_ms: row.get(7)?,
+                    },
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(transcriptions)
+        Ok(samples)
+    }
+
+    pub fn mark_ai_failed(&self, id: i64, error: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE transcriptions SET ai_status = 'failed', ai_error = ?1 WHERE id = ?2",
+            params![error, id],
+        )?;
+        Ok(())
+    }
+
+    /// Full-text search over the raw, processed and edited text, best
+    /// matches first. Supports "quoted phrases", prefix* terms and AND, OR
+    /// and NOT (or a leading -); anything else is searched for literally.
+    pub fn search_transcriptions(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TRANSCRIPTION_COLUMNS}, snippet
+             FROM transcriptions
+             JOIN (
+                 SELECT rowid AS match_id,
+                        snippet(transcriptions_fts, -1, char(2), char(3), '…', 16) AS snippet,
+                        bm25(transcriptions_fts) AS rank
+                 FROM transcriptions_fts
+                 WHERE transcriptions_fts MATCH ?1
+             ) ON id = match_id
+             ORDER BY rank
+             LIMIT ?2"
+        ))?;
+
+        let hits = stmt
+            .query_map(params![fts_query, limit as i64], |row| {
+                Ok(SearchHit {
+                    transcription: transcription_from_row(row)?,
+                    snippet: row.get("snippet")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hits)
     }
 
-    pub fn search_transcriptions(&self, query: &str) -> Result<Vec<Transcription>> {
-        let search_pattern = format!("%{}%", query);
+    /// Pairs of the text Convey produced and the user's correction of it,
+    /// for entries that have been edited.
+    pub fn get_corrections(&self) -> Result<Vec<(String, String)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, text, processed_text, language, duration_ms, created_at
+            "SELECT COALESCE(processed_text, text), edited_text
              FROM transcriptions
-             WHERE text LIKE ?1 OR processed_text LIKE ?1
-             ORDER BY created_at DESC
-             LIMIT 100",
+             WHERE edited_text IS NOT NULL",
         )?;
 
-        let transcriptions = stmt
-            .query_map([&search_pattern], |row| {
-                Ok(Transcription {
+        let corrections = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(corrections)
+    }
+
+    pub fn get_replacements(&self) -> Result<Vec<Replacement>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, pattern, replacement, enabled FROM replacements ORDER BY id",
+        )?;
+
+        let replacements = stmt
+            .query_map([], |row| {
+                Ok(Replacement {
                     id: row.get(0)?,
-                    text: row.get(1)?,
-                    processed_text: row.get(2)?,
-                    language: row.get(3)?,
-                    duration_ms: row.get(4)?,
-                    created_at: row.get(5)?,
+                    pattern: row.get(1)?,
+                    replacement: row.get(2)?,
+                    enabled: row.get(3)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(transcriptions)
+        Ok(replacements)
+    }
+
+    pub fn insert_replacement(&self, pattern: &str, replacement: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO replacements (pattern, replacement, enabled, created_at)
+             VALUES (?1, ?2, 1, ?3)",
+            params![pattern, replacement, Utc::now().to_rfc3339()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn set_replacement_enabled(&self, id: i64, enabled: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE replacements SET enabled = ?1 WHERE id = ?2",
+            params![enabled, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_replacement(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM replacements WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn insert_ai_usage(&self, usage: &NewAiUsage) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO ai_usage
+                (transcription_id, provider, model, prompt_tokens, completion_tokens, cost_usd,
+                 created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                usage.transcription_id,
+                usage.provider,
+                usage.model,
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64,
+                usage.cost_usd,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Totals for calls made at or after `since` (an RFC 3339 UTC timestamp).
+    pub fn ai_usage_since(&self, since: &str) -> Result<UsageTotals> {
+        let totals = self.conn.query_row(
+            "SELECT COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0),
+                    COALESCE(SUM(cost_usd), 0.0)
+             FROM ai_usage
+             WHERE created_at >= ?1",
+            [since],
+            |row| {
+                Ok(UsageTotals {
+                    prompt_tokens: row.get::<_, i64>(0)? as u64,
+                    completion_tokens: row.get::<_, i64>(1)? as u64,
+                    cost_usd: row.get(2)?,
+                })
+            },
+        )?;
+        Ok(totals)
+    }
+
+    /// Saves `text` as the entry's newest revision and makes it the current
+    /// text. A revision matching the produced text (restoring the original)
+    /// clears `edited_text`, so it isn't learned from as a correction.
+    pub fn add_revision(&mut self, id: i64, text: &str) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO transcription_revisions (transcription_id, text, created_at)
+             VALUES (?1, ?2, ?3)",
+            params![id, text, Utc::now().to_rfc3339()],
+        )?;
+        tx.execute(
+            "UPDATE transcriptions
+             SET edited_text = NULLIF(?1, COALESCE(processed_text, text))
+             WHERE id = ?2",
+            params![text, id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The entry's revisions, newest first.
+    pub fn get_revisions(&self, id: i64) -> Result<Vec<Revision>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT text, created_at FROM transcription_revisions
+             WHERE transcription_id = ?1
+             ORDER BY id DESC",
+        )?;
+        let revisions = stmt
+            .query_map([id], |row| {
+                Ok(Revision {
+                    text: row.get(0)?,
+                    created_at: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(revisions)
+    }
+
+    pub fn set_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE transcriptions SET pinned = ?1 WHERE id = ?2",
+            params![pinned, id],
+        )?;
+        Ok(())
+    }
+
+    /// Tags the entry, creating the tag if it is new. Tags differing only in
+    /// case are the same tag, keeping the spelling it was first created with.
+    pub fn add_tag(&self, id: i64, name: &str) -> Result<()> {
+        self.conn
+            .execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [name])?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO transcription_tags (transcription_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![id, name],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_tag(&self, id: i64, name: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM transcription_tags
+             WHERE transcription_id = ?1
+               AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+            params![id, name],
+        )?;
+        Ok(())
+    }
+
+    /// Tags in use on at least one entry, alphabetically.
+    pub fn get_tags(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name FROM tags
+             WHERE id IN (SELECT tag_id FROM transcription_tags)
+             ORDER BY name COLLATE NOCASE",
+        )?;
+        let tags = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    /// Deletes unpinned entries created before `cutoff` (RFC 3339) and
+    /// returns how many were removed.
+    pub fn delete_unpinned_before(&self, cutoff: &str) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM transcriptions WHERE pinned = 0 AND created_at < ?1",
+            [cutoff],
+        )?;
+        Ok(deleted)
     }
 
     pub fn delete_transcription(&self, id: i64) -> Result<()> {
@@ -111,3 +762,266 @@ impl Database {
         Ok(())
     }
 }
+
+/// Turns the search box input into an FTS5 query, or `None` if nothing is
+/// left to search for. Terms are quoted so characters FTS5 would read as
+/// syntax ("e-mail", "c++") are matched literally, and operators that would
+/// leave the query malformed are dropped, as is NEAR, which needs FTS5's
+/// group syntax.
+fn fts_query(input: &str) -> Option<String> {
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    let mut parts: Vec<String> = Vec::new();
+    // Operators go between terms, so one is held until the next term
+    let mut operator: Option<&str> = None;
+
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let phrase = quoted[..end].trim();
+            if !phrase.is_empty() {
+                push_term(&mut parts, operator.take(), quote(phrase));
+            }
+            rest = quoted.get(end + 1..).unwrap_or("").trim_start();
+            continue;
+        }
+
+        let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = rest[end..].trim_start();
+        match word {
+            "AND" | "OR" | "NOT" => operator = Some(word),
+            "NEAR" => {}
+            _ => {
+                if let Some(negated) = word.strip_prefix('-').filter(|w| !w.is_empty()) {
+                    operator = None;
+                    push_term(&mut parts, Some("NOT"), quote(negated));
+                } else if let Some(prefix) = word.strip_suffix('*').filter(|w| !w.is_empty()) {
+                    push_term(&mut parts, operator.take(), format!("{}*", quote(prefix)));
+                } else {
+                    push_term(&mut parts, operator.take(), quote(word));
+                }
+            }
+        }
+    }
+
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Adds a term with the operator before it. A leading operator is dropped,
+/// and so is a leading NOT's term, since FTS5 can't search for absence alone.
+fn push_term(parts: &mut Vec<String>, operator: Option<&str>, term: String) {
+    match operator {
+        Some("NOT") if parts.is_empty() => return,
+        Some(op) if !parts.is_empty() => parts.push(op.to_string()),
+        _ => {}
+    }
+    parts.push(term);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(name: &str) -> Database {
+        let dir = std::env::temp_dir().join(format!("convey-db-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Database::new(dir.join("history.db")).unwrap()
+    }
+
+    fn insert(db: &Database, text: &str) -> i64 {
+        db.insert_transcription(&NewTranscription {
+            text: text.to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn entry(db: &Database, id: i64) -> Transcription {
+        db.get_transcription(id).unwrap().unwrap()
+    }
+
+    fn revision_texts(db: &Database, id: i64) -> Vec<String> {
+        db.get_revisions(id).unwrap().into_iter().map(|r| r.text).collect()
+    }
+
+    #[test]
+    fn revisions_become_the_current_text() {
+        let mut db = database("revisions");
+        let id = insert(&db, "meet at noon");
+        assert_eq!(entry(&db, id).current_text(), "meet at noon");
+        assert!(revision_texts(&db, id).is_empty());
+
+        db.add_revision(id, "meet at one").unwrap();
+        db.add_revision(id, "meet at two").unwrap();
+        let edited = entry(&db, id);
+        assert_eq!(edited.edited_text.as_deref(), Some("meet at two"));
+        assert_eq!(edited.produced_text(), "meet at noon");
+        assert_eq!(edited.current_text(), "meet at two");
+        assert_eq!(revision_texts(&db, id), ["meet at two", "meet at one"]);
+
+        // Restoring the produced text is kept as a revision but isn't an edit
+        db.add_revision(id, "meet at noon").unwrap();
+        let restored = entry(&db, id);
+        assert_eq!(restored.edited_text, None);
+        assert_eq!(restored.current_text(), "meet at noon");
+        assert_eq!(revision_texts(&db, id).len(), 3);
+    }
+
+    #[test]
+    fn ai_result_after_an_edit_is_kept_as_a_revision() {
+        let mut db = database("ai-after-edit");
+        let id = insert(&db, "send the report");
+        db.add_revision(id, "send the quarterly report").unwrap();
+
+        db.update_processed_text(id, "Please send the quarterly report.", Some("email"))
+            .unwrap();
+        let entry = entry(&db, id);
+        assert_eq!(entry.current_text(), "Please send the quarterly report.");
+        assert_eq!(entry.mode.as_deref(), Some("email"));
+        assert_eq!(
+            revision_texts(&db, id),
+            ["Please send the quarterly report.", "send the quarterly report"]
+        );
+    }
+
+    #[test]
+    fn ai_result_without_edits_adds_no_revision() {
+        let mut db = database("ai-unedited");
+        let id = insert(&db, "send the report");
+
+        db.update_processed_text(id, "Send the report.", None).unwrap();
+        assert_eq!(entry(&db, id).current_text(), "Send the report.");
+        assert!(revision_texts(&db, id).is_empty());
+    }
+
+    #[test]
+    fn search_input_becomes_a_safe_fts_query() {
+        let cases = [
+            ("invoice", Some(r#""invoice""#)),
+            ("quarterly report", Some(r#""quarterly" "report""#)),
+            (r#""quarterly report" draft"#, Some(r#""quarterly report" "draft""#)),
+            ("invo*", Some(r#""invo"*"#)),
+            ("e-mail c++", Some(r#""e-mail" "c++""#)),
+            ("report -draft", Some(r#""report" NOT "draft""#)),
+            ("report AND draft", Some(r#""report" AND "draft""#)),
+            ("report OR NOT draft", Some(r#""report" NOT "draft""#)),
+            ("report and draft", Some(r#""report" "and" "draft""#)),
+            ("report NEAR draft", Some(r#""report" "draft""#)),
+            ("AND report OR", Some(r#""report""#)),
+            ("-draft report", Some(r#""report""#)),
+            // Stray syntax characters
+            (r#"say "hi"#, Some(r#""say" "hi""#)),
+            (r#"it's 5" long"#, Some(r#""it's" "5" "long""#)),
+            (r#"""#, None),
+            ("(draft", Some(r#""(draft""#)),
+            ("title: draft", Some(r#""title:" "draft""#)),
+            (r#"a"b"#, Some(r#""a" "b""#)),
+            ("*", Some(r#""*""#)),
+            ("-", Some(r#""-""#)),
+            ("AND OR NOT", None),
+            ("   ", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(fts_query(input).as_deref(), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn stray_syntax_searches_without_errors() {
+        let db = database("fts-syntax");
+        insert(&db, "Send the e-mail (draft) to Bob: it's 5\" long");
+        let inputs = [
+            r#"""#, r#"say "hi"#, "(draft", "draft)", "title: draft", "Bob:", "*", "-", "^", "+",
+            "NEAR(a b)",
+        ];
+        for input in inputs {
+            assert!(db.search_transcriptions(input, 10).is_ok(), "input: {:?}", input);
+        }
+        assert_eq!(db.search_transcriptions("(draft", 10).unwrap().len(), 1);
+        assert_eq!(db.search_transcriptions("e-mail -bob", 10).unwrap().len(), 0);
+        assert_eq!(db.search_transcriptions("e-ma*", 10).unwrap().len(), 1);
+    }
+
+    /// Ids of every entry matching `filter`, read `limit` at a time, and the
+    /// number of pages it took.
+    fn all_pages(db: &Database, filter: &HistoryFilter, limit: usize) -> (Vec<i64>, usize) {
+        let (mut ids, mut pages, mut cursor) = (Vec::new(), 0, None);
+        loop {
+            let page = db.query_transcriptions(filter, cursor.as_ref(), limit).unwrap();
+            assert!(page.entries.len() <= limit);
+            ids.extend(page.entries.iter().map(|e| e.id));
+            pages += 1;
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return (ids, pages),
+            }
+        }
+    }
+
+    fn set_created_at(db: &Database, id: i64, created_at: &str) {
+        db.conn
+            .execute(
+                "UPDATE transcriptions SET created_at = ?1 WHERE id = ?2",
+                params![created_at, id],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn pages_end_exactly_at_the_last_entry() {
+        let db = database("pages-exact");
+        let ids: Vec<i64> = (0..4).map(|i| insert(&db, &format!("entry {}", i))).collect();
+        for (i, id) in ids.iter().enumerate() {
+            set_created_at(&db, *id, &format!("2024-01-0{}T00:00:00+00:00", i + 1));
+        }
+        let newest_first: Vec<i64> = ids.iter().rev().copied().collect();
+
+        // Two full pages, and no empty third one
+        assert_eq!(all_pages(&db, &HistoryFilter::default(), 2), (newest_first.clone(), 2));
+        assert_eq!(all_pages(&db, &HistoryFilter::default(), 4), (newest_first.clone(), 1));
+        assert_eq!(all_pages(&db, &HistoryFilter::default(), 3), (newest_first, 2));
+    }
+
+    #[test]
+    fn pages_split_entries_with_the_same_time() {
+        let db = database("pages-same-time");
+        let ids: Vec<i64> = (0..5).map(|i| insert(&db, &format!("entry {}", i))).collect();
+        for id in &ids {
+            set_created_at(&db, *id, "2024-01-01T00:00:00+00:00");
+        }
+
+        // The id breaks the tie, so nothing is skipped or repeated
+        let newest_first: Vec<i64> = ids.iter().rev().copied().collect();
+        assert_eq!(all_pages(&db, &HistoryFilter::default(), 2), (newest_first, 3));
+    }
+
+    #[test]
+    fn pages_keep_the_filter() {
+        let db = database("pages-filter");
+        let mut email = Vec::new();
+        for i in 0..7 {
+            let id = db
+                .insert_transcription(&NewTranscription {
+                    text: format!("entry {}", i),
+                    mode: Some(if i % 2 == 0 { "email" } else { "notes" }.to_string()),
+                    ..Default::default()
+                })
+                .unwrap();
+            // Pairs share a time so the cursor's tie-break is exercised too
+            set_created_at(&db, id, &format!("2024-01-0{}T00:00:00+00:00", i / 2 + 1));
+            if i % 2 == 0 {
+                email.push(id);
+            }
+        }
+
+        let filter = HistoryFilter {
+            mode: Some("email".to_string()),
+            ..Default::default()
+        };
+        email.reverse();
+        assert_eq!(all_pages(&db, &filter, 1), (email.clone(), 4));
+        assert_eq!(all_pages(&db, &filter, 3), (email, 2));
+    }
+}
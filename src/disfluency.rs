@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+/// Longest run of words collapsed as a repeat ("I think I think").
+const MAX_REPEAT_WORDS: usize = 3;
+
+/// Words that are correctly doubled often enough to leave alone, mostly for
+/// emphasis ("very very good").
+const LEGITIMATE_DOUBLES: &[&str] = &[
+    "had", "that", "very", "really", "so", "too", "no", "bye", "far", "many", "much", "more",
+];
+
+/// Default filler lists by language code. Fillers are matched case-sensitively
+/// (apart from a capital first letter) and only where punctuation sets them off
+/// or they start the dictation, so "the ER" and "5 mm" keep their words.
+pub fn default_fillers() -> BTreeMap<String, Vec<String>> {
+    let lists: &[(&str, &[&str])] = &[
+        ("en", &["um", "umm", "uh", "uhm", "er", "erm", "hmm", "mm", "you know", "I mean"]),
+        ("de", &["äh", "ähm", "öhm", "hm", "hmm", "sag ich mal"]),
+        ("es", &["eh", "em", "mmm", "o sea", "pues nada"]),
+        ("fr", &["euh", "heu", "hum", "tu vois", "tu sais"]),
+    ];
+    lists
+        .iter()
+        .map(|(language, words)| {
+            (
+                language.to_string(),
+                words.iter().map(|w| w.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// A whitespace-separated token split into its word and the punctuation
+/// around it, with the whitespace that preceded it.
+struct Token {
+    separator: String,
+    lead: String,
+    core: String,
+    trail: String,
+}
+
+impl Token {
+    fn key(&self) -> String {
+        self.core.to_lowercase()
+    }
+
+    fn ends_sentence(&self) -> bool {
+        self.trail.contains(['.', '?', '!'])
+    }
+}
+
+/// Removes filler words and collapses stuttered repeats ("I I think"),
+/// then repairs the punctuation and capitalization they leave behind.
+/// Text that needs no cleanup comes back unchanged.
+pub fn remove_disfluencies(text: &str, fillers: &[String]) -> String {
+    let (mut tokens, tail) = tokenize(text);
+    let mut fillers: Vec<Vec<String>> = fillers
+        .iter()
+        .map(|f| f.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|f| !f.is_empty())
+        .collect();
+    fillers.sort_by_key(|f| std::cmp::Reverse(f.len()));
+
+    let mut changed = false;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match filler_at(&tokens, i, &fillers) {
+            Some(len) => {
+                remove_run(&mut tokens, i, len);
+                changed = true;
+            }
+            None => i += 1,
+        }
+    }
+
+    // Compare the n words before `i` with the n words from `i`, and drop
+    // the first copy so the kept words carry the sentence's punctuation.
+    let mut i = 1;
+    while i < tokens.len() {
+        match (1..=MAX_REPEAT_WORDS).rev().find(|&n| is_repeat(&tokens, i, n)) {
+            Some(n) => {
+                remove_run(&mut tokens, i - n, n);
+                changed = true;
+                i = (i - n).max(1);
+            }
+            None => i += 1,
+        }
+    }
+
+    if !changed {
+        return text.to_string();
+    }
+
+    let mut result: String = tokens
+        .iter()
+        .map(|t| format!("{}{}{}{}", t.separator, t.lead, t.core, t.trail))
+        .collect();
+    result.push_str(&tail);
+    result
+}
+
+/// Length of the filler starting at token `i`, if any.
+fn filler_at(tokens: &[Token], i: usize, fillers: &[Vec<String>]) -> Option<usize> {
+    // "5 mm" and "10 er" are units, not hesitation
+    let after_number = i > 0
+        && tokens[i - 1].trail.is_empty()
+        && tokens[i - 1].core.chars().any(|c| c.is_ascii_digit());
+    if after_number {
+        return None;
+    }
+
+    fillers
+        .iter()
+        .find(|filler| {
+            let Some(run) = tokens.get(i..i + filler.len()) else {
+                return false;
+            };
+            // Case-sensitive so acronyms survive: "Er," goes, "the ER" stays
+            let words_match = run
+                .iter()
+                .zip(filler.iter())
+                .all(|(t, w)| t.core == *w || t.core == capitalize(w));
+            let unbroken = run[..run.len() - 1].iter().all(|t| t.trail.is_empty());
+            let before = i == 0 || !tokens[i - 1].trail.is_empty();
+            let after = !run[run.len() - 1].trail.is_empty();
+            // "Do you know him" keeps its words; ", you know," is a filler.
+            // A single sound only needs punctuation on one side ("So, uh we").
+            let set_off = if filler.len() == 1 {
+                before || after
+            } else {
+                before && (after || i + filler.len() == tokens.len())
+            };
+            words_match && unbroken && set_off
+        })
+        .map(|filler| filler.len())
+}
+
+/// Whether the `n` words before `i` are repeated from `i` on.
+fn is_repeat(tokens: &[Token], i: usize, n: usize) -> bool {
+    if i < n || i + n > tokens.len() {
+        return false;
+    }
+    let (first, second) = (&tokens[i - n..i], &tokens[i..i + n]);
+    if n == 1 && LEGITIMATE_DOUBLES.contains(&first[0].key().as_str()) {
+        return false;
+    }
+
+    let same = first.iter().zip(second).all(|(a, b)| {
+        !a.core.is_empty() && !a.core.chars().any(|c| c.is_ascii_digit()) && a.key() == b.key()
+    });
+    // Only a comma may sit between the copies ("I, I think"); words within
+    // each copy must run on
+    let joined = first[..n - 1].iter().chain(&second[..n - 1]).all(|t| t.trail.is_empty())
+        && (first[n - 1].trail.is_empty() || first[n - 1].trail == ",");
+    same && joined
+}
+
+/// Drops tokens `i..i + len`, moving sentence punctuation and capitalization
+/// to the neighbours: "So uh." -> "So.", "Um, we" -> "We", "I, uh, think" ->
+/// "I think".
+fn remove_run(tokens: &mut Vec<Token>, i: usize, len: usize) {
+    let separator = tokens[i].separator.clone();
+    let removed_trail = tokens[i + len - 1].trail.clone();
+    let capitalized = tokens[i].core.starts_with(char::is_uppercase);
+    let next_is_lowercase = tokens
+        .get(i + len)
+        .is_some_and(|t| t.core.starts_with(char::is_lowercase));
+
+    let mut sentence_start = i == 0;
+    if let Some(prev) = i.checked_sub(1).map(|p| &mut tokens[p]) {
+        if removed_trail.contains(['.', '?', '!']) && !prev.ends_sentence() {
+            let kept = prev.trail.trim_end_matches(',').len();
+            prev.trail.truncate(kept);
+            prev.trail.push_str(removed_trail.trim_start_matches(','));
+        } else if removed_trail.ends_with(',') && prev.trail.ends_with(',') && next_is_lowercase {
+            prev.trail.pop();
+        }
+        sentence_start = prev.ends_sentence();
+    }
+
+    if let Some(next) = tokens.get_mut(i + len) {
+        next.separator = separator;
+        if sentence_start && capitalized {
+            next.core = capitalize(&next.core);
+        }
+    }
+    tokens.drain(i..i + len);
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Tokens and the whitespace after the last one.
+fn tokenize(text: &str) -> (Vec<Token>, String) {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    loop {
+        let word_start = rest.len() - rest.trim_start().len();
+        if word_start == rest.len() {
+            return (tokens, rest.to_string());
+        }
+        let separator = &rest[..word_start];
+        let after = &rest[word_start..];
+        let word_end = after.find(char::is_whitespace).unwrap_or(after.len());
+        let raw = &after[..word_end];
+
+        let lead_len = raw.len() - raw.trim_start_matches(|c: char| !c.is_alphanumeric()).len();
+        let core_end = raw.trim_end_matches(|c: char| !c.is_alphanumeric()).len().max(lead_len);
+        tokens.push(Token {
+            separator: separator.to_string(),
+            lead: raw[..lead_len].to_string(),
+            core: raw[lead_len..core_end].to_string(),
+            trail: raw[core_end..].to_string(),
+        });
+        rest = &after[word_end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english() -> Vec<String> {
+        default_fillers().remove("en").unwrap()
+    }
+
+    #[test]
+    fn removes_fillers_and_repeats() {
+        let fillers = english();
+        let cases = [
+            ("Um, we should go.", "We should go."),
+            ("Um we should go.", "We should go."),
+            ("I, uh, think so.", "I think so."),
+            ("So uh.", "So."),
+            ("We went, er, home.", "We went home."),
+            ("It was, you know, fine.", "It was fine."),
+            ("I mean, it works.", "It works."),
+            ("Er, the ER is full.", "The ER is full."),
+            ("I I think so.", "I think so."),
+            ("I, I think so.", "I think so."),
+            ("I think I think we should.", "I think we should."),
+            ("The the plan works.", "The plan works."),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(remove_disfluencies(input, &fillers), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn leaves_ordinary_words_alone() {
+        let fillers = english();
+        let sentences = [
+            "The bolt is 5 mm wide.",
+            "Is it 10 mm or 12 mm?",
+            "The ER is full.",
+            "It was very very good.",
+            "I really really mean it.",
+            "He said that that was fine.",
+            "We had had enough.",
+            "Do you know him?",
+            "I mean what I say.",
+            "The summer was hot.",
+            "Call 555 555 1234.",
+            "",
+        ];
+        for sentence in sentences {
+            assert_eq!(remove_disfluencies(sentence, &fillers), sentence);
+        }
+    }
+
+    #[test]
+    fn fillers_follow_the_language_list() {
+        let german = default_fillers().remove("de").unwrap();
+        assert_eq!(remove_disfluencies("Ähm, wir gehen.", &german), "Wir gehen.");
+        assert_eq!(remove_disfluencies("Es ist, sag ich mal, gut.", &german), "Es ist gut.");
+        assert_eq!(remove_disfluencies("Um, we go.", &german), "Um, we go.");
+    }
+}
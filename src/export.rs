@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Transcription;
+
+/// Bumped when the JSON layout changes incompatibly.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Every entry with all its metadata.
+    Json,
+    /// One row per entry, for spreadsheets.
+    Csv,
+    /// Readable archive grouped by day.
+    Markdown,
+    /// Subtitles from the timestamped segments.
+    Srt,
+    Vtt,
+}
+
+impl ExportFormat {
+    pub fn all() -> [ExportFormat; 5] {
+        [
+            ExportFormat::Json,
+            ExportFormat::Csv,
+            ExportFormat::Markdown,
+            ExportFormat::Srt,
+            ExportFormat::Vtt,
+        ]
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "srt" => Some(ExportFormat::Srt),
+            "vtt" | "webvtt" => Some(ExportFormat::Vtt),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Srt => "SRT",
+            ExportFormat::Vtt => "VTT",
+        })
+    }
+}
+
+/// Top level of a JSON export. Entries deserialize back into `Transcription`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonExport {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub entries: Vec<Transcription>,
+}
+
+/// Renders entries, oldest first whatever order they come in. Subtitle
+/// formats fail if no entry has timestamped segments.
+pub fn render(entries: &[Transcription], format: ExportFormat) -> Result<String> {
+    let mut entries: Vec<&Transcription> = entries.iter().collect();
+    entries.sort_by(|a, b| (&a.created_at, a.id).cmp(&(&b.created_at, b.id)));
+
+    match format {
+        ExportFormat::Json => render_json(&entries),
+        ExportFormat::Csv => Ok(render_csv(&entries)),
+        ExportFormat::Markdown => Ok(render_markdown(&entries)),
+        ExportFormat::Srt => render_subtitles(&entries, ExportFormat::Srt),
+        ExportFormat::Vtt => render_subtitles(&entries, ExportFormat::Vtt),
+    }
+}
+
+/// `~/Downloads/convey-history-20261018-143200.csv`, or the home directory
+/// if there is no downloads folder.
+pub fn default_path(format: ExportFormat) -> PathBuf {
+    let dir = UserDirs::new()
+        .and_then(|dirs| {
+            dirs.download_dir()
+                .map(PathBuf::from)
+                .or_else(|| Some(dirs.home_dir().to_path_buf()))
+        })
+        .unwrap_or_default();
+    dir.join(format!(
+        "convey-history-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ))
+}
+
+fn render_json(entries: &[&Transcription]) -> Result<String> {
+    let export = JsonExport {
+        format_version: JSON_FORMAT_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        entries: entries.iter().map(|&entry| entry.clone()).collect(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+const CSV_HEADER: [&str; 13] = [
+    "id",
+    "created_at",
+    "language",
+    "mode",
+    "app",
+    "duration_ms",
+    "avg_confidence",
+    "ai_status",
+    "pinned",
+    "tags",
+    "text",
+    "processed_text",
+    "edited_text",
+];
+
+/// RFC 4180: CRLF line ends, fields quoted when they need it. Tags are
+/// joined with "; ".
+fn render_csv(entries: &[&Transcription]) -> String {
+    let mut out = String::new();
+    push_csv_row(&mut out, CSV_HEADER.map(str::to_string));
+    for entry in entries {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        push_csv_row(
+            &mut out,
+            [
+                entry.id.to_string(),
+                entry.created_at.clone(),
+                optional(&entry.language),
+                optional(&entry.mode),
+                optional(&entry.app),
+                entry.duration_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+                entry.avg_confidence.map(|c| c.to_string()).unwrap_or_default(),
+                entry.ai_status.map(|s| s.as_str().to_string()).unwrap_or_default(),
+                entry.pinned.to_string(),
+                entry.tags.join("; "),
+                entry.text.clone(),
+                optional(&entry.processed_text),
+                optional(&entry.edited_text),
+            ],
+        );
+    }
+    out
+}
+
+fn push_csv_row<const N: usize>(out: &mut String, fields: [String; N]) {
+    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    out.push_str(&fields.join(","));
+    out.push_str("\r\n");
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A section per local day with each entry's current text under its time.
+fn render_markdown(entries: &[&Transcription]) -> String {
+    let mut days: BTreeMap<NaiveDate, Vec<(DateTime<Local>, &Transcription)>> = BTreeMap::new();
+    for &entry in entries {
+        let Ok(created) = DateTime::parse_from_rfc3339(&entry.created_at) else {
+            continue;
+        };
+        let created = created.with_timezone(&Local);
+        days.entry(created.date_naive()).or_default().push((created, entry));
+    }
+
+    let mut out = String::from("# Convey history\n");
+    for (day, entries) in days {
+        let _ = write!(out, "\n## {}\n", day.format("%A, %-d %B %Y"));
+        for (created, entry) in entries {
+            let mut heading = created.format("%H:%M").to_string();
+            for detail in [&entry.mode, &entry.app].into_iter().flatten() {
+                heading = format!("{} · {}", heading, detail);
+            }
+            if entry.edited_text.is_some() {
+                heading.push_str(" · edited");
+            }
+            let _ = write!(out, "\n### {}\n\n{}\n", heading, entry.current_text().trim());
+            if !entry.tags.is_empty() {
+                let tags: Vec<String> = entry.tags.iter().map(|tag| format!("#{}", tag)).collect();
+                let _ = write!(out, "\nTags: {}\n", tags.join(" "));
+            }
+        }
+    }
+    out
+}
+
+/// Entries follow each other on one timeline, each starting where the
+/// previous one's audio ended. Entries without segments are left out. The
+/// cues hold whisper's words, since edits and AI output have no timings.
+fn render_subtitles(entries: &[&Transcription], format: ExportFormat) -> Result<String> {
+    let timed: Vec<&Transcription> = entries
+        .iter()
+        .copied()
+        .filter(|entry| !entry.segments.is_empty())
+        .collect();
+    if timed.is_empty() {
+        bail!("None of the selected entries has timestamped segments");
+    }
+
+    let vtt = format == ExportFormat::Vtt;
+    let mut out = String::new();
+    if vtt {
+        out.push_str("WEBVTT\n");
+    }
+    let mut offset = 0;
+    let mut cue = 0;
+    for entry in timed {
+        if vtt {
+            let _ = write!(out, "\nNOTE {}\n", entry.created_at);
+        }
+        let mut end = 0;
+        for segment in &entry.segments {
+            end = end.max(segment.end_ms);
+            let text = segment.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            cue += 1;
+            let start = subtitle_time(offset + segment.start_ms, vtt);
+            let stop = subtitle_time(offset + segment.end_ms.max(segment.start_ms), vtt);
+            if vtt {
+                let _ = write!(out, "\n{} --> {}\n{}\n", start, stop, text);
+            } else {
+                let _ = write!(out, "{}\n{} --> {}\n{}\n\n", cue, start, stop, text);
+            }
+        }
+        offset += entry.duration_ms.unwrap_or(0).max(end);
+    }
+    Ok(out)
+}
+
+/// `01:02:03,456` for SRT, `01:02:03.456` for VTT.
+fn subtitle_time(ms: i64, vtt: bool) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        if vtt { '.' } else { ',' },
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AiStatus, Timings};
+    use crate::replacements::AppliedReplacement;
+    use crate::whisper::Segment;
+
+    fn entry(id: i64, created_at: &str, text: &str) -> Transcription {
+        Transcription {
+            id,
+            text: text.to_string(),
+            processed_text: None,
+            edited_text: None,
+            language: Some("en".to_string()),
+            duration_ms: None,
+            created_at: created_at.to_string(),
+            avg_confidence: None,
+            segments: Vec::new(),
+            mode: None,
+            ai_status: None,
+            ai_error: None,
+            app: None,
+            applied_replacements: Vec::new(),
+            pre_cleanup_text: None,
+            whisper_model: None,
+            timings: Timings::default(),
+            pinned: false,
+            tags: Vec::new(),
+        }
+    }
+
+    fn segment(start_ms: i64, end_ms: i64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            words: Vec::new(),
+        }
+    }
+
+    /// RFC 4180 rows, honouring quoted commas, quotes and line breaks.
+    fn parse_csv(input: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => quoted = true,
+                (false, ',') => row.push(std::mem::take(&mut field)),
+                (false, '\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        assert!(field.is_empty() && row.is_empty(), "unterminated row");
+        rows
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let mut full = entry(2, "2024-03-05T10:00:00+00:00", "send the draft");
+        full.processed_text = Some("Send the draft.".to_string());
+        full.edited_text = Some("Send the final draft.".to_string());
+        full.duration_ms = Some(2500);
+        full.avg_confidence = Some(0.875);
+        full.segments = vec![segment(0, 2400, " send the draft")];
+        full.mode = Some("clean_up".to_string());
+        full.ai_status = Some(AiStatus::Done);
+        full.app = Some("Mail".to_string());
+        full.applied_replacements = vec![AppliedReplacement {
+            rule_id: 3,
+            pattern: "teh".to_string(),
+        }];
+        full.pre_cleanup_text = Some("um send the draft".to_string());
+        full.whisper_model = Some("base".to_string());
+        full.timings = Timings {
+            whisper_ms: Some(800),
+            ai_ms: Some(1200),
+            paste_ms: Some(40),
+            total_ms: Some(2100),
+        };
+        full.pinned = true;
+        full.tags = vec!["work".to_string(), "email".to_string()];
+        let entries = vec![full, entry(1, "2024-03-04T09:00:00+00:00", "first \"quoted\" entry")];
+
+        let json = render(&entries, ExportFormat::Json).unwrap();
+        let parsed: JsonExport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.format_version, JSON_FORMAT_VERSION);
+        assert!(DateTime::parse_from_rfc3339(&parsed.exported_at).is_ok());
+        // Oldest first, otherwise unchanged
+        let expected: Vec<serde_json::Value> = [&entries[1], &entries[0]]
+            .iter()
+            .map(|entry| serde_json::to_value(entry).unwrap())
+            .collect();
+        let actual: Vec<serde_json::Value> = parsed
+            .entries
+            .iter()
+            .map(|entry| serde_json::to_value(entry).unwrap())
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn csv_quotes_what_needs_it() {
+        let mut tricky = entry(7, "2024-03-05T10:00:00+00:00", "apples, pears and \"figs\"");
+        tricky.processed_text = Some("Line one\r\nline two\nline three".to_string());
+        tricky.tags = vec!["a,b".to_string(), "c".to_string()];
+        tricky.pinned = true;
+        tricky.ai_status = Some(AiStatus::Done);
+        let plain = entry(3, "2024-03-04T09:00:00+00:00", "plain");
+
+        let csv = render(&[tricky, plain], ExportFormat::Csv).unwrap();
+        assert!(csv.ends_with("\r\n"));
+        let rows = parse_csv(&csv);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], CSV_HEADER);
+        assert!(rows.iter().all(|row| row.len() == CSV_HEADER.len()));
+        assert_eq!(rows[1][0], "3");
+        assert_eq!(rows[1][10], "plain");
+        assert_eq!(rows[1][11], "");
+        let tricky = &rows[2];
+        assert_eq!(tricky[0], "7");
+        assert_eq!(tricky[7], "done");
+        assert_eq!(tricky[8], "true");
+        assert_eq!(tricky[9], "a,b; c");
+        assert_eq!(tricky[10], "apples, pears and \"figs\"");
+        assert_eq!(tricky[11], "Line one\r\nline two\nline three");
+    }
+
+    #[test]
+    fn csv_field_quoting() {
+        assert_eq!(csv_field("plain text"), "plain text");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\rlf"), "\"cr\rlf\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    fn timed_entries() -> Vec<Transcription> {
+        let mut first = entry(1, "2024-03-04T09:00:00+00:00", "hello there");
+        first.duration_ms = Some(3000);
+        first.segments = vec![
+            segment(0, 1200, " Hello"),
+            segment(1200, 1200, " "),
+            segment(1300, 2500, " there."),
+        ];
+        let untimed = entry(2, "2024-03-04T09:05:00+00:00", "no timings");
+        let mut second = entry(3, "2024-03-04T10:00:00+00:00", "second entry");
+        second.segments = vec![segment(500, 3_723_456, "Second entry.")];
+        vec![second, untimed, first]
+    }
+
+    #[test]
+    fn srt_numbers_cues_across_entries() {
+        let srt = render(&timed_entries(), ExportFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,200\nHello\n\n\
+             2\n00:00:01,300 --> 00:00:02,500\nthere.\n\n\
+             3\n00:00:03,500 --> 01:02:06,456\nSecond entry.\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_uses_dots_and_notes() {
+        let vtt = render(&timed_entries(), ExportFormat::Vtt).unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\
+             \nNOTE 2024-03-04T09:00:00+00:00\n\
+             \n00:00:00.000 --> 00:00:01.200\nHello\n\
+             \n00:00:01.300 --> 00:00:02.500\nthere.\n\
+             \nNOTE 2024-03-04T10:00:00+00:00\n\
+             \n00:00:03.500 --> 01:02:06.456\nSecond entry.\n"
+        );
+    }
+
+    #[test]
+    fn subtitles_need_timed_entries() {
+        let err = render(&[entry(1, "2024-03-04T09:00:00+00:00", "x")], ExportFormat::Srt)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "None of the selected entries has timestamped segments");
+    }
+
+    #[test]
+    fn subtitle_times() {
+        assert_eq!(subtitle_time(0, false), "00:00:00,000");
+        assert_eq!(subtitle_time(61_001, true), "00:01:01.001");
+        assert_eq!(subtitle_time(36_000_000 + 59_999, false), "10:00:59,999");
+        assert_eq!(subtitle_time(-5, true), "00:00:00.000");
+    }
+}
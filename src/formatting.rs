@@ -0,0 +1,471 @@
+/// How a spoken symbol is spaced against its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Spacing {
+    /// Attaches to the previous word: `,` `.` `)`
+    Left,
+    /// Attaches to the next word: `(` `$`
+    Right,
+    /// Joins both words: `_` `/` `@` and "dot"
+    Both,
+    /// Stands alone like a word: `=` `+`
+    Spaced,
+}
+
+/// Symbols spoken in prose as well as code. Single words that are common in
+/// ordinary sentences ("dash", "slash", "colon") are code-only, and "period"
+/// only counts at the end of a clause (see `CLAUSE_END_ONLY`).
+const SYMBOLS: &[(&str, &str, Spacing)] = &[
+    ("comma", ",", Spacing::Left),
+    ("period", ".", Spacing::Left),
+    ("full stop", ".", Spacing::Left),
+    ("question mark", "?", Spacing::Left),
+    ("exclamation mark", "!", Spacing::Left),
+    ("exclamation point", "!", Spacing::Left),
+    ("semicolon", ";", Spacing::Left),
+    ("ellipsis", "...", Spacing::Left),
+    ("percent sign", "%", Spacing::Left),
+    ("close paren", ")", Spacing::Left),
+    ("close parenthesis", ")", Spacing::Left),
+    ("close bracket", "]", Spacing::Left),
+    ("close brace", "}", Spacing::Left),
+    ("close quote", "\"", Spacing::Left),
+    ("open paren", "(", Spacing::Right),
+    ("open parenthesis", "(", Spacing::Right),
+    ("open bracket", "[", Spacing::Right),
+    ("open brace", "{", Spacing::Right),
+    ("open quote", "\"", Spacing::Right),
+    ("dollar sign", "$", Spacing::Right),
+    ("hash sign", "#", Spacing::Right),
+    ("at sign", "@", Spacing::Both),
+    ("equals sign", "=", Spacing::Spaced),
+    ("plus sign", "+", Spacing::Spaced),
+    ("ampersand", "&", Spacing::Spaced),
+    ("asterisk", "*", Spacing::Spaced),
+];
+
+/// Code dictation also takes bare symbol words, which in prose would too
+/// often be meant literally, and spaces brackets for calls and indexing.
+const CODE_SYMBOLS: &[(&str, &str, Spacing)] = &[
+    ("dot", ".", Spacing::Both),
+    ("colon", ":", Spacing::Left),
+    ("underscore", "_", Spacing::Both),
+    ("dash", "-", Spacing::Both),
+    ("hyphen", "-", Spacing::Both),
+    ("slash", "/", Spacing::Both),
+    ("backslash", "\\", Spacing::Both),
+    ("open paren", "(", Spacing::Both),
+    ("open parenthesis", "(", Spacing::Both),
+    ("open bracket", "[", Spacing::Both),
+    ("open brace", "{", Spacing::Spaced),
+    ("close brace", "}", Spacing::Spaced),
+    ("double colon", "::", Spacing::Both),
+    ("arrow", "->", Spacing::Spaced),
+    ("fat arrow", "=>", Spacing::Spaced),
+    ("equals", "=", Spacing::Spaced),
+    ("double equals", "==", Spacing::Spaced),
+    ("triple equals", "===", Spacing::Spaced),
+    ("not equals", "!=", Spacing::Spaced),
+    ("plus equals", "+=", Spacing::Spaced),
+    ("minus equals", "-=", Spacing::Spaced),
+    ("greater than", ">", Spacing::Spaced),
+    ("less than", "<", Spacing::Spaced),
+    ("plus", "+", Spacing::Spaced),
+    ("minus", "-", Spacing::Spaced),
+    ("star", "*", Spacing::Spaced),
+    ("and and", "&&", Spacing::Spaced),
+    ("or or", "||", Spacing::Spaced),
+    ("pipe", "|", Spacing::Spaced),
+    ("bang", "!", Spacing::Right),
+];
+
+/// Symbol words that are also ordinary nouns ("the trial period ends"),
+/// recognized only as the last word or where whisper ended a clause.
+const CLAUSE_END_ONLY: &[&str] = &["period"];
+
+const BREAKS: &[(&str, &str)] = &[("new line", "\n"), ("new paragraph", "\n\n")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Camel,
+    Pascal,
+    Snake,
+    Kebab,
+    Constant,
+    Upper,
+    Capitalized,
+}
+
+/// Case commands and how many of the following words they take. Identifier
+/// styles take words up to the next punctuation, at most `IDENTIFIER_WORDS`.
+const CASES: &[(&str, Case, usize)] = &[
+    ("camel case", Case::Camel, IDENTIFIER_WORDS),
+    ("pascal case", Case::Pascal, IDENTIFIER_WORDS),
+    ("snake case", Case::Snake, IDENTIFIER_WORDS),
+    ("kebab case", Case::Kebab, IDENTIFIER_WORDS),
+    ("constant case", Case::Constant, IDENTIFIER_WORDS),
+    ("all caps", Case::Upper, 1),
+    ("capitalize next", Case::Capitalized, 1),
+];
+
+const IDENTIFIER_WORDS: usize = 5;
+
+/// Which spoken commands apply and how the rest of the text is treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Prose,
+    /// Whisper's punctuation and sentence capitalization are dropped; only
+    /// spoken symbols are written.
+    Code,
+}
+
+/// A whitespace-separated token: the bare word used for matching, and the
+/// punctuation whisper attached around it.
+struct Token<'a> {
+    raw: &'a str,
+    /// `raw` without the surrounding punctuation.
+    core: &'a str,
+    word: String,
+    /// Ends in punctuation, so an identifier stops here.
+    ends_clause: bool,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    text.split_whitespace()
+        .map(|raw| {
+            let core = raw.trim_matches(|c: char| !c.is_alphanumeric());
+            Token {
+                raw,
+                core,
+                word: core.to_lowercase(),
+                ends_clause: raw.ends_with(|c: char| !c.is_alphanumeric()),
+            }
+        })
+        .collect()
+}
+
+/// Length of `phrase` if the tokens at `at` spell it out.
+fn phrase_at(tokens: &[Token], at: usize, phrase: &str) -> Option<usize> {
+    let words: Vec<&str> = phrase.split(' ').collect();
+    let matches = tokens.len() >= at + words.len()
+        && tokens[at..]
+            .iter()
+            .zip(&words)
+            .all(|(token, word)| token.word == *word);
+    matches.then_some(words.len())
+}
+
+/// The longest spoken symbol at `at`; code symbols win ties.
+fn symbol_at(tokens: &[Token], at: usize, style: Style) -> Option<(usize, &'static str, Spacing)> {
+    let code: &[(&str, &str, Spacing)] = match style {
+        Style::Prose => &[],
+        Style::Code => CODE_SYMBOLS,
+    };
+    SYMBOLS
+        .iter()
+        .chain(code)
+        .filter_map(|(phrase, symbol, spacing)| {
+            let len = phrase_at(tokens, at, phrase)?;
+            let last = at + len - 1;
+            let at_clause_end = last + 1 == tokens.len() || tokens[last].ends_clause;
+            (at_clause_end || !CLAUSE_END_ONLY.contains(phrase)).then_some((len, *symbol, *spacing))
+        })
+        .max_by_key(|(len, ..)| *len)
+}
+
+fn is_command_word(tokens: &[Token], at: usize, style: Style) -> bool {
+    symbol_at(tokens, at, style).is_some()
+        || BREAKS.iter().any(|(phrase, _)| phrase_at(tokens, at, phrase).is_some())
+        || CASES.iter().any(|(phrase, ..)| phrase_at(tokens, at, phrase).is_some())
+}
+
+/// Joins output pieces, spacing them according to each symbol's rules.
+struct Output {
+    text: String,
+    /// No space before the next piece (after `(`, `_`, a line break, ...).
+    glue_next: bool,
+    /// Capitalize the next word (after a spoken `.`, `?` or `!`).
+    capitalize_next: bool,
+    /// Spoken sentence ends capitalize the next word; off for code.
+    sentences: bool,
+}
+
+impl Output {
+    fn push_word(&mut self, word: &str) {
+        if !self.glue_next && !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        if self.capitalize_next {
+            self.text.push_str(&capitalize(word));
+        } else {
+            self.text.push_str(word);
+        }
+        self.glue_next = false;
+        self.capitalize_next = false;
+    }
+
+    fn push_symbol(&mut self, symbol: &str, spacing: Spacing) {
+        match spacing {
+            Spacing::Left | Spacing::Both => {
+                self.text.truncate(self.text.trim_end_matches(' ').len());
+                self.text.push_str(symbol);
+            }
+            Spacing::Right | Spacing::Spaced => {
+                if !self.glue_next && !self.text.is_empty() {
+                    self.text.push(' ');
+                }
+                self.text.push_str(symbol);
+            }
+        }
+        self.glue_next = matches!(spacing, Spacing::Right | Spacing::Both);
+        self.capitalize_next =
+            self.sentences && matches!(symbol, "." | "?" | "!") && spacing == Spacing::Left;
+    }
+
+    fn push_break(&mut self, separator: &str) {
+        self.text.truncate(self.text.trim_end_matches(' ').len());
+        self.text.push_str(separator);
+        self.glue_next = true;
+    }
+}
+
+/// Turns spoken punctuation and formatting into the literal result:
+/// "comma", "open paren", "new line", "all caps foo", "camel case user id",
+/// "capitalize next word".
+/// Deterministic and offline; text without commands comes back unchanged.
+pub fn apply_spoken_formatting(text: &str) -> String {
+    format_spoken(text, Style::Prose)
+}
+
+/// Code dictation: "snake case get user name equals self dot name" ->
+/// "get_user_name = self.name". Symbol words need no "sign" suffix here,
+/// and the result has no sentence capitalization or trailing period.
+pub fn apply_code_formatting(text: &str) -> String {
+    format_spoken(text, Style::Code)
+}
+
+fn format_spoken(text: &str, style: Style) -> String {
+    let tokens = tokenize(text);
+    let mut output = Output {
+        text: String::new(),
+        glue_next: false,
+        capitalize_next: false,
+        sentences: style == Style::Prose,
+    };
+
+    let mut i = 0;
+    let mut changed = style == Style::Code;
+    while i < tokens.len() {
+        if let Some((len, symbol, spacing)) = symbol_at(&tokens, i, style) {
+            output.push_symbol(symbol, spacing);
+            i += len;
+            changed = true;
+            continue;
+        }
+
+        if let Some((len, separator)) = BREAKS.iter().find_map(|(phrase, separator)| {
+            phrase_at(&tokens, i, phrase).map(|len| (len, *separator))
+        }) {
+            output.push_break(separator);
+            i += len;
+            changed = true;
+            continue;
+        }
+
+        if let Some((len, case, max_words)) = CASES.iter().find_map(|(phrase, case, max)| {
+            phrase_at(&tokens, i, phrase).map(|len| (len, *case, *max))
+        }) {
+            let start = i + len;
+            let mut end = start;
+            while end < tokens.len()
+                && end - start < max_words
+                && !is_command_word(&tokens, end, style)
+            {
+                end += 1;
+                if tokens[end - 1].ends_clause {
+                    break;
+                }
+            }
+            if end > start {
+                let words: Vec<&str> = tokens[start..end].iter().map(|t| t.word.as_str()).collect();
+                output.push_word(&apply_case(case, &words));
+                // Keep punctuation whisper put after the last word
+                let last = tokens[end - 1].raw;
+                let trailing = &last[last.trim_end_matches(|c: char| !c.is_alphanumeric()).len()..];
+                if !trailing.is_empty() && style == Style::Prose {
+                    output.push_symbol(trailing, Spacing::Left);
+                }
+                i = end;
+                changed = true;
+                continue;
+            }
+        }
+
+        if style == Style::Code {
+            let word = code_word(&tokens, i);
+            if !word.is_empty() {
+                output.push_word(&word);
+            }
+            i += 1;
+            continue;
+        }
+
+        // Whisper often punctuates around a spoken command ("Hello, comma,
+        // world."), so drop its punctuation right before one.
+        let raw = tokens[i].raw;
+        let next_is_symbol = matches!(symbol_at(&tokens, i + 1, style), Some((_, _, Spacing::Left)));
+        if next_is_symbol {
+            output.push_word(raw.trim_end_matches(|c: char| !c.is_alphanumeric()));
+        } else {
+            output.push_word(raw);
+        }
+        i += 1;
+    }
+
+    if changed {
+        output.text
+    } else {
+        text.to_string()
+    }
+}
+
+/// A plain word in code: whisper's punctuation dropped, and its sentence
+/// capitalization undone unless the word is an acronym.
+fn code_word(tokens: &[Token], i: usize) -> String {
+    let core = tokens[i].core;
+    let sentence_start = i == 0 || tokens[i - 1].raw.ends_with(['.', '?', '!']);
+    let acronym = core.chars().filter(|c| c.is_alphabetic()).all(char::is_uppercase);
+    if !sentence_start || acronym {
+        return core.to_string();
+    }
+    let mut chars = core.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn apply_case(case: Case, words: &[&str]) -> String {
+    match case {
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_string() } else { capitalize(w) })
+            .collect(),
+        Case::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        Case::Snake => words.join("_"),
+        Case::Kebab => words.join("-"),
+        Case::Constant => words.join("_").to_uppercase(),
+        Case::Upper => words.join(" ").to_uppercase(),
+        Case::Capitalized => words.iter().map(|w| capitalize(w)).collect::<Vec<_>>().join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prose_commands() {
+        let cases = [
+            ("Hello comma world", "Hello, world"),
+            ("Hello, comma, world.", "Hello, world."),
+            ("That's all period", "That's all."),
+            ("That's all. Period. See you", "That's all. See you"),
+            ("Stop full stop then go", "Stop. Then go"),
+            ("Really question mark", "Really?"),
+            ("Wow exclamation mark", "Wow!"),
+            ("Wow exclamation point", "Wow!"),
+            ("First semicolon second", "First; second"),
+            ("Wait ellipsis", "Wait..."),
+            ("Ten percent sign off", "Ten% off"),
+            ("Call me open paren maybe close paren", "Call me (maybe)"),
+            ("See open parenthesis below close parenthesis", "See (below)"),
+            ("An open bracket note close bracket", "An [note]"),
+            ("An open brace block close brace", "An {block}"),
+            ("He said open quote hi close quote", "He said \"hi\""),
+            ("It costs dollar sign five", "It costs $five"),
+            ("Use hash sign tags", "Use #tags"),
+            ("Mail me at sign home", "Mail me@home"),
+            ("A equals sign b", "A = b"),
+            ("A plus sign b", "A + b"),
+            ("Salt ampersand pepper", "Salt & pepper"),
+            ("Note asterisk", "Note *"),
+            ("Dear Bob new line Hi", "Dear Bob\nHi"),
+            ("Dear Bob new paragraph Hi", "Dear Bob\n\nHi"),
+            ("Call all caps nasa today", "Call NASA today"),
+            ("Meet capitalize next bob", "Meet Bob"),
+            ("Set camel case user id", "Set userId"),
+            ("Set pascal case user id", "Set UserId"),
+            ("Set snake case user id", "Set user_id"),
+            ("Set kebab case user id", "Set user-id"),
+            ("Set constant case max size", "Set MAX_SIZE"),
+            ("Set snake case user id, then go", "Set user_id, then go"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(apply_spoken_formatting(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn prose_leaves_ordinary_words_alone() {
+        let sentences = [
+            "The trial period ends Friday.",
+            "Use a dash of salt.",
+            "Either/or, a slash or a dot.",
+            "The colon is part of the bowel.",
+            "Add a hyphen and an underscore.",
+            "Please capitalize the first letter.",
+            "Plus, it equals what we said.",
+            "No commands here at all",
+        ];
+        for sentence in sentences {
+            assert_eq!(apply_spoken_formatting(sentence), sentence);
+        }
+    }
+
+    #[test]
+    fn code_commands() {
+        let cases = [
+            ("snake case get user name equals self dot name", "get_user_name = self.name"),
+            ("Camel case user id.", "userId"),
+            ("pascal case http client", "HttpClient"),
+            ("kebab case main menu", "main-menu"),
+            ("constant case max size equals ten", "MAX_SIZE = ten"),
+            ("items open bracket i close bracket", "items[i]"),
+            ("print open paren x close paren", "print(x)"),
+            ("std double colon io", "std::io"),
+            ("key colon value", "key: value"),
+            ("x arrow y", "x -> y"),
+            ("x fat arrow y", "x => y"),
+            ("a double equals b", "a == b"),
+            ("a triple equals b", "a === b"),
+            ("a not equals b", "a != b"),
+            ("a plus equals one", "a += one"),
+            ("a minus equals one", "a -= one"),
+            ("a greater than b", "a > b"),
+            ("a less than b", "a < b"),
+            ("a plus b minus c star d", "a + b - c * d"),
+            ("a and and b or or c", "a && b || c"),
+            ("a pipe b", "a | b"),
+            ("bang done", "!done"),
+            ("src slash main", "src/main"),
+            ("c colon backslash temp", "c:\\temp"),
+            ("my underscore var", "my_var"),
+            ("dry dash run", "dry-run"),
+            ("dry hyphen run", "dry-run"),
+            ("if open brace close brace", "if { }"),
+            ("Read the URL.", "read the URL"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(apply_code_formatting(input), expected, "input: {:?}", input);
+        }
+    }
+}
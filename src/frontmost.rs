@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+
+/// Overrides detection, e.g. `CONVEY_FRONTMOST_APP=Slack`. Used by tests and
+/// on desktops where detection isn't possible.
+const OVERRIDE_NAME_VAR: &str = "CONVEY_FRONTMOST_APP";
+const OVERRIDE_BUNDLE_ID_VAR: &str = "CONVEY_FRONTMOST_BUNDLE_ID";
+
+/// The application that had focus when recording started, i.e. where the
+/// dictated text is going.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FrontmostApp {
+    pub name: String,
+    /// macOS bundle identifier (e.g. `com.tinyspeck.slackmacgap`); the window
+    /// class on Linux.
+    pub bundle_id: Option<String>,
+}
+
+impl FrontmostApp {
+    /// Case-insensitive match against the name or the bundle identifier.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+        self.name.eq_ignore_ascii_case(pattern)
+            || self
+                .bundle_id
+                .as_deref()
+                .is_some_and(|id| id.eq_ignore_ascii_case(pattern))
+    }
+}
+
+/// Best-effort lookup of the focused application. Returns `None` when it
+/// can't be determined, or when Convey itself is focused (recording started
+/// from the window rather than a hotkey).
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    if let Some(name) = std::env::var(OVERRIDE_NAME_VAR).ok().filter(|n| !n.is_empty()) {
+        return Some(FrontmostApp {
+            name,
+            bundle_id: std::env::var(OVERRIDE_BUNDLE_ID_VAR).ok().filter(|id| !id.is_empty()),
+        });
+    }
+
+    platform::frontmost_app()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::FrontmostApp;
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let app: *mut Object = msg_send![workspace, frontmostApplication];
+            if app.is_null() {
+                return None;
+            }
+
+            let pid: i32 = msg_send![app, processIdentifier];
+            if pid as u32 == std::process::id() {
+                return None;
+            }
+
+            let bundle_id = to_string(msg_send![app, bundleIdentifier]);
+            let name = to_string(msg_send![app, localizedName]).or_else(|| bundle_id.clone())?;
+            Some(FrontmostApp { name, bundle_id })
+        }
+    }
+
+    unsafe fn to_string(string: *mut Object) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![string, UTF8String];
+        if utf8.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    use super::FrontmostApp;
+
+    /// Wayland has no common protocol for this, so ask the compositors that
+    /// expose it (Hyprland, Sway); on X11 read the active window's class.
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            if let Some(app) = hyprland().or_else(sway) {
+                return Some(app);
+            }
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            return x11();
+        }
+        None
+    }
+
+    fn run(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn from_class(class: &str) -> Option<FrontmostApp> {
+        let class = class.trim();
+        (!class.is_empty()).then(|| FrontmostApp {
+            name: class.to_string(),
+            bundle_id: Some(class.to_string()),
+        })
+    }
+
+    fn hyprland() -> Option<FrontmostApp> {
+        let json: serde_json::Value =
+            serde_json::from_str(&run("hyprctl", &["activewindow", "-j"])?).ok()?;
+        from_class(json.get("class")?.as_str()?)
+    }
+
+    fn sway() -> Option<FrontmostApp> {
+        let tree: serde_json::Value =
+            serde_json::from_str(&run("swaymsg", &["-t", "get_tree"])?).ok()?;
+        let node = focused_node(&tree)?;
+        let class = node
+            .get("app_id")
+            .and_then(|v| v.as_str())
+            .or_else(|| node.pointer("/window_properties/class").and_then(|v| v.as_str()))?;
+        from_class(class)
+    }
+
+    fn focused_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+        if node.get("focused").and_then(|v| v.as_bool()) == Some(true) {
+            return Some(node);
+        }
+        ["nodes", "floating_nodes"]
+            .iter()
+            .filter_map(|key| node.get(key).and_then(|v| v.as_array()))
+            .flatten()
+            .find_map(focused_node)
+    }
+
+    /// `xprop -root _NET_ACTIVE_WINDOW` prints `... window id # 0x3a00007`,
+    /// and `xprop -id <id> WM_CLASS` prints `WM_CLASS(STRING) = "navigator", "firefox"`.
+    fn x11() -> Option<FrontmostApp> {
+        let active = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?;
+        let window_id = active.split_whitespace().next_back()?;
+        if window_id == "0x0" {
+            return None;
+        }
+
+        let class = run("xprop", &["-id", window_id, "WM_CLASS"])?;
+        let (_, values) = class.split_once('=')?;
+        let class = values.rsplit(',').next()?.trim().trim_matches('"');
+        from_class(class)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+mod platform {
+    use super::FrontmostApp;
+
+    pub fn frontmost_app() -> Option<FrontmostApp> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modes::{render_prompt, AppModeOverride};
+    use crate::storage::AppSettings;
+
+    fn app(name: &str, bundle_id: Option<&str>) -> FrontmostApp {
+        FrontmostApp {
+            name: name.to_string(),
+            bundle_id: bundle_id.map(str::to_string),
+        }
+    }
+
+    fn settings() -> AppSettings {
+        let app_modes = [("Slack", "bullets"), ("com.apple.mail", "email"), ("Notes", "deleted")]
+            .into_iter()
+            .map(|(app, mode)| AppModeOverride {
+                app: app.to_string(),
+                mode: mode.to_string(),
+            })
+            .collect();
+        AppSettings {
+            app_modes,
+            ..AppSettings::default()
+        }
+    }
+
+    // The only test touching the environment, so the variables can't race
+    #[test]
+    fn override_picks_the_app_and_its_mode() {
+        std::env::set_var(OVERRIDE_NAME_VAR, "Mail");
+        std::env::set_var(OVERRIDE_BUNDLE_ID_VAR, "com.apple.mail");
+        let mail = frontmost_app();
+        std::env::set_var(OVERRIDE_NAME_VAR, "Slack");
+        std::env::set_var(OVERRIDE_BUNDLE_ID_VAR, "");
+        let slack = frontmost_app();
+        std::env::remove_var(OVERRIDE_NAME_VAR);
+        std::env::remove_var(OVERRIDE_BUNDLE_ID_VAR);
+
+        let mail = mail.unwrap();
+        assert_eq!(mail, app("Mail", Some("com.apple.mail")));
+        let slack = slack.unwrap();
+        assert_eq!(slack, app("Slack", None));
+
+        let settings = settings();
+        assert_eq!(settings.mode_for_target(Some(&mail)).unwrap().id, "email");
+        assert_eq!(settings.mode_for_target(Some(&slack)).unwrap().id, "bullets");
+        assert_eq!(
+            render_prompt("Write a message for {app}.", Some(&slack.name)),
+            "Write a message for Slack."
+        );
+    }
+
+    #[test]
+    fn matches_name_or_bundle_id_ignoring_case() {
+        let slack = app("Slack", Some("com.tinyspeck.slackmacgap"));
+        assert!(slack.matches("slack"));
+        assert!(slack.matches(" Slack "));
+        assert!(slack.matches("COM.TINYSPECK.SLACKMACGAP"));
+        assert!(!slack.matches("Slack Beta"));
+        assert!(!slack.matches("com.tinyspeck"));
+        assert!(!app("Slack", None).matches("com.tinyspeck.slackmacgap"));
+    }
+
+    #[test]
+    fn mode_for_app_uses_the_mapping() {
+        let settings = settings();
+        assert_eq!(settings.mode_for_app(&app("slack", None)).unwrap().id, "bullets");
+        assert_eq!(
+            settings.mode_for_app(&app("Mail", Some("com.apple.mail"))).unwrap().id,
+            "email"
+        );
+        assert!(settings.mode_for_app(&app("Terminal", Some("com.apple.Terminal"))).is_none());
+        // A mapping to a mode that no longer exists is ignored
+        assert!(settings.mode_for_app(&app("Notes", None)).is_none());
+    }
+
+    #[test]
+    fn unmapped_apps_fall_back_to_the_active_mode() {
+        let mut settings = settings();
+        settings.active_mode = "git_commit".to_string();
+        let unknown = app("Unknown", Some("org.example.unknown"));
+
+        assert_eq!(settings.mode_for_target(Some(&unknown)).unwrap().id, "git_commit");
+        assert_eq!(settings.mode_for_target(Some(&app("Notes", None))).unwrap().id, "git_commit");
+        assert_eq!(settings.mode_for_target(None).unwrap().id, "git_commit");
+    }
+
+    #[test]
+    fn render_prompt_fills_in_the_app() {
+        assert_eq!(
+            render_prompt("Reply in {app} style. Keep it short for {app}.", Some("Slack")),
+            "Reply in Slack style. Keep it short for Slack."
+        );
+        assert_eq!(
+            render_prompt("Format for {app}.", None),
+            "Format for an unknown application."
+        );
+        assert_eq!(render_prompt("No variables.", Some("Slack")), "No variables.");
+    }
+}
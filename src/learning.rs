@@ -0,0 +1,188 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::diff::{word_diff, DiffOp};
+
+/// Longest phrase, in words, taken as a misrecognition. Longer edits are
+/// rewrites and say nothing about what whisper keeps getting wrong.
+const MAX_PHRASE_WORDS: usize = 4;
+
+/// How many corrected entries must share a substitution before it is suggested.
+pub const MIN_OCCURRENCES: usize = 2;
+
+/// A substitution the user keeps making by hand, offered as a replacement
+/// rule or a vocabulary entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// What whisper wrote, lowercased since replacement patterns ignore case.
+    pub heard: String,
+    pub corrected: String,
+    /// Number of entries in which the user made this correction.
+    pub count: usize,
+}
+
+/// Finds word-level substitutions that recur across `(original, corrected)`
+/// pairs, most frequent first. Each entry counts once per substitution.
+pub fn suggest<'a>(
+    corrections: impl IntoIterator<Item = (&'a str, &'a str)>,
+    min_count: usize,
+) -> Vec<Suggestion> {
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    for (original, corrected) in corrections {
+        let unique: HashSet<(String, String)> = substitutions(original, corrected).into_iter().collect();
+        for key in unique {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= min_count)
+        .map(|((heard, corrected), count)| Suggestion {
+            heard,
+            corrected,
+            count,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.heard.cmp(&b.heard)));
+    suggestions
+}
+
+/// Replaced runs of words: removed words directly followed by added ones
+/// (or the other way round), between unchanged words.
+fn substitutions(original: &str, corrected: &str) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    let mut removed: Vec<String> = Vec::new();
+    let mut added: Vec<String> = Vec::new();
+
+    let ops = word_diff(original, corrected);
+    for op in ops.into_iter().chain(std::iter::once(DiffOp::Equal(String::new()))) {
+        match op {
+            DiffOp::Removed(word) => removed.push(word),
+            DiffOp::Added(word) => added.push(word),
+            DiffOp::Equal(_) => {
+                if let Some(pair) = substitution(&removed, &added) {
+                    found.push(pair);
+                }
+                removed.clear();
+                added.clear();
+            }
+        }
+    }
+
+    found
+}
+
+fn substitution(mut removed: &[String], mut added: &[String]) -> Option<(String, String)> {
+    // The diff compares words exactly, so a word whose punctuation changed
+    // ("today," -> "today.") lands in the run; trim those off both ends.
+    let same = |a: &String, b: &String| trim_punctuation(a).eq_ignore_ascii_case(trim_punctuation(b));
+    while let (Some(a), Some(b)) = (removed.first(), added.first()) {
+        if !same(a, b) {
+            break;
+        }
+        removed = &removed[1..];
+        added = &added[1..];
+    }
+    while let (Some(a), Some(b)) = (removed.last(), added.last()) {
+        if !same(a, b) {
+            break;
+        }
+        removed = &removed[..removed.len() - 1];
+        added = &added[..added.len() - 1];
+    }
+
+    if removed.is_empty()
+        || added.is_empty()
+        || removed.len() > MAX_PHRASE_WORDS
+        || added.len() > MAX_PHRASE_WORDS
+    {
+        return None;
+    }
+
+    let heard = trim_punctuation(&removed.join(" ")).to_lowercase();
+    let corrected = trim_punctuation(&added.join(" ")).to_string();
+    // Only punctuation changed, e.g. "today," -> "today."
+    if heard.is_empty() || corrected.is_empty() || heard == corrected {
+        return None;
+    }
+    Some((heard, corrected))
+}
+
+/// Strips sentence punctuation whisper attached to the phrase, keeping
+/// symbols that are part of it ("C++", "Node.js").
+fn trim_punctuation(phrase: &str) -> &str {
+    phrase.trim_matches(|c: char| matches!(c, ',' | '.' | ';' | ':' | '!' | '?' | '"' | '\''))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(heard: &str, corrected: &str, count: usize) -> Suggestion {
+        Suggestion {
+            heard: heard.to_string(),
+            corrected: corrected.to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn substitutions_in_one_correction() {
+        // Original, corrected, and the (heard, corrected) pairs found
+        type Case = (&'static str, &'static str, &'static [(&'static str, &'static str)]);
+        let cases: &[Case] = &[
+            ("push it to get hub", "push it to GitHub", &[("get hub", "GitHub")]),
+            ("ask Jon today.", "ask John today.", &[("jon", "John")]),
+            ("ask Jon, then Jon.", "ask John, then John.", &[("jon", "John"), ("jon", "John")]),
+            ("use cargo clip e now", "use cargo clippy now", &[("clip e", "clippy")]),
+            // Punctuation that moved with the word is trimmed
+            ("meet cube nets today,", "meet Kubernetes today.", &[("cube nets", "Kubernetes")]),
+            ("learn c plus plus", "learn C++", &[("c plus plus", "C++")]),
+            // Only punctuation or nothing changed
+            ("see you today,", "see you today.", &[]),
+            ("same text", "same text", &[]),
+            // Added or removed words aren't substitutions
+            ("send it", "send it now", &[]),
+            ("send it now please", "send it please", &[]),
+            // Longer edits are rewrites
+            (
+                "we should probably see the new film",
+                "let us watch a movie together tonight",
+                &[],
+            ),
+        ];
+        for (original, corrected, expected) in cases {
+            let expected: Vec<(String, String)> =
+                expected.iter().map(|(h, c)| (h.to_string(), c.to_string())).collect();
+            assert_eq!(substitutions(original, corrected), expected, "{:?}", original);
+        }
+    }
+
+    #[test]
+    fn suggests_recurring_substitutions() {
+        let corrections = [
+            ("push to get hub", "push to GitHub"),
+            ("open get hub, please", "open GitHub, please"),
+            ("Get hub is down", "GitHub is down"),
+            ("ask Jon", "ask John"),
+            ("ask Jon and Jon", "ask John and John"),
+            ("call Ann", "call Anne"),
+        ];
+        assert_eq!(
+            suggest(corrections, MIN_OCCURRENCES),
+            [suggestion("get hub", "GitHub", 3), suggestion("jon", "John", 2)]
+        );
+        // Repeats within one entry count once, so Jon needs a third entry
+        assert_eq!(suggest(corrections, 3), [suggestion("get hub", "GitHub", 3)]);
+        assert_eq!(suggest(corrections, 1).len(), 3);
+    }
+
+    #[test]
+    fn rewrites_are_not_suggested() {
+        let rewrite = (
+            "I think we could maybe do it on Friday",
+            "Let's schedule it for the end of next week",
+        );
+        assert!(suggest([rewrite, rewrite, rewrite], MIN_OCCURRENCES).is_empty());
+    }
+}
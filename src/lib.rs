@@ -1,10 +1,25 @@
 mod ai;
 mod audio;
+mod binaries;
+mod cli;
 mod clipboard;
+mod commands;
 mod database;
+mod diff;
+mod disfluency;
+mod export;
+mod formatting;
+mod frontmost;
+mod learning;
+mod migrations;
+mod modes;
+mod normalize;
 mod notch;
+mod redaction;
+mod replacements;
 mod services;
 mod sound;
+mod stats;
 mod storage;
 mod ui;
 mod whisper;
@@ -18,15 +33,15 @@ use clipboard::ClipboardManager;
 use database::Database;
 use directories::ProjectDirs;
 use services::{
-    clipboard::ClipboardService, history::HistoryService, recorder::RecorderService,
-    settings::SettingsService, AppServices,
+    clipboard::ClipboardService, history::HistoryService, preview::PreviewService,
+    recorder::RecorderService, settings::SettingsService, AppServices,
 };
 use std::fs;
+use std::path::PathBuf;
 use storage::SecureStorage;
 
-pub fn run() -> iced::Result {
-    env_logger::init();
-
+/// Where settings and history live, creating the directories on first run.
+fn data_paths() -> (PathBuf, PathBuf) {
     let project_dirs = ProjectDirs::from("com", "narennaik", "Convey")
         .expect("Failed to resolve project directories");
 
@@ -38,7 +53,27 @@ pub fn run() -> iced::Result {
     if let Err(e) = fs::create_dir_all(project_dirs.data_dir()) {
         panic!("Failed to create data directory: {}", e);
     }
-    let db_path = data_path.join("transcriptions.db");
+    (config_path, data_path.join("transcriptions.db"))
+}
+
+/// Runs `convey export` with the arguments after "export" and returns the
+/// process exit code.
+pub fn run_export(args: &[String]) -> i32 {
+    env_logger::init();
+    let (_, db_path) = data_paths();
+    match cli::export(args, &db_path) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("convey export: {:#}", e);
+            1
+        }
+    }
+}
+
+pub fn run() -> iced::Result {
+    env_logger::init();
+
+    let (config_path, db_path) = data_paths();
 
     let storage = SecureStorage::new(config_path).expect("Failed to initialize storage");
     let database = Database::new(db_path).expect("Failed to initialize database");
@@ -48,7 +83,21 @@ pub fn run() -> iced::Result {
         SettingsService::new(storage),
         HistoryService::new(database),
         ClipboardService::new(ClipboardManager::new()),
+        PreviewService::new(),
     );
 
+    // Prune old history before the UI loads it; pinned entries are kept
+    let retention = services
+        .settings
+        .load()
+        .ok()
+        .and_then(|settings| settings.history_retention_days);
+    if let Some(days) = retention {
+        match services.history.apply_retention(days) {
+            Ok(deleted) => log::info!("Retention removed {} entries older than {} days", deleted, days),
+            Err(e) => log::warn!("Failed to apply history retention: {}", e),
+        }
+    }
+
     ui::run(services)
 }
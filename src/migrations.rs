@@ -0,0 +1,512 @@
+use anyhow::{bail, Context, Result};
+use log::info;
+use rusqlite::{Connection, Transaction};
+use std::path::{Path, PathBuf};
+
+/// One schema change. `user_version` holds the number of migrations applied,
+/// so migrations are only ever appended, never edited or reordered.
+struct Migration {
+    description: &'static str,
+    apply: fn(&Transaction) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create or adopt the unversioned schema",
+        apply: adopt_unversioned_schema,
+    },
+    Migration {
+        description: "Add full-text search",
+        apply: add_full_text_search,
+    },
+    Migration {
+        description: "Index history for filtered, paginated queries",
+        apply: add_history_indexes,
+    },
+    Migration {
+        description: "Record pipeline timings",
+        apply: add_timings,
+    },
+    Migration {
+        description: "Add tags and pinned entries",
+        apply: add_tags_and_pins,
+    },
+    Migration {
+        description: "Keep revisions of edited entries",
+        apply: add_revisions,
+    },
+];
+
+/// Brings the database up to the latest schema. Each migration runs in its
+/// own transaction together with the version bump, so a failure leaves the
+/// file at the last good version. An existing database is first copied next
+/// to `db_path` (see `backup_path`).
+pub fn migrate(conn: &mut Connection, db_path: &Path) -> Result<()> {
+    let version = user_version(conn)?;
+    let latest = MIGRATIONS.len();
+    if version > latest {
+        bail!(
+            "The history database is at schema version {} but this version of Convey only \
+             knows {}; it was written by a newer Convey",
+            version,
+            latest
+        );
+    }
+    if version == latest {
+        return Ok(());
+    }
+
+    if has_tables(conn)? {
+        let backup = backup_path(db_path, version);
+        // An older backup from the same version holds the same schema
+        if backup.exists() {
+            std::fs::remove_file(&backup)
+                .with_context(|| format!("Failed to replace the old backup {:?}", backup))?;
+        }
+        info!("Backing up the history database to {:?} before migrating", backup);
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .with_context(|| format!("Failed to back up the database to {:?}", backup))?;
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let target = index + 1;
+        info!("Migrating database to version {}: {}", target, migration.description);
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)
+            .with_context(|| format!("Migration {} ({}) failed", target, migration.description))?;
+        tx.pragma_update(None, "user_version", target as i64)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// `history.db` at version 3 is backed up as `history.db.v3.bak`.
+fn backup_path(db_path: &Path, version: usize) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    db_path.with_file_name(name)
+}
+
+fn user_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version.max(0) as usize)
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Version 1. Databases from before versioning have the original
+/// transcriptions table plus whichever columns and tables the releases
+/// they went through added on startup, so every step checks first.
+fn adopt_unversioned_schema(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS transcriptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            text TEXT NOT NULL,
+            processed_text TEXT,
+            language TEXT,
+            duration_ms INTEGER,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    ensure_column(tx, "transcriptions", "avg_confidence", "REAL")?;
+    ensure_column(tx, "transcriptions", "segments", "TEXT")?;
+    ensure_column(tx, "transcriptions", "mode", "TEXT")?;
+    ensure_column(tx, "transcriptions", "ai_status", "TEXT")?;
+    ensure_column(tx, "transcriptions", "ai_error", "TEXT")?;
+    ensure_column(tx, "transcriptions", "app", "TEXT")?;
+    ensure_column(tx, "transcriptions", "applied_replacements", "TEXT")?;
+    // The user's corrected text, once they fix an entry by hand
+    ensure_column(tx, "transcriptions", "edited_text", "TEXT")?;
+    ensure_column(tx, "transcriptions", "pre_cleanup_text", "TEXT")?;
+
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS replacements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pattern TEXT NOT NULL,
+            replacement TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS ai_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transcription_id INTEGER,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            cost_usd REAL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Version 2. An external-content FTS5 index over every text an entry has,
+/// kept in sync by triggers and filled from the existing rows.
+fn add_full_text_search(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE transcriptions_fts USING fts5(
+            text, processed_text, edited_text,
+            content = 'transcriptions',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER transcriptions_fts_insert AFTER INSERT ON transcriptions BEGIN
+            INSERT INTO transcriptions_fts (rowid, text, processed_text, edited_text)
+            VALUES (new.id, new.text, new.processed_text, new.edited_text);
+        END;
+
+        CREATE TRIGGER transcriptions_fts_delete AFTER DELETE ON transcriptions BEGIN
+            INSERT INTO transcriptions_fts (transcriptions_fts, rowid, text, processed_text, edited_text)
+            VALUES ('delete', old.id, old.text, old.processed_text, old.edited_text);
+        END;
+
+        CREATE TRIGGER transcriptions_fts_update
+        AFTER UPDATE OF text, processed_text, edited_text ON transcriptions BEGIN
+            INSERT INTO transcriptions_fts (transcriptions_fts, rowid, text, processed_text, edited_text)
+            VALUES ('delete', old.id, old.text, old.processed_text, old.edited_text);
+            INSERT INTO transcriptions_fts (rowid, text, processed_text, edited_text)
+            VALUES (new.id, new.text, new.processed_text, new.edited_text);
+        END;
+
+        INSERT INTO transcriptions_fts (transcriptions_fts) VALUES ('rebuild');",
+    )?;
+    Ok(())
+}
+
+/// Version 3. History pages are read newest first by `(created_at, id)`,
+/// optionally narrowed by one of the filter columns.
+fn add_history_indexes(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX idx_transcriptions_created ON transcriptions (created_at, id);
+        CREATE INDEX idx_transcriptions_language ON transcriptions (language, created_at, id);
+        CREATE INDEX idx_transcriptions_mode ON transcriptions (mode, created_at, id);
+        CREATE INDEX idx_transcriptions_app ON transcriptions (app, created_at, id);
+        CREATE INDEX idx_transcriptions_duration ON transcriptions (duration_ms);
+        CREATE INDEX idx_transcriptions_processed ON transcriptions (created_at, id)
+            WHERE processed_text IS NOT NULL;",
+    )?;
+    Ok(())
+}
+
+/// Version 4. Per-stage timings in milliseconds and the whisper model used,
+/// for spotting slow machines and models.
+fn add_timings(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE transcriptions ADD COLUMN whisper_model TEXT;
+        ALTER TABLE transcriptions ADD COLUMN whisper_ms INTEGER;
+        ALTER TABLE transcriptions ADD COLUMN ai_ms INTEGER;
+        ALTER TABLE transcriptions ADD COLUMN paste_ms INTEGER;
+        ALTER TABLE transcriptions ADD COLUMN total_ms INTEGER;",
+    )?;
+    Ok(())
+}
+
+/// Version 5. Tags are shared between entries; a trigger drops an entry's
+/// links when it is deleted, since foreign keys aren't enforced.
+fn add_tags_and_pins(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE transcriptions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+
+        CREATE TABLE transcription_tags (
+            transcription_id INTEGER NOT NULL REFERENCES transcriptions (id),
+            tag_id INTEGER NOT NULL REFERENCES tags (id),
+            PRIMARY KEY (transcription_id, tag_id)
+        );
+        CREATE INDEX idx_transcription_tags_tag ON transcription_tags (tag_id, transcription_id);
+
+        CREATE TRIGGER transcription_tags_delete AFTER DELETE ON transcriptions BEGIN
+            DELETE FROM transcription_tags WHERE transcription_id = old.id;
+        END;",
+    )?;
+    Ok(())
+}
+
+/// Version 6. Every text the user saves for an entry, and AI results that
+/// replace an edit, oldest first. The latest one is mirrored in
+/// `transcriptions.edited_text` unless it matches the produced text.
+fn add_revisions(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE transcription_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transcription_id INTEGER NOT NULL REFERENCES transcriptions (id),
+            text TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_transcription_revisions_entry
+            ON transcription_revisions (transcription_id, id);
+
+        CREATE TRIGGER transcription_revisions_delete AFTER DELETE ON transcriptions BEGIN
+            DELETE FROM transcription_revisions WHERE transcription_id = old.id;
+        END;",
+    )?;
+    Ok(())
+}
+
+/// Adds a column to an existing table if an older database file lacks it.
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )
+        .with_context(|| format!("Failed to add column {}.{}", table, column))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Columns releases before versioning added to the original table.
+    const AD_HOC_COLUMNS: &[(&str, &str)] = &[
+        ("avg_confidence", "REAL"),
+        ("segments", "TEXT"),
+        ("mode", "TEXT"),
+        ("ai_status", "TEXT"),
+        ("ai_error", "TEXT"),
+        ("app", "TEXT"),
+        ("applied_replacements", "TEXT"),
+        ("edited_text", "TEXT"),
+        ("pre_cleanup_text", "TEXT"),
+    ];
+
+    fn db_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("convey-migrate-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("history.db")
+    }
+
+    /// Object names and `transcriptions` columns, which is what queries rely
+    /// on; column order differs between upgraded and fresh files.
+    fn schema(conn: &Connection) -> (BTreeSet<String>, BTreeSet<String>) {
+        let objects = conn
+            .prepare("SELECT type || ' ' || name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let columns = conn
+            .prepare("SELECT name || ' ' || type FROM pragma_table_info('transcriptions')")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        (objects, columns)
+    }
+
+    fn latest_schema() -> (BTreeSet<String>, BTreeSet<String>) {
+        let path = db_path("fresh");
+        let mut conn = Connection::open(&path).unwrap();
+        migrate(&mut conn, &path).unwrap();
+        schema(&conn)
+    }
+
+    /// A database as the release before versioning left it, with the given
+    /// ad-hoc columns and one entry.
+    fn unversioned(path: &Path, columns: &[(&str, &str)], with_tables: bool) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE transcriptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                text TEXT NOT NULL,
+                processed_text TEXT,
+                language TEXT,
+                duration_ms INTEGER,
+                created_at TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        for (column, definition) in columns {
+            conn.execute_batch(&format!(
+                "ALTER TABLE transcriptions ADD COLUMN {} {}",
+                column, definition
+            ))
+            .unwrap();
+        }
+        if with_tables {
+            conn.execute_batch(
+                "CREATE TABLE replacements (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    pattern TEXT NOT NULL,
+                    replacement TEXT NOT NULL,
+                    enabled INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL
+                );
+                INSERT INTO replacements (pattern, replacement, created_at)
+                VALUES ('teh', 'the', '2024-01-01T00:00:00Z');",
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO transcriptions (text, processed_text, language, duration_ms, created_at)
+             VALUES ('remember the zucchini', 'Remember the zucchini.', 'en', 1200,
+                     '2024-01-02T03:04:05Z')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    /// A database at `version`, built by the migrations of that time, with
+    /// one entry.
+    fn versioned(path: &Path, version: usize) -> Connection {
+        let mut conn = Connection::open(path).unwrap();
+        for (index, migration) in MIGRATIONS.iter().take(version).enumerate() {
+            let tx = conn.transaction().unwrap();
+            (migration.apply)(&tx).unwrap();
+            tx.pragma_update(None, "user_version", (index + 1) as i64).unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute(
+            "INSERT INTO transcriptions (text, processed_text, language, duration_ms, created_at)
+             VALUES ('remember the zucchini', 'Remember the zucchini.', 'en', 1200,
+                     '2024-01-02T03:04:05Z')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn assert_upgraded(mut conn: Connection, path: &Path, from_version: usize) {
+        migrate(&mut conn, path).unwrap();
+
+        assert_eq!(user_version(&conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(schema(&conn), latest_schema(), "upgraded from v{}", from_version);
+
+        let (text, processed): (String, String) = conn
+            .query_row("SELECT text, processed_text FROM transcriptions", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(text, "remember the zucchini");
+        assert_eq!(processed, "Remember the zucchini.");
+
+        // Rows from before the index existed are searchable
+        let hits: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM transcriptions_fts WHERE transcriptions_fts MATCH 'zucchini'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hits, 1, "upgraded from v{}", from_version);
+
+        let backup = path.with_file_name(format!("history.db.v{}.bak", from_version));
+        let backup = Connection::open(backup).unwrap();
+        assert_eq!(user_version(&backup).unwrap(), from_version);
+        let rows: i64 = backup
+            .query_row("SELECT COUNT(*) FROM transcriptions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn fresh_database_gets_the_latest_schema_without_a_backup() {
+        let path = db_path("new");
+        let mut conn = Connection::open(&path).unwrap();
+        migrate(&mut conn, &path).unwrap();
+
+        assert_eq!(user_version(&conn).unwrap(), MIGRATIONS.len());
+        assert!(!path.with_file_name("history.db.v0.bak").exists());
+        // Running again is a no-op
+        migrate(&mut conn, &path).unwrap();
+    }
+
+    #[test]
+    fn upgrades_the_original_schema() {
+        let path = db_path("original");
+        assert_upgraded(unversioned(&path, &[], false), &path, 0);
+    }
+
+    #[test]
+    fn upgrades_the_unversioned_schema_with_every_ad_hoc_column() {
+        let path = db_path("all-columns");
+        let conn = unversioned(&path, AD_HOC_COLUMNS, true);
+        assert_upgraded(conn, &path, 0);
+
+        let conn = Connection::open(&path).unwrap();
+        let pattern: String = conn
+            .query_row("SELECT pattern FROM replacements", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(pattern, "teh");
+    }
+
+    #[test]
+    fn upgrades_the_unversioned_schema_with_each_ad_hoc_column() {
+        for column in AD_HOC_COLUMNS {
+            let path = db_path(&format!("only-{}", column.0));
+            assert_upgraded(unversioned(&path, &[*column], false), &path, 0);
+        }
+    }
+
+    #[test]
+    fn upgrades_the_unversioned_schema_missing_each_ad_hoc_column() {
+        for missing in AD_HOC_COLUMNS {
+            let path = db_path(&format!("without-{}", missing.0));
+            let columns: Vec<(&str, &str)> = AD_HOC_COLUMNS
+                .iter()
+                .copied()
+                .filter(|column| column != missing)
+                .collect();
+            assert_upgraded(unversioned(&path, &columns, true), &path, 0);
+        }
+    }
+
+    #[test]
+    fn upgrades_every_earlier_version() {
+        for version in 1..MIGRATIONS.len() {
+            let path = db_path(&format!("v{}", version));
+            assert_upgraded(versioned(&path, version), &path, version);
+        }
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let path = db_path("newer");
+        let mut conn = versioned(&path, MIGRATIONS.len());
+        conn.pragma_update(None, "user_version", (MIGRATIONS.len() + 1) as i64)
+            .unwrap();
+
+        let err = migrate(&mut conn, &path).unwrap_err().to_string();
+        assert!(err.contains("written by a newer Convey"), "{}", err);
+        assert_eq!(user_version(&conn).unwrap(), MIGRATIONS.len() + 1);
+        assert!(!path
+            .with_file_name(format!("history.db.v{}.bak", MIGRATIONS.len() + 1))
+            .exists());
+    }
+}
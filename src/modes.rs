@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+/// How a mode turns a transcript into the final text.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeKind {
+    /// Rewritten by the AI provider with the mode's prompt.
+    #[default]
+    Ai,
+    /// Spoken code: casing commands and symbol words become identifiers and
+    /// operators locally ("snake case get user name" -> "get_user_name").
+    Code,
+}
+
+/// A named way of post-processing a dictation, e.g. "Email" or "Bullet points".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProcessingMode {
+    pub id: String,
+    pub name: String,
+    /// System prompt sent to the AI provider.
+    pub prompt: String,
+    /// Overrides `AppSettings::ai_model` when set.
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+    /// Global hotkey that records straight into this mode, e.g. "ctrl+alt+KeyE".
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    /// Overrides `AppSettings::spoken_formatting` for this mode when set.
+    #[serde(default)]
+    pub spoken_formatting: Option<bool>,
+    /// Overrides `AppSettings::inverse_text_normalization` for this mode when set.
+    #[serde(default)]
+    pub inverse_text_normalization: Option<bool>,
+    #[serde(default)]
+    pub kind: ModeKind,
+}
+
+/// Records into `mode` whenever dictating into `app`, matched against the
+/// application name or bundle identifier (e.g. "Slack", "com.apple.mail").
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppModeOverride {
+    pub app: String,
+    pub mode: String,
+}
+
+/// Fills in the `{app}` variable of a mode prompt with the target application.
+pub fn render_prompt(prompt: &str, app: Option<&str>) -> String {
+    prompt.replace("{app}", app.unwrap_or("an unknown application"))
+}
+
+fn default_temperature() -> f32 {
+    0.3
+}
+
+impl ProcessingMode {
+    fn new(id: &str, name: &str, prompt: &str, temperature: f32) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            prompt: prompt.to_string(),
+            model: None,
+            temperature,
+            hotkey: None,
+            spoken_formatting: None,
+            inverse_text_normalization: None,
+            kind: ModeKind::Ai,
+        }
+    }
+
+    pub fn is_code(&self) -> bool {
+        self.kind == ModeKind::Code
+    }
+
+    /// The built-in code dictation mode. Its prompt is only used when an
+    /// entry is explicitly re-run through AI from history.
+    pub fn code() -> Self {
+        Self {
+            kind: ModeKind::Code,
+            ..ProcessingMode::new(
+                "code",
+                "Code",
+                "The text is dictated source code. Fix obvious speech recognition errors in \
+                 identifiers, keywords and symbols. Output only the code.",
+                0.1,
+            )
+        }
+    }
+
+    pub fn defaults() -> Vec<ProcessingMode> {
+        vec![
+            ProcessingMode::new(
+                "clean_up",
+                "Clean up",
+                "You are a helpful assistant that cleans up and improves transcribed text. \
+                 Fix grammar, punctuation, and formatting while preserving the original meaning.",
+                0.3,
+            ),
+            ProcessingMode::new(
+                "email",
+                "Email",
+                "Rewrite the transcribed text as a clear, friendly email body. \
+                 Keep the sender's intent and facts. Do not add a subject line or signature.",
+                0.5,
+            ),
+            ProcessingMode::new(
+                "bullets",
+                "Bullet points",
+                "Turn the transcribed text into a concise bulleted list using '- ' bullets. \
+                 One idea per bullet. Output only the list.",
+                0.3,
+            ),
+            ProcessingMode::new(
+                "git_commit",
+                "Git commit",
+                "Format the transcribed text as a git commit message: an imperative subject \
+                 line of at most 72 characters, a blank line, then a wrapped body if needed. \
+                 Output only the message.",
+                0.2,
+            ),
+            ProcessingMode::new(
+                "german",
+                "German",
+                "Translate the transcribed text into natural German. Output only the translation.",
+                0.3,
+            ),
+            ProcessingMode::code(),
+        ]
+    }
+}
+
+impl std::fmt::Display for ProcessingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
@@ -0,0 +1,982 @@
+/// Role of a number word when reading a number left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// 0-9
+    Unit,
+    /// A complete number below 100 that takes no further units: "twelve",
+    /// "veintitrés".
+    Teen,
+    /// 20, 30, ... 90
+    Tens,
+    /// A whole number of hundreds in one word: "doscientos".
+    Hundreds,
+    /// Multiplies what precedes it by 100: "hundred".
+    Hundred,
+    /// thousand, million, ...
+    Scale,
+    /// "and" in "one hundred and five", "y" in "treinta y cinco".
+    Connector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    /// `$100`
+    Prefix,
+    /// `100 €`
+    Suffix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clock {
+    /// "three thirty pm" -> "3:30 PM"
+    AmPm,
+    /// "drei uhr dreißig" -> "3:30 Uhr": the spoken word and how it's written.
+    Suffix(&'static str, &'static str),
+    None,
+}
+
+type Words = &'static [(&'static str, u64)];
+
+struct Locale {
+    units: Words,
+    teens: Words,
+    tens: Words,
+    hundreds: Words,
+    hundred: &'static [&'static str],
+    scales: Words,
+    /// The connector word and the kinds it may follow.
+    connector: Option<(&'static str, &'static [Kind])>,
+    /// "hundert", "mil": hundred and thousand on their own mean one of them.
+    implicit_one: bool,
+    /// Number words are written as one word ("fünfundzwanzig").
+    compounds: bool,
+    /// Informal zero in decimals and minutes ("three oh five").
+    oh: Option<&'static str>,
+    decimal_words: &'static [&'static str],
+    decimal_separator: char,
+    group_separator: char,
+    percent: &'static [&'static str],
+    /// "25 %" rather than "25%".
+    percent_spaced: bool,
+    currencies: &'static [(&'static str, &'static str, Placement)],
+    /// Minor unit, read as "and fifty cents" after the currency.
+    cents: &'static [&'static str],
+    measures: &'static [(&'static str, &'static str)],
+    clock: Clock,
+    /// Lowercase month names, for "March fifth" -> "March 5". Empty when
+    /// dates aren't handled.
+    months: &'static [&'static str],
+    /// Months that are also ordinary words ("I may second that"). Written
+    /// lowercase they only count before a two-word day or a year.
+    verb_months: &'static [&'static str],
+    ordinals: Words,
+}
+
+const ENGLISH: Locale = Locale {
+    units: &[
+        ("zero", 0), ("one", 1), ("two", 2), ("three", 3), ("four", 4),
+        ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
+    ],
+    teens: &[
+        ("ten", 10), ("eleven", 11), ("twelve", 12), ("thirteen", 13), ("fourteen", 14),
+        ("fifteen", 15), ("sixteen", 16), ("seventeen", 17), ("eighteen", 18), ("nineteen", 19),
+    ],
+    tens: &[
+        ("twenty", 20), ("thirty", 30), ("forty", 40), ("fifty", 50),
+        ("sixty", 60), ("seventy", 70), ("eighty", 80), ("ninety", 90),
+    ],
+    hundreds: &[],
+    hundred: &["hundred"],
+    scales: &[("thousand", 1_000), ("million", 1_000_000), ("billion", 1_000_000_000)],
+    connector: Some(("and", &[Kind::Hundred, Kind::Scale])),
+    implicit_one: false,
+    compounds: false,
+    oh: Some("oh"),
+    decimal_words: &["point"],
+    decimal_separator: '.',
+    group_separator: ',',
+    percent: &["percent", "per cent"],
+    percent_spaced: false,
+    currencies: &[
+        ("dollars", "$", Placement::Prefix),
+        ("dollar", "$", Placement::Prefix),
+        ("euros", "€", Placement::Prefix),
+        ("euro", "€", Placement::Prefix),
+        ("yen", "¥", Placement::Prefix),
+    ],
+    cents: &["cents", "cent"],
+    measures: &[
+        ("kilometers per hour", "km/h"), ("kilometres per hour", "km/h"), ("miles per hour", "mph"),
+        ("kilometers", "km"), ("kilometres", "km"), ("kilometer", "km"), ("kilometre", "km"),
+        ("meters", "m"), ("metres", "m"), ("meter", "m"), ("metre", "m"),
+        ("centimeters", "cm"), ("centimetres", "cm"), ("centimeter", "cm"), ("centimetre", "cm"),
+        ("millimeters", "mm"), ("millimetres", "mm"), ("millimeter", "mm"), ("millimetre", "mm"),
+        ("kilograms", "kg"), ("kilogram", "kg"), ("grams", "g"), ("gram", "g"),
+        ("terabytes", "TB"), ("gigabytes", "GB"), ("megabytes", "MB"),
+        ("degrees celsius", "°C"), ("degrees fahrenheit", "°F"), ("degrees", "°"),
+    ],
+    clock: Clock::AmPm,
+    months: &[
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ],
+    verb_months: &["march", "may"],
+    ordinals: &[
+        ("first", 1), ("second", 2), ("third", 3), ("fourth", 4), ("fifth", 5),
+        ("sixth", 6), ("seventh", 7), ("eighth", 8), ("ninth", 9), ("tenth", 10),
+        ("eleventh", 11), ("twelfth", 12), ("thirteenth", 13), ("fourteenth", 14),
+        ("fifteenth", 15), ("sixteenth", 16), ("seventeenth", 17), ("eighteenth", 18),
+        ("nineteenth", 19), ("twentieth", 20), ("thirtieth", 30),
+    ],
+};
+
+const GERMAN: Locale = Locale {
+    units: &[
+        ("null", 0), ("eins", 1), ("ein", 1), ("eine", 1), ("zwei", 2), ("drei", 3),
+        ("vier", 4), ("fünf", 5), ("sechs", 6), ("sieben", 7), ("acht", 8), ("neun", 9),
+    ],
+    teens: &[
+        ("zehn", 10), ("elf", 11), ("zwölf", 12), ("dreizehn", 13), ("vierzehn", 14),
+        ("fünfzehn", 15), ("sechzehn", 16), ("siebzehn", 17), ("achtzehn", 18), ("neunzehn", 19),
+    ],
+    tens: &[
+        ("zwanzig", 20), ("dreißig", 30), ("dreissig", 30), ("vierzig", 40), ("fünfzig", 50),
+        ("sechzig", 60), ("siebzig", 70), ("achtzig", 80), ("neunzig", 90),
+    ],
+    hundreds: &[],
+    hundred: &["hundert"],
+    scales: &[
+        ("tausend", 1_000),
+        ("million", 1_000_000), ("millionen", 1_000_000),
+        ("milliarde", 1_000_000_000), ("milliarden", 1_000_000_000),
+    ],
+    connector: Some(("und", &[Kind::Unit])),
+    implicit_one: true,
+    compounds: true,
+    oh: None,
+    decimal_words: &["komma"],
+    decimal_separator: ',',
+    group_separator: '.',
+    percent: &["prozent"],
+    percent_spaced: true,
+    currencies: &[("euro", "€", Placement::Suffix), ("dollar", "$", Placement::Suffix)],
+    cents: &[],
+    measures: &[
+        ("kilometer pro stunde", "km/h"), ("stundenkilometer", "km/h"),
+        ("kilometer", "km"), ("meter", "m"), ("zentimeter", "cm"), ("millimeter", "mm"),
+        ("kilogramm", "kg"), ("gramm", "g"),
+        ("grad celsius", "°C"), ("grad", "°"),
+    ],
+    clock: Clock::Suffix("uhr", "Uhr"),
+    months: &[],
+    verb_months: &[],
+    ordinals: &[],
+};
+
+const SPANISH: Locale = Locale {
+    units: &[
+        ("cero", 0), ("uno", 1), ("un", 1), ("una", 1), ("dos", 2), ("tres", 3),
+        ("cuatro", 4), ("cinco", 5), ("seis", 6), ("siete", 7), ("ocho", 8), ("nueve", 9),
+    ],
+    teens: &[
+        ("diez", 10), ("once", 11), ("doce", 12), ("trece", 13), ("catorce", 14), ("quince", 15),
+        ("dieciséis", 16), ("dieciseis", 16), ("diecisiete", 17), ("dieciocho", 18),
+        ("diecinueve", 19), ("veintiuno", 21), ("veintiún", 21), ("veintiuna", 21),
+        ("veintidós", 22), ("veintidos", 22), ("veintitrés", 23), ("veintitres", 23),
+        ("veinticuatro", 24), ("veinticinco", 25), ("veintiséis", 26), ("veintiseis", 26),
+        ("veintisiete", 27), ("veintiocho", 28), ("veintinueve", 29),
+    ],
+    tens: &[
+        ("veinte", 20), ("treinta", 30), ("cuarenta", 40), ("cincuenta", 50),
+        ("sesenta", 60), ("setenta", 70), ("ochenta", 80), ("noventa", 90),
+    ],
+    hundreds: &[
+        ("cien", 100), ("ciento", 100), ("doscientos", 200), ("doscientas", 200),
+        ("trescientos", 300), ("trescientas", 300), ("cuatrocientos", 400),
+        ("cuatrocientas", 400), ("quinientos", 500), ("quinientas", 500),
+        ("seiscientos", 600), ("seiscientas", 600), ("setecientos", 700),
+        ("setecientas", 700), ("ochocientos", 800), ("ochocientas", 800),
+        ("novecientos", 900), ("novecientas", 900),
+    ],
+    hundred: &[],
+    scales: &[("mil", 1_000), ("millón", 1_000_000), ("millon", 1_000_000), ("millones", 1_000_000)],
+    connector: Some(("y", &[Kind::Tens])),
+    implicit_one: true,
+    compounds: false,
+    oh: None,
+    decimal_words: &["coma"],
+    decimal_separator: ',',
+    group_separator: '.',
+    percent: &["por ciento"],
+    percent_spaced: true,
+    currencies: &[
+        ("euros", "€", Placement::Suffix),
+        ("euro", "€", Placement::Suffix),
+        ("dólares", "$", Placement::Suffix),
+        ("dolares", "$", Placement::Suffix),
+        ("dólar", "$", Placement::Suffix),
+        ("pesos", "$", Placement::Suffix),
+    ],
+    cents: &[],
+    measures: &[
+        ("kilómetros por hora", "km/h"), ("kilometros por hora", "km/h"),
+        ("kilómetros", "km"), ("kilometros", "km"), ("kilómetro", "km"),
+        ("metros", "m"), ("metro", "m"), ("centímetros", "cm"), ("centimetros", "cm"),
+        ("milímetros", "mm"), ("milimetros", "mm"),
+        ("kilogramos", "kg"), ("kilos", "kg"), ("gramos", "g"),
+        ("grados centígrados", "°C"), ("grados celsius", "°C"), ("grados", "°"),
+    ],
+    clock: Clock::None,
+    months: &[],
+    verb_months: &[],
+    ordinals: &[],
+};
+
+/// A word of the transcript. Hyphenated and compound number words are
+/// split into several words that share their token's span.
+struct Word {
+    start: usize,
+    end: usize,
+    /// Lowercased, without surrounding punctuation.
+    text: String,
+    /// Capitalized in the source.
+    capitalized: bool,
+    /// Index of the whitespace-separated token the word came from.
+    token: usize,
+    /// Punctuation follows, so nothing continues across it.
+    break_after: bool,
+}
+
+/// A number read from the words at some position.
+struct Number {
+    len: usize,
+    value: u64,
+    written: String,
+    /// Read from number words rather than already written with digits.
+    spoken: bool,
+    has_decimals: bool,
+}
+
+/// Inverse text normalization: spoken-form numbers, money, units, times and
+/// dates become their written form ("twenty five percent" -> "25%", "three
+/// thirty pm" -> "3:30 PM", "one hundred dollars" -> "$100"). Offline and
+/// table-driven, one `Locale` per language; `None` or "auto" is read as
+/// English, and languages without a locale come back unchanged.
+pub fn inverse_normalize(text: &str, language: Option<&str>) -> String {
+    let Some(locale) = Locale::for_language(language) else {
+        return text.to_string();
+    };
+
+    let words = tokenize(text, locale);
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let mut after_number = false;
+    let mut i = 0;
+    while i < words.len() {
+        let starts_token = i == 0 || words[i - 1].token != words[i].token;
+        let matched = starts_token
+            .then(|| locale.match_at(&words, i, after_number))
+            .flatten()
+            .filter(|(len, _)| ends_token(&words, i + len - 1));
+
+        if let Some((len, written)) = matched {
+            edits.push((words[i].start, words[i + len - 1].end, written));
+            after_number = false;
+            i += len;
+        } else {
+            after_number = !words[i].break_after
+                && locale
+                    .number_word(&words[i].text)
+                    .is_some_and(|(kind, _)| kind != Kind::Connector);
+            i += 1;
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (start, end, written) in edits {
+        result.push_str(&text[cursor..start]);
+        result.push_str(&written);
+        cursor = end;
+    }
+    result.push_str(&text[cursor..]);
+    result
+}
+
+fn ends_token(words: &[Word], at: usize) -> bool {
+    words.get(at + 1).is_none_or(|next| next.token != words[at].token)
+}
+
+impl Locale {
+    fn for_language(language: Option<&str>) -> Option<&'static Locale> {
+        let code = language.unwrap_or("en").trim().to_lowercase();
+        match code.split(['-', '_']).next().unwrap_or_default() {
+            "" | "auto" | "en" => Some(&ENGLISH),
+            "de" => Some(&GERMAN),
+            "es" => Some(&SPANISH),
+            _ => None,
+        }
+    }
+
+    fn number_word(&self, word: &str) -> Option<(Kind, u64)> {
+        let find = |table: Words| table.iter().find(|(w, _)| *w == word).map(|(_, v)| *v);
+        find(self.units)
+            .map(|v| (Kind::Unit, v))
+            .or_else(|| find(self.teens).map(|v| (Kind::Teen, v)))
+            .or_else(|| find(self.tens).map(|v| (Kind::Tens, v)))
+            .or_else(|| find(self.hundreds).map(|v| (Kind::Hundreds, v)))
+            .or_else(|| self.hundred.contains(&word).then_some((Kind::Hundred, 100)))
+            .or_else(|| find(self.scales).map(|v| (Kind::Scale, v)))
+            .or_else(|| {
+                self.connector
+                    .filter(|(connector, _)| *connector == word)
+                    .map(|_| (Kind::Connector, 0))
+            })
+    }
+
+    fn ordinal(&self, word: &str) -> Option<u64> {
+        self.ordinals.iter().find(|(w, _)| *w == word).map(|(_, v)| *v)
+    }
+
+    /// Splits "twenty-five" and "fünfundzwanzig" into their number words.
+    fn split(&self, word: &str) -> Vec<String> {
+        let is_part = |part: &str| self.number_word(part).is_some() || self.ordinal(part).is_some();
+        if word.contains('-') {
+            let parts: Vec<&str> = word.split('-').collect();
+            if parts.iter().all(|p| is_part(p)) {
+                return parts.into_iter().map(str::to_string).collect();
+            }
+        }
+        if self.compounds && !is_part(word) {
+            if let Some(parts) = self.segment(word).filter(|parts| parts.len() > 1) {
+                return parts;
+            }
+        }
+        vec![word.to_string()]
+    }
+
+    fn segment(&self, word: &str) -> Option<Vec<String>> {
+        if word.is_empty() {
+            return Some(Vec::new());
+        }
+        let mut morphemes: Vec<&str> = [self.units, self.teens, self.tens, self.scales]
+            .iter()
+            .flat_map(|table| table.iter().map(|(w, _)| *w))
+            .chain(self.hundred.iter().copied())
+            .chain(self.connector.map(|(c, _)| c))
+            .filter(|m| word.starts_with(m))
+            .collect();
+        morphemes.sort_by_key(|m| std::cmp::Reverse(m.len()));
+
+        morphemes.into_iter().find_map(|m| {
+            let mut rest = self.segment(&word[m.len()..])?;
+            rest.insert(0, m.to_string());
+            Some(rest)
+        })
+    }
+
+    fn allows(&self, prev: Option<Kind>, kind: Kind, current: u64, last_scale: Option<u64>, value: u64) -> bool {
+        use Kind::*;
+        match kind {
+            Unit => matches!(prev, None | Some(Tens | Hundreds | Hundred | Scale | Connector)),
+            Teen | Tens => matches!(prev, None | Some(Hundreds | Hundred | Scale | Connector)),
+            Hundreds => matches!(prev, None | Some(Scale)),
+            Hundred => {
+                current < 100
+                    && match prev {
+                        None => self.implicit_one,
+                        Some(Unit | Teen | Tens) => true,
+                        _ => false,
+                    }
+            }
+            Scale => {
+                last_scale.is_none_or(|scale| value < scale)
+                    && match prev {
+                        None => self.implicit_one,
+                        Some(Connector) => false,
+                        Some(_) => true,
+                    }
+            }
+            Connector => self
+                .connector
+                .is_some_and(|(_, after)| prev.is_some_and(|p| after.contains(&p))),
+        }
+    }
+
+    /// The longest number starting at `i`, either number words or digits
+    /// whisper already wrote.
+    fn number_at(&self, words: &[Word], i: usize) -> Option<Number> {
+        let first = words.get(i)?;
+        if let Some((value, has_decimals)) = digit_value(&first.text) {
+            return Some(Number {
+                len: 1,
+                value,
+                written: first.text.clone(),
+                spoken: false,
+                has_decimals,
+            });
+        }
+
+        let (mut total, mut current) = (0u64, 0u64);
+        let mut prev: Option<Kind> = None;
+        let mut last_scale: Option<u64> = None;
+        let mut best: Option<(usize, u64)> = None;
+        for (k, word) in words[i..].iter().enumerate() {
+            let Some((kind, value)) = self.number_word(&word.text) else {
+                break;
+            };
+            if !self.allows(prev, kind, current, last_scale, value) {
+                break;
+            }
+            match kind {
+                Kind::Unit | Kind::Teen | Kind::Tens | Kind::Hundreds => current += value,
+                Kind::Hundred => current = current.max(1) * 100,
+                Kind::Scale => {
+                    total += current.max(1) * value;
+                    current = 0;
+                    last_scale = Some(value);
+                }
+                Kind::Connector => {}
+            }
+            prev = Some(kind);
+            // A number can't end on a connector or halfway through a compound
+            if kind != Kind::Connector && ends_token(words, i + k) {
+                best = Some((k + 1, total + current));
+            }
+            if word.break_after {
+                break;
+            }
+        }
+        let (mut len, value) = best?;
+
+        let mut written = self.format_integer(value);
+        let mut has_decimals = false;
+        let point = words.get(i + len).filter(|_| !words[i + len - 1].break_after);
+        if point.is_some_and(|p| self.decimal_words.contains(&p.text.as_str()) && !p.break_after) {
+            let digits = self.digits_at(words, i + len + 1);
+            if !digits.is_empty() {
+                written.push(self.decimal_separator);
+                written.push_str(&digits);
+                len += 1 + digits.len();
+                has_decimals = true;
+            }
+        }
+
+        Some(Number {
+            len,
+            value,
+            written,
+            spoken: true,
+            has_decimals,
+        })
+    }
+
+    /// Single digits read one by one, as after "point".
+    fn digits_at(&self, words: &[Word], i: usize) -> String {
+        let mut digits = String::new();
+        for word in &words[i.min(words.len())..] {
+            let digit = match self.number_word(&word.text) {
+                Some((Kind::Unit, value)) => value,
+                _ if self.oh == Some(word.text.as_str()) => 0,
+                _ => break,
+            };
+            digits.push_str(&digit.to_string());
+            if word.break_after {
+                break;
+            }
+        }
+        digits
+    }
+
+    fn format_integer(&self, value: u64) -> String {
+        let digits = value.to_string();
+        if value < 10_000 {
+            return digits;
+        }
+        let mut grouped = String::new();
+        for (k, c) in digits.chars().enumerate() {
+            if k > 0 && (digits.len() - k).is_multiple_of(3) {
+                grouped.push(self.group_separator);
+            }
+            grouped.push(c);
+        }
+        grouped
+    }
+
+    /// Length of `phrase` if the words at `at` spell it out without
+    /// punctuation in between.
+    fn phrase_at(&self, words: &[Word], at: usize, phrase: &str) -> Option<usize> {
+        let parts: Vec<&str> = phrase.split(' ').collect();
+        let slice = words.get(at..at + parts.len())?;
+        let matches = slice.iter().zip(&parts).all(|(w, p)| w.text == *p)
+            && slice[..parts.len() - 1].iter().all(|w| !w.break_after);
+        matches.then_some(parts.len())
+    }
+
+    /// A written replacement for the words starting at `i`, and how many
+    /// words it covers.
+    fn match_at(&self, words: &[Word], i: usize, after_number: bool) -> Option<(usize, String)> {
+        self.time_at(words, i)
+            .or_else(|| self.date_at(words, i))
+            .or_else(|| self.quantity_at(words, i, after_number))
+    }
+
+    /// A number with what it counts: percent, money or a unit. Bare numbers
+    /// are only rewritten from 10 up ("one of them" stays as is), and not
+    /// next to other number words, which are usually a time or a year. A
+    /// lone "mil" or "tausend" is left too, as in "mil gracias".
+    fn quantity_at(&self, words: &[Word], i: usize, after_number: bool) -> Option<(usize, String)> {
+        let number = self.number_at(words, i)?;
+        let end = i + number.len;
+        let suffix_allowed = !words[end - 1].break_after;
+
+        if suffix_allowed {
+            if let Some((len, written)) = self.suffix_at(words, end, &number) {
+                return Some((number.len + len, written));
+            }
+        }
+
+        let next_is_number = suffix_allowed
+            && words
+                .get(end)
+                .is_some_and(|w| self.number_word(&w.text).is_some());
+        let lone_multiplier = number.len == 1
+            && matches!(self.number_word(&words[i].text), Some((Kind::Hundred | Kind::Scale, _)));
+        let standalone = number.spoken
+            && (number.value >= 10 || number.has_decimals)
+            && !lone_multiplier
+            && !after_number
+            && !next_is_number;
+        standalone.then_some((number.len, number.written))
+    }
+
+    fn suffix_at(&self, words: &[Word], at: usize, number: &Number) -> Option<(usize, String)> {
+        let longest = |phrases: &mut dyn Iterator<Item = &'static str>| {
+            phrases
+                .filter_map(|p| self.phrase_at(words, at, p).map(|len| (len, p)))
+                .max_by_key(|(len, _)| *len)
+        };
+
+        let percent = longest(&mut self.percent.iter().copied());
+        let currency = longest(&mut self.currencies.iter().map(|(p, ..)| *p));
+        let measure = longest(&mut self.measures.iter().map(|(p, _)| *p));
+
+        let best = [percent.map(|m| (m, 0)), currency.map(|m| (m, 1)), measure.map(|m| (m, 2))]
+            .into_iter()
+            .flatten()
+            .max_by_key(|((len, _), _)| *len)?;
+
+        let ((len, phrase), which) = best;
+        let amount = &number.written;
+        match which {
+            0 if self.percent_spaced => Some((len, format!("{} %", amount))),
+            0 => Some((len, format!("{}%", amount))),
+            1 => {
+                let (_, symbol, placement) = self.currencies.iter().find(|(p, ..)| *p == phrase)?;
+                let (cents_len, amount) = match self.cents_at(words, at + len) {
+                    Some((cents_len, cents)) if !number.has_decimals && !words[at + len - 1].break_after => {
+                        (cents_len, format!("{}.{:02}", amount, cents))
+                    }
+                    _ => (0, amount.clone()),
+                };
+                let written = match placement {
+                    Placement::Prefix => format!("{}{}", symbol, amount),
+                    Placement::Suffix => format!("{} {}", amount, symbol),
+                };
+                Some((len + cents_len, written))
+            }
+            _ => {
+                let (_, symbol) = self.measures.iter().find(|(p, _)| *p == phrase)?;
+                if symbol.starts_with('°') {
+                    Some((len, format!("{}{}", amount, symbol)))
+                } else {
+                    Some((len, format!("{} {}", amount, symbol)))
+                }
+            }
+        }
+    }
+
+    /// "and fifty cents" after a currency: its length and the cents.
+    fn cents_at(&self, words: &[Word], at: usize) -> Option<(usize, u64)> {
+        if self.cents.is_empty() {
+            return None;
+        }
+        let mut i = at;
+        if let Some((connector, _)) = self.connector {
+            if words.get(i).is_some_and(|w| w.text == connector && !w.break_after) {
+                i += 1;
+            }
+        }
+        let number = self.number_at(words, i).filter(|n| n.value < 100 && !n.has_decimals)?;
+        let end = i + number.len;
+        if words[end - 1].break_after {
+            return None;
+        }
+        words.get(end).filter(|w| self.cents.contains(&w.text.as_str()))?;
+        Some((end + 1 - at, number.value))
+    }
+
+    fn time_at(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        match self.clock {
+            Clock::AmPm => self.am_pm_at(words, i),
+            Clock::Suffix(spoken, written) => self.clock_suffix_at(words, i, spoken, written),
+            Clock::None => None,
+        }
+    }
+
+    /// "three thirty pm", "twelve oh five a.m."
+    fn am_pm_at(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let hour = self
+            .number_at(words, i)
+            .filter(|n| (1..=12).contains(&n.value) && !n.has_decimals)?;
+        let mut spoken = hour.spoken;
+        let mut j = i + hour.len;
+
+        let mut minutes = None;
+        if !words[j - 1].break_after {
+            if let Some((len, value)) = self.minutes_at(words, j) {
+                minutes = Some(value);
+                j += len;
+                spoken = true;
+            }
+        }
+        // "3 pm" is already written out; leave whisper's spelling alone
+        if !spoken || words[j - 1].break_after {
+            return None;
+        }
+
+        let suffix = match words.get(j)?.text.replace('.', "").as_str() {
+            "am" => "AM",
+            "pm" => "PM",
+            _ => return None,
+        };
+        let hour = hour.value;
+
+        let written = match minutes {
+            Some(minutes) => format!("{}:{:02} {}", hour, minutes, suffix),
+            None => format!("{} {}", hour, suffix),
+        };
+        Some((j + 1 - i, written))
+    }
+
+    /// "thirty", "oh five", "fifteen": minutes after an hour.
+    fn minutes_at(&self, words: &[Word], i: usize) -> Option<(usize, u64)> {
+        let word = words.get(i)?;
+        if self.oh == Some(word.text.as_str()) && !word.break_after {
+            let digit = match self.number_word(&words.get(i + 1)?.text)? {
+                (Kind::Unit, value) if value > 0 => value,
+                _ => return None,
+            };
+            return Some((2, digit));
+        }
+        let minutes = self
+            .number_at(words, i)
+            .filter(|n| n.spoken && !n.has_decimals && (10..60).contains(&n.value))?;
+        Some((minutes.len, minutes.value))
+    }
+
+    /// "fünfzehn uhr dreißig" -> "15:30 Uhr"
+    fn clock_suffix_at(
+        &self,
+        words: &[Word],
+        i: usize,
+        spoken: &str,
+        written: &str,
+    ) -> Option<(usize, String)> {
+        let hour = self
+            .number_at(words, i)
+            .filter(|n| n.spoken && !n.has_decimals && n.value <= 24)?;
+        let mut j = i + hour.len;
+        if words[j - 1].break_after || words.get(j)?.text != spoken {
+            return None;
+        }
+        j += 1;
+
+        let minutes = if words[j - 1].break_after {
+            None
+        } else {
+            self.number_at(words, j)
+                .filter(|n| n.spoken && !n.has_decimals && n.value < 60)
+        };
+        match minutes {
+            Some(minutes) => Some((
+                j + minutes.len - i,
+                format!("{}:{:02} {}", hour.value, minutes.value, written),
+            )),
+            None => Some((j - i, format!("{} {}", hour.value, written))),
+        }
+    }
+
+    /// "June fifth" -> "June 5", "march fifth twenty twenty four" ->
+    /// "March 5, 2024".
+    fn date_at(&self, words: &[Word], i: usize) -> Option<(usize, String)> {
+        let word = words.get(i).filter(|w| !w.break_after)?;
+        let month = self.months.iter().find(|m| **m == word.text)?;
+        let (day_len, day, mut spoken) = self.day_at(words, i + 1)?;
+        let mut j = i + 1 + day_len;
+
+        let mut written = format!("{} {}", capitalize(month), day);
+        let mut has_year = false;
+        if !words[j - 1].break_after {
+            if let Some((len, year, year_spoken)) = self.year_at(words, j) {
+                written = format!("{}, {}", written, year);
+                j += len;
+                spoken |= year_spoken;
+                has_year = true;
+            }
+        }
+        // "we march first" is no date, "march twenty-first" and "march
+        // first twenty twenty four" are
+        let verb = !word.capitalized && self.verb_months.contains(month);
+        if verb && day_len == 1 && !has_year {
+            return None;
+        }
+        spoken.then_some((j - i, written))
+    }
+
+    /// An ordinal day: "fifth", "twenty-first", "5th".
+    fn day_at(&self, words: &[Word], i: usize) -> Option<(usize, u64, bool)> {
+        let word = words.get(i)?;
+        let numeric = ["st", "nd", "rd", "th"]
+            .iter()
+            .find_map(|suffix| word.text.strip_suffix(suffix))
+            .unwrap_or(&word.text);
+        if let Ok(day) = numeric.parse::<u64>() {
+            return (1..=31).contains(&day).then_some((1, day, false));
+        }
+        if let Some(day) = self.ordinal(&word.text) {
+            return Some((1, day, true));
+        }
+        let (kind, tens) = self.number_word(&word.text)?;
+        if kind != Kind::Tens || word.break_after {
+            return None;
+        }
+        let unit = self.ordinal(&words.get(i + 1)?.text).filter(|u| *u < 10)?;
+        let day = tens + unit;
+        (day <= 31).then_some((2, day, true))
+    }
+
+    /// "twenty twenty four", "nineteen oh five", "two thousand and one", "2024".
+    fn year_at(&self, words: &[Word], i: usize) -> Option<(usize, u64, bool)> {
+        let first = self.number_at(words, i)?;
+        if !first.spoken {
+            let year = first.written.parse::<u64>().ok()?;
+            return (first.written.len() == 4).then_some((1, year, false));
+        }
+        if (1000..3000).contains(&first.value) && !first.has_decimals {
+            return Some((first.len, first.value, true));
+        }
+        if first.len != 1 || !(10..100).contains(&first.value) || words[i].break_after {
+            return None;
+        }
+        let (len, rest) = self.minutes_at(words, i + 1).or_else(|| {
+            self.number_at(words, i + 1)
+                .filter(|n| n.spoken && (60..100).contains(&n.value))
+                .map(|n| (n.len, n.value))
+        })?;
+        Some((1 + len, first.value * 100 + rest, true))
+    }
+}
+
+/// Whole part of a number whisper already wrote with digits ("25", "2,500",
+/// "3.5"), and whether it has a fractional part.
+fn digit_value(text: &str) -> Option<(u64, bool)> {
+    if !text.starts_with(|c: char| c.is_ascii_digit())
+        || !text.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '.')
+    {
+        return None;
+    }
+    // Whole part only; "2,500" and "2.500" are both thousands, "3.5" is 3
+    let groups: Vec<&str> = text.split([',', '.']).collect();
+    if groups.len() > 1 && groups[1..].iter().all(|g| g.len() == 3) {
+        return Some((groups.concat().parse().ok()?, false));
+    }
+    Some((groups[0].parse().ok()?, groups.len() > 1))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn tokenize(text: &str, locale: &Locale) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut token_start = None;
+    let mut token = 0;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (token_start, c.is_whitespace()) {
+            (None, false) => token_start = Some(i),
+            (Some(start), true) => {
+                push_token(&mut words, text, start, i, token, locale);
+                token += 1;
+                token_start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn push_token(words: &mut Vec<Word>, text: &str, start: usize, end: usize, token: usize, locale: &Locale) {
+    let raw = &text[start..end];
+    let is_word_char = |c: char| c.is_alphanumeric();
+    let core_start = end - raw.trim_start_matches(|c: char| !is_word_char(c)).len();
+    let mut core_end = start + raw.trim_end_matches(|c: char| !is_word_char(c)).len();
+    // The dot of "p.m." only ends the sentence when nothing lowercase follows
+    let abbreviation = text[core_start..core_end].eq_ignore_ascii_case("a.m")
+        || text[core_start..core_end].eq_ignore_ascii_case("p.m");
+    if abbreviation
+        && text[core_end..end] == *"."
+        && text[end..].trim_start().starts_with(|c: char| c.is_lowercase())
+    {
+        core_end = end;
+    }
+
+    // Leading punctuation ("(twenty") or a lone dash also ends the word before
+    if core_start >= core_end || core_start > start {
+        if let Some(last) = words.last_mut() {
+            last.break_after = true;
+        }
+        if core_start >= core_end {
+            return;
+        }
+    }
+
+    let core = &text[core_start..core_end];
+    for part in locale.split(&core.to_lowercase()) {
+        words.push(Word {
+            start: core_start,
+            end: core_end,
+            text: part,
+            capitalized: core.starts_with(char::is_uppercase),
+            token,
+            break_after: false,
+        });
+    }
+    if core_end < end {
+        if let Some(last) = words.last_mut() {
+            last.break_after = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(language: &str, cases: &[(&str, &str)]) {
+        for (spoken, written) in cases {
+            assert_eq!(
+                inverse_normalize(spoken, Some(language)),
+                *written,
+                "{}: {:?}",
+                language,
+                spoken
+            );
+        }
+    }
+
+    #[test]
+    fn english() {
+        check(
+            "en",
+            &[
+                ("twenty five percent", "25%"),
+                ("it costs one hundred dollars", "it costs $100"),
+                ("ten dollars and fifty cents", "$10.50"),
+                ("three thirty pm", "3:30 PM"),
+                ("meet me at three oh five am", "meet me at 3:05 AM"),
+                ("March fifth", "March 5"),
+                ("see you june fifth", "see you June 5"),
+                ("may twenty first", "May 21"),
+                ("march first twenty twenty four", "March 1, 2024"),
+                ("one hundred and five", "105"),
+                ("two thousand twenty four", "2024"),
+                ("one million", "1,000,000"),
+                ("twenty-one", "21"),
+                ("three point one four", "3.14"),
+                ("forty two.", "42."),
+                ("five kilometers", "5 km"),
+                ("sixty miles per hour", "60 mph"),
+                ("four gigabytes of RAM", "4 GB of RAM"),
+                ("twenty degrees celsius", "20°C"),
+                // Left alone
+                ("one of them", "one of them"),
+                ("a second opinion", "a second opinion"),
+                ("I have one idea", "I have one idea"),
+                ("we won by one", "we won by one"),
+                ("at nine", "at nine"),
+                ("the first time", "the first time"),
+                ("on the first of may", "on the first of may"),
+                ("may I ask", "may I ask"),
+                ("I may second that motion.", "I may second that motion."),
+                ("We march first, then eat.", "We march first, then eat."),
+                ("they march fifth in line", "they march fifth in line"),
+                ("point taken", "point taken"),
+                ("one, two, three", "one, two, three"),
+                ("half past nine", "half past nine"),
+            ],
+        );
+    }
+
+    #[test]
+    fn german() {
+        check(
+            "de",
+            &[
+                ("fünfundzwanzig prozent", "25 %"),
+                ("hundert euro", "100 €"),
+                ("drei uhr dreißig", "3:30 Uhr"),
+                ("zweitausendvierundzwanzig", "2024"),
+                ("neunzehnhundert", "1900"),
+                ("eine million", "1.000.000"),
+                ("drei komma eins vier", "3,14"),
+                ("fünf kilometer", "5 km"),
+                ("zwanzig grad celsius", "20°C"),
+                // Left alone
+                ("ein guter tag", "ein guter tag"),
+                ("einer von ihnen", "einer von ihnen"),
+                ("sieben brücken", "sieben brücken"),
+                ("tausend dank", "tausend dank"),
+                ("acht", "acht"),
+            ],
+        );
+    }
+
+    #[test]
+    fn spanish() {
+        check(
+            "es",
+            &[
+                ("veinticinco por ciento", "25 %"),
+                ("cien euros", "100 €"),
+                ("treinta y cinco", "35"),
+                ("doscientos cincuenta", "250"),
+                ("dos mil veinticuatro", "2024"),
+                ("un millón", "1.000.000"),
+                ("tres coma cinco", "3,5"),
+                ("cinco kilómetros", "5 km"),
+                ("mil euros", "1000 €"),
+                ("hoy es once", "hoy es 11"),
+                // Left alone
+                ("uno de ellos", "uno de ellos"),
+                ("una buena idea", "una buena idea"),
+                ("mil gracias", "mil gracias"),
+            ],
+        );
+    }
+
+    #[test]
+    fn language_selection() {
+        assert_eq!(inverse_normalize("twelve percent", Some("en-US")), "12%");
+        assert_eq!(inverse_normalize("twelve percent", Some("auto")), "12%");
+        assert_eq!(inverse_normalize("twelve percent", None), "12%");
+        // No French locale yet
+        assert_eq!(
+            inverse_normalize("vingt cinq pour cent", Some("fr")),
+            "vingt cinq pour cent"
+        );
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::sync::{
     atomic::{AtomicBool, AtomicU32, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
@@ -24,6 +24,10 @@ const NOTCH_THRESHOLD: f64 = 30.0;
 const OVERLAY_WINDOW_LEVEL: i32 = 2147483631; // CGShieldingWindowLevel - highest possible level
 const FLOATING_WINDOW_LEVEL: i32 = 5; // NSFloatingWindowLevel - above most windows
 const COLLECTION_BEHAVIOR: u64 = (1 << 0) | (1 << 4) | (1 << 6); // CanJoinAllSpaces | Stationary | IgnoresCycle
+const PREVIEW_PADDING: f64 = 12.0;
+const PREVIEW_FONT_SIZE: f64 = 12.0;
+const NS_LINE_BREAK_BY_TRUNCATING_HEAD: u64 = 3; // Keep the newest streamed text visible
+const NS_UTF8_STRING_ENCODING: u64 = 4;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -66,6 +70,19 @@ fn nscolor(r: f64, g: f64, b: f64, a: f64) -> *mut Object {
     unsafe { msg_send![class!(NSColor), colorWithCalibratedRed:r green:g blue:b alpha:a] }
 }
 
+// Returns an autoreleased NSString; only call on the main thread.
+fn nsstring(value: &str) -> *mut Object {
+    unsafe {
+        let string: *mut Object = msg_send![class!(NSString), alloc];
+        let string: *mut Object = msg_send![string,
+            initWithBytes:value.as_ptr()
+            length:value.len()
+            encoding:NS_UTF8_STRING_ENCODING
+        ];
+        msg_send![string, autorelease]
+    }
+}
+
 pub struct NotchOverlay {
     panel: Option<StrongPtr>,
     panel_ptr: Option<*mut Object>,
@@ -76,11 +93,15 @@ pub struct NotchOverlay {
     running: Arc<AtomicBool>,
     processing: Arc<AtomicBool>,
     meter: Arc<AtomicU32>,
+    /// Streamed AI output, shown in place of the waveform while processing.
+    preview: Arc<Mutex<String>>,
+    preview_label: Option<StrongPtr>,
+    preview_label_ptr: usize,
     update_handle: Option<JoinHandle<()>>,
 }
 
 impl NotchOverlay {
-    pub fn new(meter: Arc<AtomicU32>) -> Self {
+    pub fn new(meter: Arc<AtomicU32>, preview: Arc<Mutex<String>>) -> Self {
         Self {
             panel: None,
             panel_ptr: None,
@@ -91,6 +112,9 @@ impl NotchOverlay {
             running: Arc::new(AtomicBool::new(false)),
             processing: Arc::new(AtomicBool::new(false)),
             meter,
+            preview,
+            preview_label: None,
+            preview_label_ptr: 0,
             update_handle: None,
         }
     }
@@ -258,6 +282,25 @@ impl NotchOverlay {
                 bars.push(StrongPtr::new(bar));
             }
 
+            // Label for streamed AI text, hidden until the first delta arrives
+            let label_rect = nsrect(
+                PREVIEW_PADDING,
+                0.0,
+                PANEL_WIDTH - PREVIEW_PADDING * 2.0,
+                panel_height,
+            );
+            let label: *mut Object = msg_send![class!(NSTextField), labelWithString:nsstring("")];
+            let _: () = msg_send![label, setFrame:label_rect];
+            let font: *mut Object = msg_send![class!(NSFont), systemFontOfSize:PREVIEW_FONT_SIZE];
+            let _: () = msg_send![label, setFont:font];
+            let _: () = msg_send![label, setTextColor:nscolor(1.0, 1.0, 1.0, 0.95)];
+            let _: () = msg_send![label, setLineBreakMode:NS_LINE_BREAK_BY_TRUNCATING_HEAD];
+            let _: () = msg_send![label, setHidden:YES];
+            let _: () = msg_send![notch_bar, addSubview:label];
+            let label: *mut Object = msg_send![label, retain];
+            self.preview_label_ptr = label as usize;
+            self.preview_label = Some(StrongPtr::new(label));
+
             self.icon_view = None; // No icon in simplified version
 
             self.bars = bars;
@@ -324,12 +367,40 @@ impl NotchOverlay {
         let processing = Arc::clone(&self.processing);
         let bars_ptrs = self.bar_ptrs.clone();
         let panel_height = self.panel_height;
+        let preview = Arc::clone(&self.preview);
+        let label_ptr = self.preview_label_ptr;
 
         self.update_handle = Some(thread::spawn(move || {
             let queue = Queue::main();
             let mut phase = 0.0f64;
+            let mut shown_preview: Option<String> = None;
             while running.load(Ordering::Relaxed) {
                 phase += 0.2;
+
+                // Swap the waveform for streamed text once there is some
+                let preview_text = if processing.load(Ordering::Relaxed) {
+                    preview.lock().map(|p| p.clone()).unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                let wanted = (!preview_text.is_empty()).then_some(preview_text);
+                if wanted != shown_preview && label_ptr != 0 {
+                    let text = wanted.clone();
+                    let bars_clone = bars_ptrs.clone();
+                    queue.exec_async(move || unsafe {
+                        let label = label_ptr as *mut Object;
+                        let showing = text.is_some();
+                        let value = text.unwrap_or_default().replace('\n', " ");
+                        let _: () = msg_send![label, setStringValue:nsstring(&value)];
+                        let _: () = msg_send![label, setHidden:if showing { NO } else { YES }];
+                        for bar_ptr in &bars_clone {
+                            let bar_view = *bar_ptr as *mut Object;
+                            let _: () = msg_send![bar_view, setHidden:if showing { YES } else { NO }];
+                        }
+                    });
+                    shown_preview = wanted;
+                }
+
                 let amplitude = (meter.load(Ordering::Relaxed) as f64 / 1000.0).min(1.0);
                 let is_processing = processing.load(Ordering::Relaxed);
                 let max_height = panel_height - 16.0; // 8px padding top and bottom
@@ -0,0 +1,450 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Kinds of personal data the built-in detectors find.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+    Iban,
+}
+
+impl PiiKind {
+    pub fn all() -> Vec<PiiKind> {
+        vec![PiiKind::Email, PiiKind::Phone, PiiKind::CreditCard, PiiKind::Iban]
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::CreditCard => "CARD",
+            PiiKind::Iban => "IBAN",
+        }
+    }
+}
+
+/// A user-defined detector, e.g. customer numbers. `name` labels the
+/// placeholder: "customer id" becomes `<CUSTOMER_ID_1>`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomPattern {
+    pub name: String,
+    pub pattern: String,
+}
+
+/// Where dictations are redacted and which detectors run.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RedactionSettings {
+    /// Redact text before it is sent to the AI provider.
+    #[serde(default)]
+    pub before_ai: bool,
+    /// Redact text before it is saved to history.
+    #[serde(default)]
+    pub before_storage: bool,
+    /// Redact text before it is pasted or copied.
+    #[serde(default)]
+    pub before_paste: bool,
+    /// Send numbered placeholders (`<EMAIL_1>`) to the AI and put the
+    /// original values back into its reply, instead of masking them for good.
+    #[serde(default = "default_reversible")]
+    pub reversible: bool,
+    #[serde(default = "PiiKind::all")]
+    pub detectors: Vec<PiiKind>,
+    #[serde(default)]
+    pub custom_patterns: Vec<CustomPattern>,
+}
+
+fn default_reversible() -> bool {
+    true
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            before_ai: false,
+            before_storage: false,
+            before_paste: false,
+            reversible: default_reversible(),
+            detectors: PiiKind::all(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+/// Checksum or shape test a match must pass, cutting false positives such
+/// as order numbers that merely look like cards.
+type Validator = fn(&str) -> bool;
+
+struct Detector {
+    label: String,
+    regex: Regex,
+    validate: Validator,
+}
+
+/// Values replaced by numbered placeholders, for putting them back later.
+#[derive(Debug, Default)]
+pub struct Placeholders {
+    values: Vec<(String, String)>,
+}
+
+impl Placeholders {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Puts the original values back wherever their placeholders appear.
+    pub fn restore(&self, text: &str) -> String {
+        self.values
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, value)| {
+                text.replace(placeholder, value)
+            })
+    }
+
+    /// A restorer for text that arrives in pieces.
+    pub fn streaming(&self) -> StreamRestorer<'_> {
+        StreamRestorer {
+            placeholders: self,
+            pending: String::new(),
+        }
+    }
+}
+
+/// Restores placeholders in streamed text. A piece ending inside a possible
+/// placeholder ("... <EMA") is held back until the rest of it arrives.
+pub struct StreamRestorer<'a> {
+    placeholders: &'a Placeholders,
+    pending: String,
+}
+
+impl StreamRestorer<'_> {
+    /// Restored text that is safe to show, possibly empty.
+    pub fn push(&mut self, delta: &str) -> String {
+        self.pending.push_str(delta);
+        let split = match self.pending.rfind('<') {
+            Some(open) if !self.pending[open..].contains('>') => open,
+            _ => self.pending.len(),
+        };
+        let ready: String = self.pending.drain(..split).collect();
+        self.placeholders.restore(&ready)
+    }
+
+    /// Whatever was still held back.
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        self.placeholders.restore(&rest)
+    }
+}
+
+/// Finds personal data with the configured detectors. Detectors run in a
+/// fixed order and an earlier match wins an overlap, so a card number is
+/// never also taken for a phone number.
+pub struct Redactor {
+    detectors: Vec<Detector>,
+}
+
+impl Redactor {
+    /// Fails if a custom pattern is not a valid regex.
+    pub fn new(settings: &RedactionSettings) -> Result<Self> {
+        let builtin: [(PiiKind, &str, Validator); 4] = [
+            (
+                PiiKind::Email,
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b",
+                |_| true,
+            ),
+            (
+                PiiKind::Iban,
+                r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}\b",
+                is_valid_iban,
+            ),
+            (PiiKind::CreditCard, r"\b\d(?:[ -]?\d){12,18}\b", is_valid_card),
+            (
+                PiiKind::Phone,
+                r"(?:\+\d{1,3}[ -]?)?(?:\(\d{1,4}\)[ -]?)?\d{2,5}(?:[ -]\d{2,8}){1,4}|\+\d{7,15}",
+                is_valid_phone,
+            ),
+        ];
+
+        let mut detectors = Vec::new();
+        for (kind, pattern, validate) in builtin {
+            if settings.detectors.contains(&kind) {
+                detectors.push(Detector {
+                    label: kind.label().to_string(),
+                    regex: Regex::new(pattern).expect("built-in pattern is valid"),
+                    validate,
+                });
+            }
+        }
+        for custom in &settings.custom_patterns {
+            let regex = Regex::new(&custom.pattern)
+                .with_context(|| format!("Invalid redaction pattern '{}'", custom.name))?;
+            detectors.push(Detector {
+                label: placeholder_label(&custom.name),
+                regex,
+                validate: |_| true,
+            });
+        }
+        Ok(Self { detectors })
+    }
+
+    /// Replaces each finding with a bare label such as `[EMAIL]`.
+    pub fn mask(&self, text: &str) -> String {
+        self.replace(text, |label, _| format!("[{}]", label))
+    }
+
+    /// Replaces each finding with a numbered placeholder such as `<EMAIL_1>`.
+    /// Repeats of the same value share a placeholder.
+    pub fn redact(&self, text: &str) -> (String, Placeholders) {
+        let mut placeholders = Placeholders::default();
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let redacted = self.replace(text, |label, value| {
+            if let Some((placeholder, _)) = placeholders.values.iter().find(|(_, v)| v == value) {
+                return placeholder.clone();
+            }
+            let number = match counts.iter_mut().find(|(l, _)| l == label) {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count
+                }
+                None => {
+                    counts.push((label.to_string(), 1));
+                    1
+                }
+            };
+            let placeholder = format!("<{}_{}>", label, number);
+            placeholders.values.push((placeholder.clone(), value.to_string()));
+            placeholder
+        });
+        (redacted, placeholders)
+    }
+
+    fn replace(&self, text: &str, mut placeholder: impl FnMut(&str, &str) -> String) -> String {
+        let mut found: Vec<(usize, usize, &str)> = Vec::new();
+        for detector in &self.detectors {
+            for m in detector.regex.find_iter(text) {
+                let overlaps = found.iter().any(|&(s, e, _)| m.start() < e && s < m.end());
+                if !m.is_empty()
+                    && !overlaps
+                    && is_standalone(text, m.start(), m.end())
+                    && (detector.validate)(m.as_str())
+                {
+                    found.push((m.start(), m.end(), &detector.label));
+                }
+            }
+        }
+        found.sort_by_key(|&(start, _, _)| start);
+
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, label) in found {
+            result.push_str(&text[last..start]);
+            result.push_str(&placeholder(label, &text[start..end]));
+            last = end;
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+}
+
+/// Whether a match isn't part of a longer word or number; the regexes can't
+/// check this themselves for matches starting with "+" or "(".
+fn is_standalone(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric();
+    !text[..start].chars().next_back().is_some_and(is_word)
+        && !text[end..].chars().next().is_some_and(is_word)
+}
+
+fn placeholder_label(name: &str) -> String {
+    let label: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if label.is_empty() {
+        "REDACTED".to_string()
+    } else {
+        label
+    }
+}
+
+fn digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Luhn checksum over 13 to 19 digits.
+fn is_valid_card(text: &str) -> bool {
+    let digits = digits(text);
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match i % 2 {
+            0 => d,
+            _ if d * 2 > 9 => d * 2 - 9,
+            _ => d * 2,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// ISO 13616 check: with the first four characters moved to the end and
+/// letters counted as 10-35, the number is 1 modulo 97.
+fn is_valid_iban(text: &str) -> bool {
+    let compact: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    let (head, tail) = compact.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// 7 to 15 digits (E.164) in a shape numbers in prose don't have: a country
+/// code, area code in parentheses, trunk prefix 0, or dashes ("555-1234", but
+/// not the dates "2024-03-05" or "05-03-2024"), or three space-separated
+/// groups that aren't years ("2019 2020 2021") or thousands ("1 200 000").
+fn is_valid_phone(text: &str) -> bool {
+    if !(7..=15).contains(&digits(text).len()) {
+        return false;
+    }
+    let groups: Vec<&str> = text.split([' ', '-']).collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let is_date = text.contains('-')
+        && matches!(lengths.as_slice(), [4, 1..=2, 1..=2] | [1..=2, 1..=2, 4]);
+    if is_date {
+        return false;
+    }
+    if text.starts_with(['+', '(', '0']) || text.contains('-') {
+        return true;
+    }
+    groups.len() >= 3
+        && !groups.iter().all(|g| g.len() == 4)
+        && !groups[1..].iter().all(|g| g.len() == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        Redactor::new(&RedactionSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn masks_personal_data() {
+        let redactor = redactor();
+        for (text, masked) in [
+            ("mail jane.doe@example.com today", "mail [EMAIL] today"),
+            ("Jane.Doe+news@Example.co.uk", "[EMAIL]"),
+            ("card 4111 1111 1111 1111 please", "card [CARD] please"),
+            ("4111-1111-1111-1111", "[CARD]"),
+            ("amex 378282246310005", "amex [CARD]"),
+            ("iban DE89 3704 0044 0532 0130 00", "iban [IBAN]"),
+            ("GB82WEST12345698765432", "[IBAN]"),
+            ("call +49 30 123456", "call [PHONE]"),
+            ("call (555) 123-4567", "call [PHONE]"),
+            ("555-1234", "[PHONE]"),
+            ("030 1234567", "[PHONE]"),
+            ("+14155552671", "[PHONE]"),
+        ] {
+            assert_eq!(redactor.mask(text), masked, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn leaves_lookalikes_alone() {
+        let redactor = redactor();
+        for text in [
+            "user@localhost",
+            // 16 digits failing the Luhn check
+            "order 4111111111111112",
+            "order 1234567812345678",
+            // Wrong IBAN check digits
+            "DE00370400440532013000",
+            "on 2024-03-05 we met",
+            "dated 05-03-2024",
+            "due 5-3-2024",
+            "the years 2019 2020 2021",
+            "1 200 000 people",
+            "room 12345",
+            "version 1.2.3",
+            "pay 1,000,000",
+            "12:30 tomorrow",
+            "IP 192.168.1.1",
+        ] {
+            assert_eq!(redactor.mask(text), text);
+        }
+    }
+
+    #[test]
+    fn checksums() {
+        assert!(is_valid_card("4111 1111 1111 1111"));
+        assert!(!is_valid_card("4111 1111 1111 1112"));
+        assert!(!is_valid_card("4111 1111 111"));
+        assert!(is_valid_iban("DE89 3704 0044 0532 0130 00"));
+        assert!(is_valid_iban("gb82 west 1234 5698 7654 32"));
+        assert!(!is_valid_iban("DE89 3704 0044 0532 0130 01"));
+        assert!(!is_valid_iban("DE89 3704"));
+    }
+
+    #[test]
+    fn placeholders_are_numbered_and_restored() {
+        let redactor = redactor();
+        let (redacted, placeholders) = redactor
+            .redact("Write to a@example.com, b@example.com and a@example.com about 555-1234.");
+        assert_eq!(
+            redacted,
+            "Write to <EMAIL_1>, <EMAIL_2> and <EMAIL_1> about <PHONE_1>."
+        );
+        assert_eq!(
+            placeholders.restore("Sent to <EMAIL_2>; call <PHONE_1>."),
+            "Sent to b@example.com; call 555-1234."
+        );
+    }
+
+    #[test]
+    fn detectors_and_custom_patterns_follow_the_settings() {
+        let settings = RedactionSettings {
+            detectors: vec![PiiKind::Email],
+            custom_patterns: vec![CustomPattern {
+                name: "customer id".to_string(),
+                pattern: r"\bC-\d{6}\b".to_string(),
+            }],
+            ..RedactionSettings::default()
+        };
+        let redactor = Redactor::new(&settings).unwrap();
+        assert_eq!(
+            redactor.mask("C-123456 at a@example.com, call 555-1234"),
+            "[CUSTOMER_ID] at [EMAIL], call 555-1234"
+        );
+
+        let invalid = RedactionSettings {
+            custom_patterns: vec![CustomPattern {
+                name: "broken".to_string(),
+                pattern: "(".to_string(),
+            }],
+            ..RedactionSettings::default()
+        };
+        assert!(Redactor::new(&invalid).is_err());
+    }
+}
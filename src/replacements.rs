@@ -0,0 +1,240 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// A user-defined replacement: a recurring misrecognition ("get hub" ->
+/// "GitHub") or a snippet ("my calendar link" -> a URL). The replacement may
+/// use the `{date}`, `{time}` and `{clipboard}` variables.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Replacement {
+    pub id: i64,
+    pub pattern: String,
+    pub replacement: String,
+    pub enabled: bool,
+}
+
+/// A rule that fired on a dictation, kept on the history entry. The pattern
+/// is copied so the record still reads well after the rule is deleted.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppliedReplacement {
+    pub rule_id: i64,
+    pub pattern: String,
+}
+
+/// Values for the template variables, captured once per dictation.
+pub struct TemplateContext {
+    pub now: DateTime<Local>,
+    pub clipboard: Option<String>,
+}
+
+impl TemplateContext {
+    fn expand(&self, template: &str) -> String {
+        let mut result = template
+            .replace("{date}", &self.now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &self.now.format("%H:%M").to_string());
+        if result.contains("{clipboard}") {
+            result = result.replace("{clipboard}", self.clipboard.as_deref().unwrap_or(""));
+        }
+        result
+    }
+}
+
+/// Whether any enabled rule reads the clipboard, so it is only touched when needed.
+pub fn uses_clipboard(rules: &[Replacement]) -> bool {
+    rules
+        .iter()
+        .any(|r| r.enabled && r.replacement.contains("{clipboard}"))
+}
+
+/// Applies the enabled rules in order. Patterns match whole words, ignoring
+/// case, so "get hub" also fixes "Get Hub," but not "together hubcap".
+pub fn apply(
+    text: &str,
+    rules: &[Replacement],
+    context: &TemplateContext,
+) -> (String, Vec<AppliedReplacement>) {
+    let mut result = text.to_string();
+    let mut applied = Vec::new();
+
+    for rule in rules.iter().filter(|r| r.enabled && !r.pattern.trim().is_empty()) {
+        let ranges = find_whole_words(&result, rule.pattern.trim());
+        if ranges.is_empty() {
+            continue;
+        }
+
+        let replacement = context.expand(&rule.replacement);
+        for (start, end) in ranges.into_iter().rev() {
+            result.replace_range(start..end, &replacement);
+        }
+        applied.push(AppliedReplacement {
+            rule_id: rule.id,
+            pattern: rule.pattern.clone(),
+        });
+    }
+
+    (result, applied)
+}
+
+/// Byte ranges of case-insensitive, non-overlapping whole-word matches.
+fn find_whole_words(text: &str, pattern: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut search_from = 0;
+
+    while let Some((start, end)) = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .filter(|&i| i >= search_from)
+        .find_map(|start| match_at(text, start, pattern).map(|end| (start, end)))
+    {
+        ranges.push((start, end));
+        search_from = end;
+    }
+
+    ranges
+}
+
+/// End of a whole-word match of `pattern` starting at byte `start`. Runs of
+/// whitespace in the pattern match any whitespace in the text.
+fn match_at(text: &str, start: usize, pattern: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric();
+    if text[..start].chars().next_back().is_some_and(is_word) {
+        return None;
+    }
+
+    let mut rest = text[start..].char_indices().peekable();
+    let mut end = start;
+    let mut pattern_chars = pattern.chars().peekable();
+    while let Some(p) = pattern_chars.next() {
+        if p.is_whitespace() {
+            while pattern_chars.peek().is_some_and(|c| c.is_whitespace()) {
+                pattern_chars.next();
+            }
+            let mut matched_space = false;
+            while let Some((i, c)) = rest.peek().copied() {
+                if !c.is_whitespace() {
+                    break;
+                }
+                matched_space = true;
+                end = start + i + c.len_utf8();
+                rest.next();
+            }
+            if !matched_space {
+                return None;
+            }
+            continue;
+        }
+
+        let (i, c) = rest.next()?;
+        if !c.to_lowercase().eq(p.to_lowercase()) {
+            return None;
+        }
+        end = start + i + c.len_utf8();
+    }
+
+    if text[end..].chars().next().is_some_and(is_word) {
+        return None;
+    }
+    Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn rule(id: i64, pattern: &str, replacement: &str) -> Replacement {
+        Replacement {
+            id,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            enabled: true,
+        }
+    }
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            now: Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap(),
+            clipboard: Some("https://example.com".to_string()),
+        }
+    }
+
+    #[test]
+    fn applies_rules() {
+        let rules = [
+            rule(1, "get hub", "GitHub"),
+            rule(2, "hub", "HUB"),
+            rule(3, "new york", "New York City"),
+            rule(4, "york", "YORK"),
+            rule(5, "straße", "Strasse"),
+            rule(6, "my link", "{clipboard}"),
+            rule(7, "today's date", "{date} {time}"),
+            rule(8, "  ", "nothing"),
+            Replacement {
+                enabled: false,
+                ..rule(9, "disabled", "ENABLED")
+            },
+        ];
+        let cases: &[(&str, &str, &[i64])] = &[
+            ("push to get hub", "push to GitHub", &[1]),
+            ("Get Hub, then GET   HUB.", "GitHub, then GitHub.", &[1]),
+            ("get\nhub", "GitHub", &[1]),
+            // Whole words only
+            ("together hubcap", "together hubcap", &[]),
+            ("gethub", "gethub", &[]),
+            ("a hub and a get hub", "a HUB and a GitHub", &[1, 2]),
+            // Rules run in order, so later ones also see earlier output
+            ("new york and york", "New YORK City and YORK", &[3, 4]),
+            ("york", "YORK", &[4]),
+            ("Die Hauptstraße", "Die Hauptstraße", &[]),
+            ("Die STRASSE und die Straße", "Die STRASSE und die Strasse", &[5]),
+            ("see my link", "see https://example.com", &[6]),
+            ("it is today's date", "it is 2024-03-05 09:07", &[7]),
+            ("a disabled rule", "a disabled rule", &[]),
+            ("", "", &[]),
+        ];
+        for (input, expected, applied) in cases {
+            let (text, fired) = apply(input, &rules, &context());
+            assert_eq!(text, *expected, "input: {:?}", input);
+            let ids: Vec<i64> = fired.iter().map(|a| a.rule_id).collect();
+            assert_eq!(ids, *applied, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        let rules = [rule(1, "na na", "NA")];
+        let (text, _) = apply("na na na na na", &rules, &context());
+        assert_eq!(text, "NA NA na");
+    }
+
+    #[test]
+    fn applied_rules_keep_their_pattern() {
+        let rules = [rule(7, "Get Hub", "GitHub")];
+        let (_, applied) = apply("get hub", &rules, &context());
+        assert_eq!(
+            applied,
+            [AppliedReplacement {
+                rule_id: 7,
+                pattern: "Get Hub".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn clipboard_is_only_read_when_an_enabled_rule_needs_it() {
+        let mut rules = vec![rule(1, "get hub", "GitHub")];
+        assert!(!uses_clipboard(&rules));
+        rules.push(Replacement {
+            enabled: false,
+            ..rule(2, "my link", "{clipboard}")
+        });
+        assert!(!uses_clipboard(&rules));
+        rules[1].enabled = true;
+        assert!(uses_clipboard(&rules));
+
+        let empty = TemplateContext {
+            clipboard: None,
+            ..context()
+        };
+        assert_eq!(apply("my link", &rules, &empty).0, "");
+    }
+}
@@ -4,6 +4,7 @@ use anyhow::Result;
 use arboard::Clipboard;
 
 use crate::clipboard::ClipboardManager;
+use crate::commands::CommandKey;
 
 pub struct ClipboardService {
     manager: Mutex<ClipboardManager>,
@@ -22,6 +23,11 @@ impl ClipboardService {
         Ok(())
     }
 
+    pub fn read_text(&self) -> Result<String> {
+        let mut clipboard = Clipboard::new()?;
+        Ok(clipboard.get_text()?)
+    }
+
     pub fn paste_text(&self, text: &str) -> Result<()> {
         self.manager
             .lock()
@@ -29,6 +35,27 @@ impl ClipboardService {
             .paste_text(text)
     }
 
+    pub fn type_text(&self, text: &str) -> Result<()> {
+        self.manager
+            .lock()
+            .expect("clipboard manager poisoned")
+            .type_text(text)
+    }
+
+    pub fn press_key(&self, key: CommandKey) -> Result<()> {
+        self.manager
+            .lock()
+            .expect("clipboard manager poisoned")
+            .press_key(key)
+    }
+
+    pub fn press_backspace(&self, count: usize) -> Result<()> {
+        self.manager
+            .lock()
+            .expect("clipboard manager poisoned")
+            .press_backspace(count)
+    }
+
     pub fn paste_text_and_enter(&self, text: &str) -> Result<()> {
         self.manager
             .lock()
@@ -1,8 +1,18 @@
 use std::sync::Mutex;
 
 use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
 
-use crate::database::{Database, Transcription};
+use crate::database::{
+    Database, HistoryCursor, HistoryFilter, HistoryPage, NewAiUsage, NewTranscription, Revision,
+    SearchHit, Timings, Transcription, UsageTotals,
+};
+use crate::learning::{self, Suggestion};
+use crate::replacements::Replacement;
+use crate::stats::{self, PipelineStats};
+
+/// Entries read per query when loading everything for an export.
+const EXPORT_PAGE_SIZE: usize = 500;
 
 pub struct HistoryService {
     database: Mutex<Database>,
@@ -15,17 +25,11 @@ impl HistoryService {
         }
     }
 
-    pub fn insert_transcription(
-        &self,
-        text: &str,
-        processed_text: Option<&str>,
-        language: Option<&str>,
-        duration_ms: Option<i64>,
-    ) -> Result<i64> {
+    pub fn insert_transcription(&self, entry: &NewTranscription) -> Result<i64> {
         self.database
             .lock()
             .expect("database poisoned")
-            .insert_transcription(text, processed_text, language, duration_ms)
+            .insert_transcription(entry)
     }
 
     pub fn recent(&self, limit: usize) -> Result<Vec<Transcription>> {
@@ -35,11 +39,145 @@ impl HistoryService {
             .get_recent_transcriptions(limit)
     }
 
-    pub fn search(&self, query: &str) -> Result<Vec<Transcription>> {
+    /// A page of entries matching `filter`; pass the previous page's `next`
+    /// cursor to continue.
+    pub fn query(
+        &self,
+        filter: &HistoryFilter,
+        after: Option<&HistoryCursor>,
+        limit: usize,
+    ) -> Result<HistoryPage> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .query_transcriptions(filter, after, limit)
+    }
+
+    /// Every entry matching `filter`, newest first.
+    pub fn all(&self, filter: &HistoryFilter) -> Result<Vec<Transcription>> {
+        let database = self.database.lock().expect("database poisoned");
+        let mut entries = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = database.query_transcriptions(filter, cursor.as_ref(), EXPORT_PAGE_SIZE)?;
+            entries.extend(page.entries);
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(entries),
+            }
+        }
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Transcription>> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .get_transcription(id)
+    }
+
+    pub fn update_processed_text(
+        &self,
+        id: i64,
+        processed_text: &str,
+        mode: Option<&str>,
+    ) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .update_processed_text(id, processed_text, mode)
+    }
+
+    pub fn mark_ai_failed(&self, id: i64, error: &str) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .mark_ai_failed(id, error)
+    }
+
+    pub fn record_timings(&self, id: i64, timings: &Timings) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .record_timings(id, timings)
+    }
+
+    /// Pipeline timings of the dictations from the last `days` days.
+    pub fn pipeline_stats(&self, days: i64) -> Result<PipelineStats> {
+        let since = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+        let samples = self
+            .database
+            .lock()
+            .expect("database poisoned")
+            .timing_samples(&since)?;
+        Ok(stats::summarize(&samples))
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .search_transcriptions(query, limit)
+    }
+
+    /// Saves an edit of the entry's text as its newest revision.
+    pub fn add_revision(&self, id: i64, text: &str) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .add_revision(id, text)
+    }
+
+    pub fn revisions(&self, id: i64) -> Result<Vec<Revision>> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .get_revisions(id)
+    }
+
+    pub fn set_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .set_pinned(id, pinned)
+    }
+
+    /// Adds a tag to an entry. Surrounding whitespace and a leading `#` are
+    /// dropped; an empty tag is ignored.
+    pub fn add_tag(&self, id: i64, tag: &str) -> Result<()> {
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if tag.is_empty() {
+            return Ok(());
+        }
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .add_tag(id, tag)
+    }
+
+    pub fn remove_tag(&self, id: i64, tag: &str) -> Result<()> {
         self.database
             .lock()
             .expect("database poisoned")
-            .search_transcriptions(query)
+            .remove_tag(id, tag)
+    }
+
+    pub fn tags(&self) -> Result<Vec<String>> {
+        self.database.lock().expect("database poisoned").get_tags()
+    }
+
+    /// Deletes entries older than `days` days, except pinned ones, and
+    /// returns how many were removed. A period reaching back past the
+    /// earliest representable date keeps everything.
+    pub fn apply_retention(&self, days: u32) -> Result<usize> {
+        let Some(cutoff) = Utc::now().checked_sub_signed(chrono::Duration::days(i64::from(days)))
+        else {
+            return Ok(0);
+        };
+        let cutoff = cutoff.to_rfc3339();
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .delete_unpinned_before(&cutoff)
     }
 
     pub fn delete(&self, id: i64) -> Result<()> {
@@ -48,4 +186,132 @@ impl HistoryService {
             .expect("database poisoned")
             .delete_transcription(id)
     }
+
+    pub fn replacements(&self) -> Result<Vec<Replacement>> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .get_replacements()
+    }
+
+    /// Corrections the user keeps making by hand that no replacement rule
+    /// covers yet.
+    pub fn correction_suggestions(&self) -> Result<Vec<Suggestion>> {
+        let database = self.database.lock().expect("database poisoned");
+        let corrections = database.get_corrections()?;
+        let rules = database.get_replacements()?;
+        drop(database);
+
+        let mut suggestions = learning::suggest(
+            corrections.iter().map(|(original, corrected)| (original.as_str(), corrected.as_str())),
+            learning::MIN_OCCURRENCES,
+        );
+        suggestions.retain(|s| !rules.iter().any(|r| r.pattern.trim().eq_ignore_ascii_case(&s.heard)));
+        Ok(suggestions)
+    }
+
+    pub fn add_replacement(&self, pattern: &str, replacement: &str) -> Result<i64> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .insert_replacement(pattern, replacement)
+    }
+
+    pub fn set_replacement_enabled(&self, id: i64, enabled: bool) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .set_replacement_enabled(id, enabled)
+    }
+
+    pub fn delete_replacement(&self, id: i64) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .delete_replacement(id)
+    }
+
+    pub fn record_ai_usage(&self, usage: &NewAiUsage) -> Result<()> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .insert_ai_usage(usage)
+    }
+
+    /// Usage since local midnight.
+    pub fn ai_usage_today(&self) -> Result<UsageTotals> {
+        let today = Local::now().date_naive();
+        self.ai_usage_since_local(today.and_time(NaiveTime::MIN))
+    }
+
+    /// Usage since the first of the current month, local time.
+    pub fn ai_usage_this_month(&self) -> Result<UsageTotals> {
+        let month_start = Local::now()
+            .date_naive()
+            .with_day(1)
+            .expect("day 1 exists in every month");
+        self.ai_usage_since_local(month_start.and_time(NaiveTime::MIN))
+    }
+
+    fn ai_usage_since_local(&self, since: NaiveDateTime) -> Result<UsageTotals> {
+        self.database
+            .lock()
+            .expect("database poisoned")
+            .ai_usage_since(&local_to_utc(since).to_rfc3339())
+    }
+}
+
+/// Converts a local wall-clock time. Midnight can be skipped by a DST change;
+/// then it falls back to treating the time as UTC.
+pub fn local_to_utc(time: NaiveDateTime) -> DateTime<Utc> {
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(name: &str) -> HistoryService {
+        let dir = std::env::temp_dir().join(format!("convey-history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        HistoryService::new(Database::new(dir.join("history.db")).unwrap())
+    }
+
+    fn insert(history: &HistoryService, text: &str) -> i64 {
+        history
+            .insert_transcription(&NewTranscription {
+                text: text.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn retention_keeps_pinned_entries() {
+        let history = history("retention");
+        let pinned = insert(&history, "keep me");
+        let unpinned = insert(&history, "drop me");
+        history.set_pinned(pinned, true).unwrap();
+
+        // Nothing is old enough yet
+        assert_eq!(history.apply_retention(30).unwrap(), 0);
+        // With no days to keep, every entry is past the cutoff
+        assert_eq!(history.apply_retention(0).unwrap(), 1);
+        assert!(history.get(pinned).unwrap().is_some());
+        assert!(history.get(unpinned).unwrap().is_none());
+    }
+
+    #[test]
+    fn retention_longer_than_the_calendar_keeps_everything() {
+        let history = history("retention-overflow");
+        let id = insert(&history, "still here");
+
+        assert_eq!(history.apply_retention(u32::MAX).unwrap(), 0);
+        assert!(history.get(id).unwrap().is_some());
+    }
 }
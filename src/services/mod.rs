@@ -1,5 +1,6 @@
 pub mod clipboard;
 pub mod history;
+pub mod preview;
 pub mod recorder;
 pub mod settings;
 
@@ -7,6 +8,7 @@ use std::sync::Arc;
 
 use clipboard::ClipboardService;
 use history::HistoryService;
+use preview::PreviewService;
 use recorder::RecorderService;
 use settings::SettingsService;
 
@@ -17,6 +19,7 @@ pub struct AppServices {
     pub settings: Arc<SettingsService>,
     pub history: Arc<HistoryService>,
     pub clipboard: Arc<ClipboardService>,
+    pub preview: Arc<PreviewService>,
 }
 
 impl AppServices {
@@ -25,12 +28,14 @@ impl AppServices {
         settings: SettingsService,
         history: HistoryService,
         clipboard: ClipboardService,
+        preview: PreviewService,
     ) -> Self {
         Self {
             recorder: Arc::new(recorder),
             settings: Arc::new(settings),
             history: Arc::new(history),
             clipboard: Arc::new(clipboard),
+            preview: Arc::new(preview),
         }
     }
 }
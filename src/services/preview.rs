@@ -0,0 +1,27 @@
+use std::sync::{Arc, Mutex};
+
+/// Text streamed back from the AI provider while a dictation is being
+/// processed. The notch overlay reads the shared buffer to show progress.
+pub struct PreviewService {
+    text: Arc<Mutex<String>>,
+}
+
+impl PreviewService {
+    pub fn new() -> Self {
+        Self {
+            text: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.text.lock().expect("preview poisoned").clear();
+    }
+
+    pub fn push(&self, delta: &str) {
+        self.text.lock().expect("preview poisoned").push_str(delta);
+    }
+
+    pub fn shared(&self) -> Arc<Mutex<String>> {
+        Arc::clone(&self.text)
+    }
+}
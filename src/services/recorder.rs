@@ -4,11 +4,14 @@ use std::sync::{atomic::AtomicU32, Arc, Mutex};
 use anyhow::Result;
 
 use crate::audio::AudioRecorder;
+use crate::frontmost::FrontmostApp;
 
 /// Provides synchronized access to the audio recorder.
 pub struct RecorderService {
     recorder: Mutex<AudioRecorder>,
     meter: Arc<AtomicU32>,
+    /// Application focused when the current recording started.
+    target_app: Mutex<Option<FrontmostApp>>,
 }
 
 impl RecorderService {
@@ -17,14 +20,22 @@ impl RecorderService {
         Self {
             recorder: Mutex::new(recorder),
             meter,
+            target_app: Mutex::new(None),
         }
     }
 
-    pub fn start(&self, output_path: PathBuf) -> Result<()> {
+    pub fn start(&self, output_path: PathBuf, target_app: Option<FrontmostApp>) -> Result<()> {
         self.recorder
             .lock()
             .expect("recorder poisoned")
-            .start_recording(output_path)
+            .start_recording(output_path)?;
+        *self.target_app.lock().expect("target app poisoned") = target_app;
+        Ok(())
+    }
+
+    /// The application captured by [`RecorderService::start`], cleared on read.
+    pub fn take_target_app(&self) -> Option<FrontmostApp> {
+        self.target_app.lock().expect("target app poisoned").take()
     }
 
     pub fn stop(&self) -> Result<PathBuf> {
@@ -2,6 +2,7 @@ use std::sync::Mutex;
 
 use anyhow::Result;
 
+use crate::commands::Grammar;
 use crate::storage::{AppSettings, SecureStorage};
 
 pub struct SettingsService {
@@ -29,6 +30,13 @@ impl SettingsService {
             .save_settings(settings)
     }
 
+    pub fn load_grammar(&self) -> Result<Grammar> {
+        self.storage
+            .lock()
+            .expect("settings storage poisoned")
+            .load_grammar()
+    }
+
     pub fn store_api_key(&self, key_name: &str, api_key: &str) -> Result<()> {
         self.storage
             .lock()
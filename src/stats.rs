@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::database::TimingSample;
+
+/// Spread of one stage's durations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageStats {
+    pub count: usize,
+    pub median_ms: i64,
+    pub p95_ms: i64,
+}
+
+impl StageStats {
+    /// `None` when there are no durations.
+    pub fn from_durations(mut durations: Vec<i64>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+        Some(Self {
+            count: durations.len(),
+            median_ms: percentile(&durations, 50),
+            p95_ms: percentile(&durations, 95),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted durations.
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Whisper timings for one model. `speed` is seconds of audio transcribed per
+/// second of whisper time, so higher is faster and 1.0 is real time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhisperModelStats {
+    pub model: String,
+    pub whisper: StageStats,
+    pub speed: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiModelStats {
+    pub model: String,
+    pub ai: StageStats,
+}
+
+/// Timings over a period, overall and broken down by model and day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PipelineStats {
+    pub entries: usize,
+    pub total: Option<StageStats>,
+    pub whisper: Option<StageStats>,
+    pub ai: Option<StageStats>,
+    pub paste: Option<StageStats>,
+    pub by_whisper_model: Vec<WhisperModelStats>,
+    pub by_ai_model: Vec<AiModelStats>,
+    /// Key-up to text per local day, oldest first, for spotting regressions.
+    pub daily_total: Vec<(NaiveDate, StageStats)>,
+}
+
+pub fn summarize(samples: &[TimingSample]) -> PipelineStats {
+    let stage = |pick: fn(&TimingSample) -> Option<i64>| {
+        StageStats::from_durations(samples.iter().filter_map(pick).collect())
+    };
+
+    let mut whisper_models: BTreeMap<&str, Vec<&TimingSample>> = BTreeMap::new();
+    let mut ai_models: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    let mut days: BTreeMap<NaiveDate, Vec<i64>> = BTreeMap::new();
+    for sample in samples {
+        if let Some(model) = &sample.whisper_model {
+            whisper_models.entry(model).or_default().push(sample);
+        }
+        if let (Some(model), Some(ai_ms)) = (&sample.ai_model, sample.timings.ai_ms) {
+            ai_models.entry(model).or_default().push(ai_ms);
+        }
+        if let (Some(day), Some(total_ms)) = (local_day(&sample.created_at), sample.timings.total_ms) {
+            days.entry(day).or_default().push(total_ms);
+        }
+    }
+
+    let by_whisper_model = whisper_models
+        .into_iter()
+        .filter_map(|(model, samples)| {
+            let whisper = StageStats::from_durations(
+                samples.iter().filter_map(|s| s.timings.whisper_ms).collect(),
+            )?;
+            // Only entries with both numbers say anything about speed
+            let (audio_ms, whisper_ms) = samples
+                .iter()
+                .filter_map(|s| Some((s.duration_ms?, s.timings.whisper_ms?)))
+                .fold((0, 0), |(audio, whisper), (a, w)| (audio + a, whisper + w));
+            Some(WhisperModelStats {
+                model: model.to_string(),
+                whisper,
+                speed: (whisper_ms > 0).then(|| audio_ms as f64 / whisper_ms as f64),
+            })
+        })
+        .collect();
+
+    let by_ai_model = ai_models
+        .into_iter()
+        .filter_map(|(model, durations)| {
+            Some(AiModelStats {
+                model: model.to_string(),
+                ai: StageStats::from_durations(durations)?,
+            })
+        })
+        .collect();
+
+    let daily_total = days
+        .into_iter()
+        .filter_map(|(day, durations)| Some((day, StageStats::from_durations(durations)?)))
+        .collect();
+
+    PipelineStats {
+        entries: samples.len(),
+        total: stage(|s| s.timings.total_ms),
+        whisper: stage(|s| s.timings.whisper_ms),
+        ai: stage(|s| s.timings.ai_ms),
+        paste: stage(|s| s.timings.paste_ms),
+        by_whisper_model,
+        by_ai_model,
+        daily_total,
+    }
+}
+
+fn local_day(created_at: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(created_at)
+        .ok()
+        .map(|dt| dt.with_timezone(&Local).date_naive())
+}
@@ -1,6 +1,14 @@
 use anyhow::{anyhow, Context, Result};
 use keyring::{Entry, Error as KeyringError};
+
+use crate::ai::{ModelPrice, ProviderConfig};
+use crate::commands::Grammar;
+use crate::disfluency;
+use crate::frontmost::FrontmostApp;
+use crate::modes::{AppModeOverride, ProcessingMode};
+use crate::redaction::RedactionSettings;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -21,6 +29,148 @@ pub struct AppSettings {
     pub whisper_cli_path: Option<String>,
     #[serde(default)]
     pub recognize_press_enter: bool,
+    #[serde(default = "default_low_confidence_threshold")]
+    pub low_confidence_threshold: f32,
+    #[serde(default)]
+    pub hold_low_confidence_paste: bool,
+    #[serde(default = "default_min_paste_confidence")]
+    pub min_paste_confidence: f32,
+    #[serde(default = "ProviderConfig::defaults")]
+    pub providers: Vec<ProviderConfig>,
+    #[serde(default = "default_active_provider")]
+    pub active_provider: String,
+    #[serde(default = "ProcessingMode::defaults")]
+    pub modes: Vec<ProcessingMode>,
+    #[serde(default = "default_active_mode")]
+    pub active_mode: String,
+    #[serde(default = "default_ai_timeout_secs")]
+    pub ai_timeout_secs: u64,
+    #[serde(default = "default_ai_max_retries")]
+    pub ai_max_retries: u32,
+    #[serde(default = "default_ai_stream")]
+    pub ai_stream: bool,
+    #[serde(default)]
+    pub type_streamed_output: bool,
+    #[serde(default = "ModelPrice::defaults")]
+    pub model_prices: Vec<ModelPrice>,
+    #[serde(default)]
+    pub monthly_ai_budget_usd: Option<f64>,
+    #[serde(default)]
+    pub app_modes: Vec<AppModeOverride>,
+    #[serde(default)]
+    pub recognize_edit_commands: bool,
+    #[serde(default)]
+    pub spoken_formatting: bool,
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    #[serde(default)]
+    pub inverse_text_normalization: bool,
+    #[serde(default)]
+    pub remove_fillers: bool,
+    /// Filler words and phrases by language code.
+    #[serde(default = "disfluency::default_fillers")]
+    pub filler_words: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    /// Days to keep unpinned history; `None` keeps everything.
+    #[serde(default)]
+    pub history_retention_days: Option<u32>,
+}
+
+impl AppSettings {
+    pub fn active_provider_config(&self) -> Option<&ProviderConfig> {
+        self.providers.iter().find(|p| p.id == self.active_provider)
+    }
+
+    pub fn mode(&self, id: &str) -> Option<&ProcessingMode> {
+        self.modes.iter().find(|m| m.id == id)
+    }
+
+    pub fn active_mode(&self) -> Option<&ProcessingMode> {
+        self.mode(&self.active_mode)
+    }
+
+    /// Whether spoken punctuation ("comma", "open paren") is turned into
+    /// symbols, per mode with the global setting as fallback.
+    pub fn spoken_formatting_for(&self, mode: Option<&ProcessingMode>) -> bool {
+        mode.and_then(|m| m.spoken_formatting).unwrap_or(self.spoken_formatting)
+    }
+
+    /// Whether spoken numbers, money, times and dates are written with
+    /// digits, per mode with the global setting as fallback.
+    pub fn inverse_text_normalization_for(&self, mode: Option<&ProcessingMode>) -> bool {
+        mode.and_then(|m| m.inverse_text_normalization)
+            .unwrap_or(self.inverse_text_normalization)
+    }
+
+    /// Filler words for the dictation language; English when it is detected
+    /// automatically.
+    pub fn fillers(&self) -> &[String] {
+        let language = self
+            .language
+            .as_deref()
+            .filter(|l| !l.is_empty() && *l != "auto")
+            .unwrap_or("en");
+        let code = language.split(['-', '_']).next().unwrap_or(language);
+        self.filler_words
+            .get(&code.to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Copies a non-empty `system_prompt` into the default mode's prompt.
+    fn adopt_system_prompt(&mut self) {
+        let Some(prompt) = self.system_prompt.clone().filter(|p| !p.trim().is_empty()) else {
+            return;
+        };
+        let default_mode = default_active_mode();
+        if let Some(mode) = self.modes.iter_mut().find(|m| m.id == default_mode) {
+            mode.prompt = prompt;
+        }
+    }
+
+    /// Mode configured for dictating into `app`, if any.
+    pub fn mode_for_app(&self, app: &FrontmostApp) -> Option<&ProcessingMode> {
+        self.app_modes
+            .iter()
+            .find(|o| app.matches(&o.app))
+            .and_then(|o| self.mode(&o.mode))
+    }
+
+    /// Mode for dictating into `app`: the one configured for it, else the
+    /// active mode.
+    pub fn mode_for_target(&self, app: Option<&FrontmostApp>) -> Option<&ProcessingMode> {
+        app.and_then(|app| self.mode_for_app(app))
+            .or_else(|| self.active_mode())
+    }
+}
+
+fn default_ai_timeout_secs() -> u64 {
+    30
+}
+
+fn default_ai_max_retries() -> u32 {
+    2
+}
+
+fn default_ai_stream() -> bool {
+    true
+}
+
+fn default_active_mode() -> String {
+    "clean_up".to_string()
+}
+
+fn default_active_provider() -> String {
+    "openai".to_string()
+}
+
+fn default_low_confidence_threshold() -> f32 {
+    0.5
+}
+
+fn default_min_paste_confidence() -> f32 {
+    0.7
 }
 
 impl Default for AppSettings {
@@ -39,7 +189,29 @@ impl Default for AppSettings {
             ),
             hotkey: "Fn".to_string(), // Default to Fn key (Globe key on newer Macs)
             whisper_cli_path: None,
-            recognize_press_enter: true, // Enable voice command "and press enter" detection by default
+            recognize_press_enter: true, // Voice commands from commands.json ("and press enter", "new line", ...)
+            low_confidence_threshold: default_low_confidence_threshold(), // Words below this are highlighted in history
+            hold_low_confidence_paste: false, // Copy instead of paste when the average confidence is low
+            min_paste_confidence: default_min_paste_confidence(),
+            providers: ProviderConfig::defaults(),
+            active_provider: default_active_provider(),
+            modes: ProcessingMode::defaults(),
+            active_mode: default_active_mode(),
+            ai_timeout_secs: default_ai_timeout_secs(),
+            ai_max_retries: default_ai_max_retries(),
+            ai_stream: default_ai_stream(), // Show AI output in the overlay as it streams in
+            type_streamed_output: false, // Type streamed AI output into the focused app (needs auto paste)
+            model_prices: ModelPrice::defaults(),
+            monthly_ai_budget_usd: None, // No spending cap unless the user sets one
+            app_modes: Vec::new(),
+            recognize_edit_commands: false, // "Scratch that" presses backspace in the focused app, so only when turned on
+            spoken_formatting: false, // "comma" stays a word unless turned on
+            vocabulary: Vec::new(), // Terms whisper should prefer, e.g. learned from corrections
+            inverse_text_normalization: false, // "twenty five percent" stays spelled out unless turned on
+            remove_fillers: false, // Keep "um" and "I I think" verbatim unless turned on
+            filler_words: disfluency::default_fillers(),
+            redaction: RedactionSettings::default(), // No redaction unless a point is turned on
+            history_retention_days: None, // Keep history until it is deleted by hand
         }
     }
 }
@@ -108,7 +280,93 @@ impl SecureStorage {
         }
 
         let json = fs::read_to_string(&self.config_path).context("Failed to read settings file")?;
-        let settings = serde_json::from_str(&json).context("Failed to deserialize settings")?;
+        let value: serde_json::Value =
+            serde_json::from_str(&json).context("Failed to deserialize settings")?;
+        let saved_before_modes = value.get("modes").is_none();
+        let mut settings: AppSettings =
+            serde_json::from_value(value).context("Failed to deserialize settings")?;
+        // Settings saved before modes existed kept the prompt in `system_prompt`;
+        // it becomes the default mode's prompt so a customised one keeps working
+        if saved_before_modes {
+            settings.adopt_system_prompt();
+            self.save_settings(&settings)?;
+        }
+        // Settings saved before code dictation existed don't list the mode
+        if !settings.modes.iter().any(ProcessingMode::is_code) {
+            settings.modes.push(ProcessingMode::code());
+        }
         Ok(settings)
     }
+
+    fn grammar_path(&self) -> PathBuf {
+        self.config_path.with_file_name("commands.json")
+    }
+
+    // Voice command grammar in its own JSON file, written out with the
+    // defaults on first use so it can be edited by hand
+    pub fn load_grammar(&self) -> Result<Grammar> {
+        let path = self.grammar_path();
+        if !path.exists() {
+            let grammar = Grammar::default();
+            let json =
+                serde_json::to_string_pretty(&grammar).context("Failed to serialize grammar")?;
+            fs::write(&path, json).context("Failed to write command grammar file")?;
+            return Ok(grammar);
+        }
+
+        let json = fs::read_to_string(&path).context("Failed to read command grammar file")?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid command grammar in {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(name: &str) -> SecureStorage {
+        let dir = std::env::temp_dir().join(format!("convey-settings-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        SecureStorage::new(dir.join("settings.json")).unwrap()
+    }
+
+    fn legacy_settings(system_prompt: &str) -> String {
+        serde_json::json!({
+            "whisper_model": "whisper-1",
+            "ai_model": "gpt-4o-mini",
+            "language": "en",
+            "auto_paste": true,
+            "ai_processing_enabled": true,
+            "system_prompt": system_prompt,
+            "hotkey": "Fn",
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn custom_system_prompt_becomes_the_default_mode_prompt() {
+        let storage = storage("legacy");
+        fs::write(&storage.config_path, legacy_settings("Answer like a pirate.")).unwrap();
+
+        let settings = storage.load_settings().unwrap();
+        assert_eq!(settings.active_mode().unwrap().prompt, "Answer like a pirate.");
+        assert_eq!(settings.mode("email"), ProcessingMode::defaults().iter().find(|m| m.id == "email"));
+
+        // Saved, so a later edit of the mode prompt isn't overwritten again
+        let saved = fs::read_to_string(&storage.config_path).unwrap();
+        assert!(saved.contains("\"modes\""));
+        let mut settings = settings;
+        settings.modes[0].prompt = "Edited.".to_string();
+        storage.save_settings(&settings).unwrap();
+        assert_eq!(storage.load_settings().unwrap().active_mode().unwrap().prompt, "Edited.");
+    }
+
+    #[test]
+    fn empty_system_prompt_keeps_the_default_mode_prompt() {
+        let storage = storage("empty");
+        fs::write(&storage.config_path, legacy_settings(" ")).unwrap();
+
+        let settings = storage.load_settings().unwrap();
+        assert_eq!(settings.active_mode(), ProcessingMode::defaults().first());
+    }
 }
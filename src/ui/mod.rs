@@ -3,7 +3,8 @@ mod theme;
 use iced::theme::{Button, Theme};
 use iced::time;
 use iced::widget::{
-    button, column, container, row, scrollable, svg, text, toggler,
+    button, column, container, pick_list, row, scrollable, svg, text, text_editor, text_input,
+    toggler,
 };
 use iced::{
     executor, window, Alignment, Application, Border, Color, Command, Element, Font, Length, Settings,
@@ -24,13 +25,29 @@ const HEART_SVG: &[u8] = include_bytes!("../../assets/heart.svg");
 // Instead we'll use Unicode symbols that IBM Plex Mono supports
 
 use crate::{
-    database::Transcription, notch::NotchOverlay, services::AppServices, storage::AppSettings,
-    workflow,
+    ai::ProviderConfig,
+    database::{
+        AiStatus, HistoryCursor, HistoryFilter, HistoryPage, Revision, SearchHit, Transcription,
+        UsageTotals,
+    },
+    diff::{word_diff, DiffOp},
+    export::{self, ExportFormat},
+    learning::Suggestion,
+    modes::ProcessingMode,
+    notch::NotchOverlay,
+    replacements::Replacement,
+    services::AppServices,
+    stats::{PipelineStats, StageStats},
+    storage::AppSettings,
+    whisper::Segment,
+    workflow::{self, TranscriptionOutcome},
 };
 use global_hotkey::{
-    GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState,
+    hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState,
 };
 use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::{mpsc, Mutex, Arc};
 use std::thread;
 use std::time::Duration;
@@ -42,12 +59,12 @@ static HOTKEY_MANAGER: Lazy<Mutex<GlobalHotKeyManager>> = Lazy::new(|| {
     Mutex::new(GlobalHotKeyManager::new().expect("Failed to initialize hotkey manager"))
 });
 
-static HOTKEY_EVENTS: Lazy<Mutex<mpsc::Receiver<HotKeyState>>> = Lazy::new(|| {
+static HOTKEY_EVENTS: Lazy<Mutex<mpsc::Receiver<(u32, HotKeyState)>>> = Lazy::new(|| {
     let (tx, rx) = mpsc::channel();
     thread::spawn(move || {
         let receiver = GlobalHotKeyEvent::receiver();
         while let Ok(event) = receiver.recv() {
-            let _ = tx.send(event.state);
+            let _ = tx.send((event.id, event.state));
         }
     });
     Mutex::new(rx)
@@ -82,17 +99,72 @@ pub fn run(services: AppServices) -> iced::Result {
 #[derive(Clone, Debug)]
 pub enum Message {
     Initialize,
-    SettingsLoaded(Result<AppSettings, String>),
-    HistoryLoaded(Result<Vec<Transcription>, String>),
+    SettingsLoaded(Result<Box<AppSettings>, String>),
+    HistoryLoaded(Result<HistoryPage, String>),
     RecordPressed,
     RecordingStarted(Result<(), String>),
     StopPressed,
-    RecordingStopped(Result<String, String>),
+    RecordingStopped(Result<TranscriptionOutcome, String>),
     ToggleAutoPaste(bool),
     ToggleRecognizePressEnter(bool),
+    ToggleHoldLowConfidence(bool),
+    ToggleTypeStreamed(bool),
+    ToggleSpokenFormatting(bool),
+    ToggleNumberFormatting(bool),
+    ToggleRemoveFillers(bool),
+    ModeSelected(ProcessingMode),
+    ProviderSelected(ProviderConfig),
     SettingsSaved(Result<(), String>),
     HistoryDelete(i64),
     HistoryCopied(String),
+    HistoryToggleDiff(i64),
+    HistoryReprocess(i64, Option<String>),
+    HistoryReprocessed(Result<HistoryPage, String>),
+    HistoryMore,
+    HistoryMoreLoaded(Result<HistoryPage, String>),
+    ToggleAiProcessedFilter(bool),
+    TogglePinnedFilter(bool),
+    HistoryTogglePin(i64, bool),
+    /// Opens the tag input on an entry.
+    HistoryTagStart(i64),
+    HistoryTagChanged(String),
+    HistoryTagSubmit,
+    HistoryTagRemove(i64, String),
+    /// Shows only entries with this tag, or all entries.
+    FilterByTag(Option<String>),
+    /// Opens the editor on an entry with its current text.
+    HistoryEditStart(i64, String),
+    HistoryEditAction(text_editor::Action),
+    HistoryEditSave,
+    HistoryEditCancel,
+    /// Shows or hides an entry's earlier versions.
+    HistoryToggleRevisions(i64),
+    RevisionsLoaded(i64, Result<Vec<Revision>, String>),
+    /// Makes an earlier version the entry's text again.
+    HistoryRestore(i64, String),
+    /// Result of changing an entry's text, pin or tags.
+    HistoryEdited(Result<(), String>),
+    TagsLoaded(Result<Vec<String>, String>),
+    /// Exports the entries matching the current filters.
+    HistoryExport(ExportFormat),
+    /// How many entries were written, and where.
+    HistoryExported(Result<(usize, PathBuf), String>),
+    AiUsageLoaded(Result<(UsageTotals, UsageTotals), String>),
+    ToggleReplacements,
+    ToggleStats,
+    StatsLoaded(Result<PipelineStats, String>),
+    ReplacementsLoaded(Result<Vec<Replacement>, String>),
+    ReplacementPatternChanged(String),
+    ReplacementTextChanged(String),
+    ReplacementAdd,
+    ReplacementToggle(i64, bool),
+    ReplacementDelete(i64),
+    SuggestionsLoaded(Result<Vec<Suggestion>, String>),
+    SuggestionAddRule(Suggestion),
+    SuggestionAddVocabulary(String),
+    SearchChanged(String),
+    /// Results for the query they were searched with.
+    SearchResults(String, Result<Vec<SearchHit>, String>),
     PollHotkey,
 }
 
@@ -102,11 +174,48 @@ pub struct App {
     settings_draft: Option<AppSettings>,
     settings_saving: bool,
     history: Vec<Transcription>,
+    /// Where the next page of history starts; `None` once all is loaded.
+    history_next: Option<HistoryCursor>,
+    history_filter: HistoryFilter,
+    /// Tags in use, offered as filters.
+    tags: Vec<String>,
+    /// Entry whose tag input is open, and what has been typed.
+    tagging: Option<(i64, String)>,
+    /// Entry whose text is being edited.
+    editing: Option<(i64, text_editor::Content)>,
+    /// Entry whose revisions are shown, newest first.
+    revisions_open: Option<(i64, Vec<Revision>)>,
     is_recording: bool,
     is_processing: bool,
     last_transcription: Option<String>,
     error: Option<String>,
+    notice: Option<String>,
     notch_overlay: NotchOverlay,
+    /// Registered global hotkeys and the mode each records into
+    /// (`None` = the active mode).
+    hotkey_bindings: Vec<(HotKey, Option<String>)>,
+    /// Mode chosen for the recording in progress, if it was started by a mode hotkey.
+    recording_mode: Option<String>,
+    /// History entries showing the raw/processed diff.
+    diff_open: HashSet<i64>,
+    /// Entry currently being re-run through AI processing.
+    reprocessing: Option<i64>,
+    /// AI usage for today and this month.
+    ai_usage: Option<(UsageTotals, UsageTotals)>,
+    /// Show the replacement dictionary instead of recent transcriptions.
+    show_replacements: bool,
+    replacements: Vec<Replacement>,
+    /// New rule being typed in the replacements view.
+    replacement_pattern: String,
+    replacement_text: String,
+    /// Replacements learned from the user's corrections.
+    suggestions: Vec<Suggestion>,
+    /// Show pipeline timings instead of recent transcriptions.
+    show_stats: bool,
+    pipeline_stats: Option<PipelineStats>,
+    /// History search box; when non-empty the list shows `search_results`.
+    search_query: String,
+    search_results: Vec<SearchHit>,
 }
 
 impl Application for App {
@@ -119,7 +228,7 @@ impl Application for App {
         Lazy::force(&HOTKEY_MANAGER);
         Lazy::force(&HOTKEY_EVENTS);
 
-        let overlay = NotchOverlay::new(flags.recorder.meter());
+        let overlay = NotchOverlay::new(flags.recorder.meter(), flags.preview.shared());
         (
             Self {
                 services: flags,
@@ -127,11 +236,32 @@ impl Application for App {
                 settings_draft: None,
                 settings_saving: false,
                 history: Vec::new(),
+                history_next: None,
+                history_filter: HistoryFilter::default(),
+                tags: Vec::new(),
+                tagging: None,
+                editing: None,
+                revisions_open: None,
                 is_recording: false,
                 is_processing: false,
                 last_transcription: None,
                 error: None,
+                notice: None,
                 notch_overlay: overlay,
+                hotkey_bindings: Vec::new(),
+                recording_mode: None,
+                diff_open: HashSet::new(),
+                reprocessing: None,
+                ai_usage: None,
+                show_replacements: false,
+                replacements: Vec::new(),
+                replacement_pattern: String::new(),
+                replacement_text: String::new(),
+                suggestions: Vec::new(),
+                show_stats: false,
+                pipeline_stats: None,
+                search_query: String::new(),
+                search_results: Vec::new(),
             },
             Command::perform(async {}, |_| Message::Initialize),
         )
@@ -163,28 +293,26 @@ impl Application for App {
         match message {
             Message::Initialize => {
                 let services = self.services.clone();
-                let services_history = self.services.clone();
                 Command::batch(vec![
-                    Command::perform(
-                        async move { services.settings.load().map_err(|e| e.to_string()) },
-                        Message::SettingsLoaded,
-                    ),
                     Command::perform(
                         async move {
-                            services_history
-                                .history
-                                .recent(50)
+                            services
+                                .settings
+                                .load()
+                                .map(Box::new)
                                 .map_err(|e| e.to_string())
                         },
-                        Message::HistoryLoaded,
+                        Message::SettingsLoaded,
                     ),
+                    self.reload_history(),
                 ])
             }
             Message::SettingsLoaded(result) => {
                 match result {
                     Ok(settings) => {
-                        self.settings_draft = Some(settings.clone());
-                        self.settings = Some(settings);
+                        self.settings_draft = Some((*settings).clone());
+                        self.settings = Some(*settings);
+                        self.register_hotkeys();
                     }
                     Err(err) => self.error = Some(err),
                 }
@@ -192,13 +320,14 @@ impl Application for App {
             }
             Message::HistoryLoaded(result) => {
                 match result {
-                    Ok(list) => self.history = list,
+                    Ok(page) => self.set_history(page),
                     Err(err) => self.error = Some(err),
                 }
-                Command::none()
+                // Refresh the results too, in case an entry changed or went away
+                Command::batch([self.load_ai_usage(), self.search_command(), self.load_tags()])
             }
             Message::RecordPressed => {
-                return self.start_recording_command();
+                return self.start_recording_command(None);
             }
             Message::RecordingStarted(result) => {
                 self.is_processing = false;
@@ -221,14 +350,31 @@ impl Application for App {
                 self.is_processing = false;
                 self.notch_overlay.hide();
                 match result {
-                    Ok(text) => {
-                        self.last_transcription = Some(text);
+                    Ok(outcome) => {
+                        self.notice = outcome.held_for_review.map(|confidence| {
+                            format!(
+                                "Low confidence ({:.0}%), copied to clipboard for review instead of pasting",
+                                confidence * 100.0
+                            )
+                        });
+                        if let Some(ai_error) = &outcome.ai_error {
+                            let ai_notice = format!(
+                                "AI processing failed, used the raw transcript. Retry from history. ({})",
+                                ai_error
+                            );
+                            self.notice = Some(match self.notice.take() {
+                                Some(notice) => format!("{}\n{}", notice, ai_notice),
+                                None => ai_notice,
+                            });
+                        }
+                        if let Some(command) = outcome.command {
+                            self.notice = Some(command);
+                        }
+                        if !outcome.text.is_empty() {
+                            self.last_transcription = Some(outcome.text);
+                        }
                         self.is_recording = false;
-                        let services = self.services.clone();
-                        return Command::perform(
-                            async move { services.history.recent(50).map_err(|e| e.to_string()) },
-                            Message::HistoryLoaded,
-                        );
+                        return self.reload_history();
                     }
                     Err(err) => {
                         self.error = Some(err);
@@ -253,6 +399,62 @@ impl Application for App {
                 }
                 Command::none()
             }
+            Message::ToggleHoldLowConfidence(value) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.hold_low_confidence_paste = value;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ToggleTypeStreamed(value) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.type_streamed_output = value;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ToggleSpokenFormatting(value) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.spoken_formatting = value;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ToggleNumberFormatting(value) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.inverse_text_normalization = value;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ToggleRemoveFillers(value) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.remove_fillers = value;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ModeSelected(mode) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.active_mode = mode.id;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
+            Message::ProviderSelected(provider) => {
+                if let Some(settings) = &mut self.settings_draft {
+                    settings.active_provider = provider.id;
+                    // Auto-save
+                    return self.save_settings_command();
+                }
+                Command::none()
+            }
             Message::SettingsSaved(result) => {
                 self.settings_saving = false;
                 match result {
@@ -260,6 +462,7 @@ impl Application for App {
                         if let Some(draft) = &self.settings_draft {
                             self.settings = Some(draft.clone());
                         }
+                        self.register_hotkeys();
                     }
                     Err(err) => self.error = Some(err),
                 }
@@ -267,10 +470,15 @@ impl Application for App {
             }
             Message::HistoryDelete(id) => {
                 let services = self.services.clone();
+                let filter = self.history_filter.clone();
+                let limit = self.history_reload_limit();
                 Command::perform(
                     async move {
                         services.history.delete(id).map_err(|e| e.to_string())?;
-                        services.history.recent(50).map_err(|e| e.to_string())
+                        services
+                            .history
+                            .query(&filter, None, limit)
+                            .map_err(|e| e.to_string())
                     },
                     Message::HistoryLoaded,
                 )
@@ -281,6 +489,342 @@ impl Application for App {
                 }
                 Command::none()
             }
+            Message::HistoryToggleDiff(id) => {
+                if !self.diff_open.remove(&id) {
+                    self.diff_open.insert(id);
+                }
+                Command::none()
+            }
+            Message::HistoryReprocess(id, mode_id) => {
+                if self.reprocessing.is_some() {
+                    return Command::none();
+                }
+                self.reprocessing = Some(id);
+                self.error = None;
+                let services = self.services.clone();
+                let filter = self.history_filter.clone();
+                let limit = self.history_reload_limit();
+                Command::perform(
+                    async move {
+                        workflow::reprocess_entry(services.clone(), id, mode_id).await?;
+                        services
+                            .history
+                            .query(&filter, None, limit)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::HistoryReprocessed,
+                )
+            }
+            Message::HistoryReprocessed(result) => {
+                self.reprocessing = None;
+                match result {
+                    Ok(page) => self.set_history(page),
+                    Err(err) => self.error = Some(err),
+                }
+                Command::batch([self.load_ai_usage(), self.search_command()])
+            }
+            Message::HistoryMore => {
+                let Some(cursor) = self.history_next.take() else {
+                    return Command::none();
+                };
+                let services = self.services.clone();
+                let filter = self.history_filter.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .query(&filter, Some(&cursor), HISTORY_PAGE_SIZE)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::HistoryMoreLoaded,
+                )
+            }
+            Message::HistoryMoreLoaded(result) => {
+                match result {
+                    Ok(page) => {
+                        self.history.extend(page.entries);
+                        self.history_next = page.next;
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+                Command::none()
+            }
+            Message::ToggleAiProcessedFilter(value) => {
+                self.history_filter.ai_processed = value.then_some(true);
+                self.history.clear();
+                self.reload_history()
+            }
+            Message::TogglePinnedFilter(value) => {
+                self.history_filter.pinned_only = value;
+                self.history.clear();
+                self.reload_history()
+            }
+            Message::FilterByTag(tag) => {
+                self.history_filter.tag = tag;
+                self.history.clear();
+                self.reload_history()
+            }
+            Message::HistoryTogglePin(id, pinned) => {
+                let services = self.services.clone();
+                Command::perform(
+                    async move { services.history.set_pinned(id, pinned).map_err(|e| e.to_string()) },
+                    Message::HistoryEdited,
+                )
+            }
+            Message::HistoryTagStart(id) => {
+                self.tagging = Some((id, String::new()));
+                Command::none()
+            }
+            Message::HistoryTagChanged(value) => {
+                if let Some((_, draft)) = self.tagging.as_mut() {
+                    *draft = value;
+                }
+                Command::none()
+            }
+            Message::HistoryTagSubmit => {
+                let Some((id, tag)) = self.tagging.take() else {
+                    return Command::none();
+                };
+                let services = self.services.clone();
+                Command::perform(
+                    async move { services.history.add_tag(id, &tag).map_err(|e| e.to_string()) },
+                    Message::HistoryEdited,
+                )
+            }
+            Message::HistoryTagRemove(id, tag) => {
+                let services = self.services.clone();
+                Command::perform(
+                    async move { services.history.remove_tag(id, &tag).map_err(|e| e.to_string()) },
+                    Message::HistoryEdited,
+                )
+            }
+            Message::HistoryEditStart(id, current) => {
+                self.editing = Some((id, text_editor::Content::with_text(&current)));
+                Command::none()
+            }
+            Message::HistoryEditAction(action) => {
+                if let Some((_, content)) = self.editing.as_mut() {
+                    content.perform(action);
+                }
+                Command::none()
+            }
+            Message::HistoryEditSave => {
+                let Some((id, content)) = self.editing.take() else {
+                    return Command::none();
+                };
+                self.save_revision(id, content.text())
+            }
+            Message::HistoryEditCancel => {
+                self.editing = None;
+                Command::none()
+            }
+            Message::HistoryToggleRevisions(id) => {
+                if self.revisions_open.take().is_some_and(|(open, _)| open == id) {
+                    return Command::none();
+                }
+                self.revisions_open = Some((id, Vec::new()));
+                self.load_revisions()
+            }
+            Message::RevisionsLoaded(id, result) => {
+                match result {
+                    Ok(list) => {
+                        if let Some((_, revisions)) =
+                            self.revisions_open.as_mut().filter(|(open, _)| *open == id)
+                        {
+                            *revisions = list;
+                        }
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+                Command::none()
+            }
+            Message::HistoryRestore(id, text) => self.save_revision(id, text),
+            Message::HistoryEdited(result) => {
+                if let Err(err) = result {
+                    self.error = Some(err);
+                }
+                Command::batch([self.reload_history(), self.search_command(), self.load_revisions()])
+            }
+            Message::HistoryExport(format) => {
+                self.error = None;
+                let services = self.services.clone();
+                let filter = self.history_filter.clone();
+                Command::perform(
+                    async move {
+                        let entries = services.history.all(&filter).map_err(|e| e.to_string())?;
+                        let rendered = export::render(&entries, format).map_err(|e| e.to_string())?;
+                        let path = export::default_path(format);
+                        std::fs::write(&path, rendered).map_err(|e| e.to_string())?;
+                        Ok((entries.len(), path))
+                    },
+                    Message::HistoryExported,
+                )
+            }
+            Message::HistoryExported(result) => {
+                match result {
+                    Ok((count, path)) => {
+                        self.notice = Some(format!("Exported {} entries to {}", count, path.display()))
+                    }
+                    Err(err) => self.error = Some(format!("Export failed: {}", err)),
+                }
+                Command::none()
+            }
+            Message::TagsLoaded(result) => {
+                match result {
+                    Ok(tags) => self.tags = tags,
+                    Err(err) => log::warn!("Failed to load tags: {}", err),
+                }
+                Command::none()
+            }
+            Message::AiUsageLoaded(result) => {
+                match result {
+                    Ok(usage) => self.ai_usage = Some(usage),
+                    Err(err) => log::warn!("Failed to load AI usage: {}", err),
+                }
+                Command::none()
+            }
+            Message::ToggleReplacements => {
+                self.show_replacements = !self.show_replacements;
+                self.show_stats = false;
+                if self.show_replacements {
+                    Command::batch([self.load_replacements(), self.load_suggestions()])
+                } else {
+                    Command::none()
+                }
+            }
+            Message::ReplacementsLoaded(result) => {
+                match result {
+                    Ok(list) => self.replacements = list,
+                    Err(err) => self.error = Some(err),
+                }
+                Command::none()
+            }
+            Message::SuggestionsLoaded(result) => {
+                match result {
+                    Ok(list) => self.suggestions = list,
+                    Err(err) => log::warn!("Failed to load correction suggestions: {}", err),
+                }
+                Command::none()
+            }
+            Message::SuggestionAddRule(suggestion) => {
+                self.suggestions.retain(|s| s != &suggestion);
+                let services = self.services.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .add_replacement(&suggestion.heard, &suggestion.corrected)
+                            .map_err(|e| e.to_string())?;
+                        services.history.replacements().map_err(|e| e.to_string())
+                    },
+                    Message::ReplacementsLoaded,
+                )
+            }
+            Message::SuggestionAddVocabulary(term) => {
+                self.suggestions.retain(|s| s.corrected != term);
+                if let Some(settings) = &mut self.settings_draft {
+                    if !settings.vocabulary.contains(&term) {
+                        settings.vocabulary.push(term);
+                        return self.save_settings_command();
+                    }
+                }
+                Command::none()
+            }
+            Message::ReplacementPatternChanged(value) => {
+                self.replacement_pattern = value;
+                Command::none()
+            }
+            Message::ReplacementTextChanged(value) => {
+                self.replacement_text = value;
+                Command::none()
+            }
+            Message::ReplacementAdd => {
+                let pattern = self.replacement_pattern.trim().to_string();
+                if pattern.is_empty() {
+                    return Command::none();
+                }
+                let replacement = std::mem::take(&mut self.replacement_text);
+                self.replacement_pattern.clear();
+                let services = self.services.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .add_replacement(&pattern, &replacement)
+                            .map_err(|e| e.to_string())?;
+                        services.history.replacements().map_err(|e| e.to_string())
+                    },
+                    Message::ReplacementsLoaded,
+                )
+            }
+            Message::ReplacementToggle(id, enabled) => {
+                let services = self.services.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .set_replacement_enabled(id, enabled)
+                            .map_err(|e| e.to_string())?;
+                        services.history.replacements().map_err(|e| e.to_string())
+                    },
+                    Message::ReplacementsLoaded,
+                )
+            }
+            Message::ReplacementDelete(id) => {
+                let services = self.services.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .delete_replacement(id)
+                            .map_err(|e| e.to_string())?;
+                        services.history.replacements().map_err(|e| e.to_string())
+                    },
+                    Message::ReplacementsLoaded,
+                )
+            }
+            Message::ToggleStats => {
+                self.show_stats = !self.show_stats;
+                self.show_replacements = false;
+                if !self.show_stats {
+                    return Command::none();
+                }
+                let services = self.services.clone();
+                Command::perform(
+                    async move {
+                        services
+                            .history
+                            .pipeline_stats(STATS_DAYS)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::StatsLoaded,
+                )
+            }
+            Message::StatsLoaded(result) => {
+                match result {
+                    Ok(stats) => self.pipeline_stats = Some(stats),
+                    Err(err) => self.error = Some(err),
+                }
+                Command::none()
+            }
+            Message::SearchChanged(query) => {
+                self.search_query = query;
+                if self.search_query.trim().is_empty() {
+                    self.search_results.clear();
+                }
+                self.search_command()
+            }
+            Message::SearchResults(query, result) => {
+                // Typing fires a search per keystroke; only the latest counts
+                if query != self.search_query {
+                    return Command::none();
+                }
+                match result {
+                    Ok(hits) => self.search_results = hits,
+                    Err(err) => self.error = Some(err),
+                }
+                Command::none()
+            }
             Message::PollHotkey => {
                 // Check for Fn key events first (macOS only)
                 #[cfg(target_os = "macos")]
@@ -294,7 +838,7 @@ impl Application for App {
                                     FnKeyState::Pressed => {
                                         log::info!("Fn pressed - starting recording");
                                         if !self.is_recording && !self.is_processing {
-                                            return self.start_recording_command();
+                                            return self.start_recording_command(None);
                                         }
                                     }
                                     FnKeyState::Released => {
@@ -305,13 +849,12 @@ impl Application for App {
                                     }
                                 }
                             }
-                            return Command::none();
                         }
                     }
                 }
 
-                // Fall back to global-hotkey for other keys
-                let events: Vec<HotKeyState> = {
+                // Registered global hotkeys: the main hotkey and per-mode bindings
+                let events: Vec<(u32, HotKeyState)> = {
                     let guard = HOTKEY_EVENTS.lock().unwrap();
                     let mut collected = Vec::new();
                     while let Ok(state) = guard.try_recv() {
@@ -321,12 +864,20 @@ impl Application for App {
                 };
 
                 // Process events - use the last event if multiple occurred
-                if let Some(last_state) = events.last() {
+                if let Some((id, last_state)) = events.last() {
+                    let Some(mode) = self
+                        .hotkey_bindings
+                        .iter()
+                        .find(|(hotkey, _)| hotkey.id() == *id)
+                        .map(|(_, mode)| mode.clone())
+                    else {
+                        return Command::none();
+                    };
                     match last_state {
                         HotKeyState::Pressed => {
                             // Start recording when key is pressed
                             if !self.is_recording && !self.is_processing {
-                                return self.start_recording_command();
+                                return self.start_recording_command(mode);
                             }
                         }
                         HotKeyState::Released => {
@@ -346,8 +897,22 @@ impl Application for App {
         // Settings section - positioned top right
         let settings_section = self.inline_settings_view();
 
+        let replacements_btn = button(
+            text(if self.show_replacements { "History" } else { "Replacements" }).size(13),
+        )
+        .padding([6, 12])
+        .style(subtle_button_style())
+        .on_press(Message::ToggleReplacements);
+
+        let stats_btn = button(text(if self.show_stats { "History" } else { "Stats" }).size(13))
+            .padding([6, 12])
+            .style(subtle_button_style())
+            .on_press(Message::ToggleStats);
+
         // Top bar with settings on right
         let top_bar = row![
+            replacements_btn,
+            stats_btn,
             row![].width(Length::Fill), // Spacer to push settings to the right
             settings_section,
         ]
@@ -357,8 +922,14 @@ impl Application for App {
         // Hero recording card
         let hero_card = self.record_view();
 
-        // Recent transcriptions list
-        let recent_list = self.recent_transcriptions_view();
+        // Recent transcriptions list, the replacement dictionary or stats
+        let recent_list = if self.show_replacements {
+            self.replacements_view()
+        } else if self.show_stats {
+            self.stats_view()
+        } else {
+            self.recent_transcriptions_view()
+        };
 
         // Footer with attribution
         let heart_icon = svg(svg::Handle::from_memory(HEART_SVG))
@@ -436,6 +1007,16 @@ impl App {
         .align_items(Alignment::Center)
         .width(Length::Fill);
 
+        if let Some(notice) = &self.notice {
+            main_column = main_column.push(
+                container(text(notice.clone()).size(14).style(WillowDark::WARNING))
+                    .padding(16)
+                    .style(modern_card_style())
+                    .width(Length::Fill)
+                    .max_width(600),
+            );
+        }
+
         if let Some(err) = &self.error {
             main_column = main_column.push(
                 container(
@@ -474,17 +1055,25 @@ impl App {
             .width(Length::Shrink);
 
             // Show "Recognize 'and press enter'" toggle only when auto_paste is enabled
-            let toggles_row = if draft.auto_paste {
+            let mut toggles_row = if draft.auto_paste {
                 row![
                     auto_paste_toggle,
                     toggler(
-                        Some("Recognize 'and press enter'".to_string()),
+                        Some("Recognize voice commands".to_string()),
                         draft.recognize_press_enter,
                         Message::ToggleRecognizePressEnter,
                     )
                     .text_size(14)
                     .spacing(8)
                     .width(Length::Shrink),
+                    toggler(
+                        Some("Review low confidence".to_string()),
+                        draft.hold_low_confidence_paste,
+                        Message::ToggleHoldLowConfidence,
+                    )
+                    .text_size(14)
+                    .spacing(8)
+                    .width(Length::Shrink),
                 ]
                 .spacing(24)
                 .align_items(Alignment::Center)
@@ -494,6 +1083,80 @@ impl App {
                 .align_items(Alignment::Center)
             };
 
+            // Live typing only applies to streamed AI output that gets pasted
+            if draft.auto_paste && draft.ai_processing_enabled && draft.ai_stream {
+                toggles_row = toggles_row.push(
+                    toggler(
+                        Some("Type as it streams".to_string()),
+                        draft.type_streamed_output,
+                        Message::ToggleTypeStreamed,
+                    )
+                    .text_size(14)
+                    .spacing(8)
+                    .width(Length::Shrink),
+                );
+            }
+
+            let mode_picker = row![
+                text("Mode").size(14),
+                pick_list(
+                    draft.modes.clone(),
+                    draft.active_mode().cloned(),
+                    Message::ModeSelected,
+                )
+                .text_size(14),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center);
+
+            toggles_row = toggles_row.push(
+                toggler(
+                    Some("Spoken punctuation".to_string()),
+                    draft.spoken_formatting,
+                    Message::ToggleSpokenFormatting,
+                )
+                .text_size(14)
+                .spacing(8)
+                .width(Length::Shrink),
+            );
+            toggles_row = toggles_row.push(
+                toggler(
+                    Some("Numbers as digits".to_string()),
+                    draft.inverse_text_normalization,
+                    Message::ToggleNumberFormatting,
+                )
+                .text_size(14)
+                .spacing(8)
+                .width(Length::Shrink),
+            );
+            toggles_row = toggles_row.push(
+                toggler(
+                    Some("Remove filler words".to_string()),
+                    draft.remove_fillers,
+                    Message::ToggleRemoveFillers,
+                )
+                .text_size(14)
+                .spacing(8)
+                .width(Length::Shrink),
+            );
+            toggles_row = toggles_row.push(mode_picker);
+
+            if draft.ai_processing_enabled {
+                toggles_row = toggles_row.push(
+                    row![
+                        text("AI").size(14),
+                        pick_list(
+                            draft.providers.clone(),
+                            draft.active_provider_config().cloned(),
+                            Message::ProviderSelected,
+                        )
+                        .text_size(14),
+                    ]
+                    .spacing(8)
+                    .align_items(Alignment::Center),
+                );
+            }
+
             // Settings without card styling - aligns with layout margin
             toggles_row.into()
         } else {
@@ -504,7 +1167,8 @@ impl App {
     }
 
     fn recent_transcriptions_view(&self) -> Element<'_, Message> {
-        if self.history.is_empty() {
+        // Keep the header while filtering so the filter can be turned off
+        if self.history.is_empty() && self.history_filter == HistoryFilter::default() {
             return container(
                 text("No recent transcriptions")
                     .size(14)
@@ -516,56 +1180,251 @@ impl App {
             .into();
         }
 
-        let header = text("Recent Transcriptions")
+        let mut header = column![text("Recent Transcriptions")
             .size(18)
-            .style(WillowDark::TEXT_PRIMARY);
+            .style(WillowDark::TEXT_PRIMARY)]
+        .spacing(4);
+        if let Some(usage) = self.ai_usage_line() {
+            header = header.push(text(usage).size(12).style(WillowDark::TEXT_MUTED));
+        }
+        header = header.push(
+            row![
+                text_input("Search history: \"exact phrase\", prefix*, OR, -exclude", &self.search_query)
+                    .on_input(Message::SearchChanged)
+                    .size(14)
+                    .padding(8),
+                toggler(
+                    Some("AI processed only".to_string()),
+                    self.history_filter.ai_processed == Some(true),
+                    Message::ToggleAiProcessedFilter,
+                )
+                .text_size(14)
+                .spacing(8)
+                .width(Length::Shrink),
+                toggler(
+                    Some("Pinned".to_string()),
+                    self.history_filter.pinned_only,
+                    Message::TogglePinnedFilter,
+                )
+                .text_size(14)
+                .spacing(8)
+                .width(Length::Shrink),
+            ]
+            .spacing(16)
+            .align_items(Alignment::Center),
+        );
+        if !self.tags.is_empty() {
+            let mut tag_row = row![text("Tags:").size(12).style(WillowDark::TEXT_MUTED)]
+                .spacing(6)
+                .align_items(Alignment::Center);
+            for tag in &self.tags {
+                let selected = self
+                    .history_filter
+                    .tag
+                    .as_ref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(tag));
+                let mut chip = button(text(format!("#{}", tag)).size(12))
+                    .padding([4, 8])
+                    .on_press(Message::FilterByTag((!selected).then(|| tag.clone())));
+                chip = if selected {
+                    chip.style(Button::Primary)
+                } else {
+                    chip.style(subtle_button_style())
+                };
+                tag_row = tag_row.push(chip);
+            }
+            header = header.push(tag_row);
+        }
+        let mut export_row = row![text("Export:").size(12).style(WillowDark::TEXT_MUTED)]
+            .spacing(6)
+            .align_items(Alignment::Center);
+        for format in ExportFormat::all() {
+            export_row = export_row.push(
+                button(text(format.to_string()).size(12))
+                    .padding([4, 8])
+                    .style(subtle_button_style())
+                    .on_press(Message::HistoryExport(format)),
+            );
+        }
+        header = header.push(export_row);
+
+        let list = if self.search_query.trim().is_empty() {
+            let mut cards = self
+                .history
+                .iter()
+                .map(|item| self.history_card(item, None))
+                .collect::<Vec<Element<_>>>();
+            if self.history.is_empty() {
+                cards.push(
+                    text("No matching transcriptions")
+                        .size(14)
+                        .style(WillowDark::TEXT_MUTED)
+                        .into(),
+                );
+            }
+            if self.history_next.is_some() {
+                cards.push(
+                    container(
+                        button(text("Show older").size(13))
+                            .padding([6, 12])
+                            .style(subtle_button_style())
+                            .on_press(Message::HistoryMore),
+                    )
+                    .center_x()
+                    .width(Length::Fill)
+                    .into(),
+                );
+            }
+            cards
+        } else if self.search_results.is_empty() {
+            vec![text("No matching transcriptions")
+                .size(14)
+                .style(WillowDark::TEXT_MUTED)
+                .into()]
+        } else {
+            self.search_results
+                .iter()
+                .map(|hit| self.history_card(&hit.transcription, Some(hit)))
+                .collect::<Vec<Element<_>>>()
+        };
 
-        let list = self
-            .history
-            .iter()
-            .take(10)
-            .map(|item| {
-                let transcription_text = item.processed_text.as_ref().unwrap_or(&item.text).clone();
-                let formatted_time = format_timestamp(&item.created_at);
+        // Add right padding to prevent scrollbar from overlapping cards
+        column![
+            header,
+            container(
+                scrollable(
+                    container(column(list).spacing(16))
+                        .padding([0, 12, 0, 0])  // 12px right padding for scrollbar space
+                        .width(Length::Fill)
+                )
+                .height(Length::Fill)
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+        ]
+        .spacing(16)
+        .width(Length::Fill)
+        .into()
+    }
 
-                let copy_btn = button(text("Copy").size(13))
-                    .padding([6, 12])
-                    .style(subtle_button_style())
-                    .on_press(Message::HistoryCopied(transcription_text.clone()));
+    fn replacements_view(&self) -> Element<'_, Message> {
+        let add_form = row![
+            text_input("Say...", &self.replacement_pattern)
+                .on_input(Message::ReplacementPatternChanged)
+                .on_submit(Message::ReplacementAdd)
+                .size(14)
+                .padding(8),
+            text_input("Insert...", &self.replacement_text)
+                .on_input(Message::ReplacementTextChanged)
+                .on_submit(Message::ReplacementAdd)
+                .size(14)
+                .padding(8),
+            button(text("Add").size(13))
+                .padding([6, 12])
+                .style(subtle_button_style())
+                .on_press(Message::ReplacementAdd),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let mut header = column![
+            text("Replacements").size(18).style(WillowDark::TEXT_PRIMARY),
+            text("Fix words Convey keeps mishearing or expand snippets. Use {date}, {time} or {clipboard} in the inserted text.")
+                .size(12)
+                .style(WillowDark::TEXT_MUTED),
+            add_form,
+        ]
+        .spacing(8);
+
+        let vocabulary = self
+            .settings
+            .as_ref()
+            .map(|s| s.vocabulary.as_slice())
+            .unwrap_or_default();
+        if !vocabulary.is_empty() {
+            header = header.push(
+                text(format!("Vocabulary: {}", vocabulary.join(", ")))
+                    .size(12)
+                    .style(WillowDark::TEXT_MUTED),
+            );
+        }
 
-                let delete_btn = button(text("Delete").size(13))
-                    .padding([6, 12])
-                    .style(subtle_button_style())
-                    .on_press(Message::HistoryDelete(item.id));
+        // Corrections already in the vocabulary don't need suggesting again
+        let suggestions: Vec<&Suggestion> = self
+            .suggestions
+            .iter()
+            .filter(|s| !vocabulary.contains(&s.corrected))
+            .collect();
+        if !suggestions.is_empty() {
+            header = header.push(
+                text("Suggested from your corrections")
+                    .size(14)
+                    .style(WillowDark::TEXT_PRIMARY),
+            );
+            for suggestion in suggestions {
+                header = header.push(
+                    row![
+                        text(format!(
+                            "{} → {} ({}×)",
+                            suggestion.heard, suggestion.corrected, suggestion.count
+                        ))
+                        .size(14)
+                        .style(WillowDark::TEXT_SECONDARY)
+                        .width(Length::Fill),
+                        button(text("Add rule").size(13))
+                            .padding([6, 12])
+                            .style(subtle_button_style())
+                            .on_press(Message::SuggestionAddRule(suggestion.clone())),
+                        button(text("Add to vocabulary").size(13))
+                            .padding([6, 12])
+                            .style(subtle_button_style())
+                            .on_press(Message::SuggestionAddVocabulary(suggestion.corrected.clone())),
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                );
+            }
+        }
 
+        let rules = self
+            .replacements
+            .iter()
+            .map(|rule| {
                 container(
-                    column![
-                        text(formatted_time)
-                            .size(12)
-                            .style(WillowDark::TEXT_MUTED),
-                        text(&transcription_text)
-                            .size(15)
-                            .style(WillowDark::TEXT_SECONDARY),
-                        row![copy_btn, delete_btn]
-                            .spacing(10)
-                            .align_items(Alignment::Center),
+                    row![
+                        text(&rule.pattern)
+                            .size(14)
+                            .style(WillowDark::TEXT_PRIMARY)
+                            .width(Length::FillPortion(1)),
+                        text(&rule.replacement)
+                            .size(14)
+                            .style(WillowDark::TEXT_SECONDARY)
+                            .width(Length::FillPortion(2)),
+                        toggler(None, rule.enabled, move |enabled| {
+                            Message::ReplacementToggle(rule.id, enabled)
+                        })
+                        .width(Length::Shrink),
+                        button(text("Delete").size(13))
+                            .padding([6, 12])
+                            .style(subtle_button_style())
+                            .on_press(Message::ReplacementDelete(rule.id)),
                     ]
-                    .spacing(8),
+                    .spacing(10)
+                    .align_items(Alignment::Center),
                 )
-                .padding(20)
+                .padding(12)
                 .style(modern_card_style())
                 .width(Length::Fill)
                 .into()
             })
             .collect::<Vec<Element<_>>>();
 
-        // Add right padding to prevent scrollbar from overlapping cards
         column![
             header,
             container(
                 scrollable(
-                    container(column(list).spacing(16))
-                        .padding([0, 12, 0, 0])  // 12px right padding for scrollbar space
+                    container(column(rules).spacing(8))
+                        .padding([0, 12, 0, 0])
                         .width(Length::Fill)
                 )
                 .height(Length::Fill)
@@ -578,6 +1437,329 @@ impl App {
         .into()
     }
 
+    fn stats_view(&self) -> Element<'_, Message> {
+        let Some(stats) = &self.pipeline_stats else {
+            return container(text("Loading stats...").size(14).style(WillowDark::TEXT_MUTED))
+                .padding(20)
+                .center_x()
+                .width(Length::Fill)
+                .into();
+        };
+
+        let mut content = column![
+            text("Pipeline timings").size(18).style(WillowDark::TEXT_PRIMARY),
+            text(format!(
+                "Last {} days · {} dictation{} with timings · median / 95th percentile",
+                STATS_DAYS,
+                stats.entries,
+                if stats.entries == 1 { "" } else { "s" }
+            ))
+            .size(12)
+            .style(WillowDark::TEXT_MUTED),
+        ]
+        .spacing(8);
+
+        if stats.entries == 0 {
+            return content
+                .push(text("No timed dictations yet").size(14).style(WillowDark::TEXT_MUTED))
+                .into();
+        }
+
+        let stages = [
+            ("Key-up to text", stats.total),
+            ("Whisper", stats.whisper),
+            ("AI", stats.ai),
+            ("Paste", stats.paste),
+        ];
+        for (label, stage) in stages {
+            if let Some(stage) = stage {
+                content = content.push(stats_row(label.to_string(), stage, None));
+            }
+        }
+
+        if !stats.by_whisper_model.is_empty() {
+            content = content.push(stats_heading("By whisper model"));
+            for model in &stats.by_whisper_model {
+                let speed = model.speed.map(|speed| format!("{:.1}× real time", speed));
+                content = content.push(stats_row(model.model.clone(), model.whisper, speed));
+            }
+        }
+
+        if !stats.by_ai_model.is_empty() {
+            content = content.push(stats_heading("By AI model"));
+            for model in &stats.by_ai_model {
+                content = content.push(stats_row(model.model.clone(), model.ai, None));
+            }
+        }
+
+        if !stats.daily_total.is_empty() {
+            content = content.push(stats_heading("Key-up to text by day"));
+            for (day, stage) in stats.daily_total.iter().rev() {
+                content = content.push(stats_row(day.format("%b %d").to_string(), *stage, None));
+            }
+        }
+
+        container(scrollable(
+            container(content).padding([0, 12, 0, 0]).width(Length::Fill),
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// A history entry. Search results show the matching snippet in place of
+    /// the full text.
+    fn history_card<'a>(
+        &'a self,
+        item: &'a Transcription,
+        hit: Option<&'a SearchHit>,
+    ) -> Element<'a, Message> {
+        let low_confidence_threshold = self
+            .settings
+            .as_ref()
+            .map(|s| s.low_confidence_threshold)
+            .unwrap_or(0.5);
+
+        let transcription_text = item.current_text();
+        let mut formatted_time = format_timestamp(&item.created_at);
+        if let Some(mode_id) = &item.mode {
+            let mode_name = self
+                .settings
+                .as_ref()
+                .and_then(|s| s.mode(mode_id))
+                .map(|m| m.name.as_str())
+                .unwrap_or(mode_id);
+            formatted_time = format!("{} · {}", formatted_time, mode_name);
+        }
+        if let Some(app) = &item.app {
+            formatted_time = format!("{} · {}", formatted_time, app);
+        }
+
+        let copy_btn = button(text("Copy").size(13))
+            .padding([6, 12])
+            .style(subtle_button_style())
+            .on_press(Message::HistoryCopied(transcription_text.to_string()));
+
+        let delete_btn = button(text("Delete").size(13))
+            .padding([6, 12])
+            .style(subtle_button_style())
+            .on_press(Message::HistoryDelete(item.id));
+
+        let show_diff = self.diff_open.contains(&item.id);
+
+        // Word confidences describe the raw whisper output, so only
+        // highlight when that is what we're showing.
+        let body: Element<_> = if let Some((_, content)) =
+            self.editing.as_ref().filter(|(id, _)| *id == item.id)
+        {
+            let mut save_btn = button(text("Save").size(13))
+                .padding([6, 12])
+                .style(subtle_button_style());
+            if !content.text().trim().is_empty() {
+                save_btn = save_btn.on_press(Message::HistoryEditSave);
+            }
+            column![
+                text_editor(content)
+                    .on_action(Message::HistoryEditAction)
+                    .padding(8),
+                row![
+                    save_btn,
+                    button(text("Cancel").size(13))
+                        .padding([6, 12])
+                        .style(subtle_button_style())
+                        .on_press(Message::HistoryEditCancel),
+                ]
+                .spacing(10),
+            ]
+            .spacing(8)
+            .into()
+        } else {
+            match (&item.processed_text, hit) {
+                (Some(processed), _) if show_diff => diff_text(&item.text, processed),
+                (_, Some(hit)) => search_snippet_text(hit.snippet_words()),
+                (None, None) if item.edited_text.is_none() && !item.segments.is_empty() => {
+                    confidence_highlighted_text(&item.segments, low_confidence_threshold)
+                }
+                _ => text(transcription_text)
+                    .size(15)
+                    .style(WillowDark::TEXT_SECONDARY)
+                    .into(),
+            }
+        };
+
+        let mut actions = row![copy_btn].spacing(10).align_items(Alignment::Center);
+
+        if item.processed_text.is_some() {
+            actions = actions
+                .push(
+                    button(text("Copy before AI").size(13))
+                        .padding([6, 12])
+                        .style(subtle_button_style())
+                        .on_press(Message::HistoryCopied(item.text.clone())),
+                )
+                .push(
+                    button(text(if show_diff { "Hide diff" } else { "Diff" }).size(13))
+                        .padding([6, 12])
+                        .style(subtle_button_style())
+                        .on_press(Message::HistoryToggleDiff(item.id)),
+                );
+        }
+
+        if let Some(original) = &item.pre_cleanup_text {
+            actions = actions.push(
+                button(text("Copy whisper text").size(13))
+                    .padding([6, 12])
+                    .style(subtle_button_style())
+                    .on_press(Message::HistoryCopied(original.clone())),
+            );
+        }
+
+        let ai_incomplete = matches!(item.ai_status, Some(AiStatus::Pending | AiStatus::Failed));
+        if self.reprocessing == Some(item.id) {
+            actions = actions.push(
+                text("Processing...")
+                    .size(13)
+                    .style(WillowDark::TEXT_SECONDARY),
+            );
+        } else if ai_incomplete {
+            // Retried with the mode it was dictated in
+            let mut retry_btn = button(text("Retry AI").size(13))
+                .padding([6, 12])
+                .style(subtle_button_style());
+            if self.reprocessing.is_none() {
+                retry_btn = retry_btn.on_press(Message::HistoryReprocess(item.id, item.mode.clone()));
+            }
+            actions = actions.push(retry_btn);
+        } else if let Some(settings) = &self.settings {
+            let id = item.id;
+            actions = actions.push(
+                pick_list(settings.modes.clone(), None::<ProcessingMode>, move |mode| {
+                    Message::HistoryReprocess(id, Some(mode.id))
+                })
+                .placeholder("Re-run as...")
+                .text_size(13),
+            );
+        }
+
+        let pin_label = if item.pinned { "Unpin" } else { "Pin" };
+        actions = actions.push(
+            button(text(pin_label).size(13))
+                .padding([6, 12])
+                .style(subtle_button_style())
+                .on_press(Message::HistoryTogglePin(item.id, !item.pinned)),
+        );
+        actions = actions.push(
+            button(text("Edit").size(13))
+                .padding([6, 12])
+                .style(subtle_button_style())
+                .on_press(Message::HistoryEditStart(item.id, transcription_text.to_string())),
+        );
+        let revisions_shown = self
+            .revisions_open
+            .as_ref()
+            .is_some_and(|(id, _)| *id == item.id);
+        actions = actions.push(
+            button(text(if revisions_shown { "Hide versions" } else { "Versions" }).size(13))
+                .padding([6, 12])
+                .style(subtle_button_style())
+                .on_press(Message::HistoryToggleRevisions(item.id)),
+        );
+        actions = actions.push(
+            button(text("Tag").size(13))
+                .padding([6, 12])
+                .style(subtle_button_style())
+                .on_press(Message::HistoryTagStart(item.id)),
+        );
+
+        actions = actions.push(delete_btn);
+
+        if item.edited_text.is_some() {
+            formatted_time = format!("{} · Edited", formatted_time);
+        }
+        if item.pinned {
+            formatted_time = format!("{} · Pinned", formatted_time);
+        }
+        let mut card = column![
+            text(formatted_time)
+                .size(12)
+                .style(WillowDark::TEXT_MUTED),
+            body,
+        ]
+        .spacing(8);
+
+        if !item.tags.is_empty() {
+            let mut tag_row = row![].spacing(6).align_items(Alignment::Center);
+            for tag in &item.tags {
+                tag_row = tag_row.push(
+                    button(text(format!("#{}", tag)).size(12))
+                        .padding([4, 8])
+                        .style(subtle_button_style())
+                        .on_press(Message::FilterByTag(Some(tag.clone()))),
+                );
+                tag_row = tag_row.push(
+                    button(text("×").size(12))
+                        .padding([4, 6])
+                        .style(subtle_button_style())
+                        .on_press(Message::HistoryTagRemove(item.id, tag.clone())),
+                );
+            }
+            card = card.push(tag_row);
+        }
+        if let Some((_, draft)) = self.tagging.as_ref().filter(|(id, _)| *id == item.id) {
+            card = card.push(
+                text_input("New tag, then Enter", draft)
+                    .on_input(Message::HistoryTagChanged)
+                    .on_submit(Message::HistoryTagSubmit)
+                    .size(13)
+                    .padding(6),
+            );
+        }
+
+        if let Some((_, revisions)) = self.revisions_open.as_ref().filter(|(id, _)| *id == item.id) {
+            card = card.push(revisions_list(item, revisions));
+        }
+
+        match item.ai_status {
+            Some(AiStatus::Failed) => {
+                card = card.push(
+                    text(format!(
+                        "AI processing failed: {}",
+                        item.ai_error.as_deref().unwrap_or("unknown error")
+                    ))
+                    .size(12)
+                    .style(WillowDark::ERROR),
+                );
+            }
+            Some(AiStatus::Pending) => {
+                card = card.push(
+                    text("AI processing pending")
+                        .size(12)
+                        .style(WillowDark::WARNING),
+                );
+            }
+            _ => {}
+        }
+
+        if !item.applied_replacements.is_empty() {
+            let patterns: Vec<&str> = item
+                .applied_replacements
+                .iter()
+                .map(|r| r.pattern.as_str())
+                .collect();
+            card = card.push(
+                text(format!("Replaced: {}", patterns.join(", ")))
+                    .size(12)
+                    .style(WillowDark::TEXT_MUTED),
+            );
+        }
+
+        container(card.push(actions))
+        .padding(20)
+        .style(modern_card_style())
+        .width(Length::Fill)
+        .into()
+    }
+
     fn sanitize_settings(_settings: &mut AppSettings) {
         // No sanitization needed anymore
     }
@@ -606,15 +1788,17 @@ impl App {
         }
     }
 
-    fn start_recording_command(&mut self) -> Command<Message> {
+    fn start_recording_command(&mut self, mode: Option<String>) -> Command<Message> {
         if self.is_processing || self.is_recording {
             return Command::none();
         }
+        self.recording_mode = mode;
         if self.services.recorder.is_recording() {
             self.is_recording = true;
             return Command::none();
         }
         self.error = None;
+        self.notice = None;
         self.notch_overlay.show_recording();
         let services = self.services.clone();
         Command::perform(
@@ -634,19 +1818,188 @@ impl App {
         self.is_processing = true;
         self.notch_overlay.show_processing();
         let services = self.services.clone();
+        let mode = self.recording_mode.take();
         Command::perform(
-            async move { workflow::stop_recording_and_transcribe(services).await },
+            async move { workflow::stop_recording_and_transcribe(services, mode).await },
             Message::RecordingStopped,
         )
     }
 
+    fn load_replacements(&self) -> Command<Message> {
+        let services = self.services.clone();
+        Command::perform(
+            async move { services.history.replacements().map_err(|e| e.to_string()) },
+            Message::ReplacementsLoaded,
+        )
+    }
+
+    fn load_suggestions(&self) -> Command<Message> {
+        let services = self.services.clone();
+        Command::perform(
+            async move {
+                services
+                    .history
+                    .correction_suggestions()
+                    .map_err(|e| e.to_string())
+            },
+            Message::SuggestionsLoaded,
+        )
+    }
+
+    /// Reloads history from the newest entry, keeping as many entries as are
+    /// shown so a change doesn't collapse the pages the user opened.
+    fn reload_history(&self) -> Command<Message> {
+        let services = self.services.clone();
+        let filter = self.history_filter.clone();
+        let limit = self.history_reload_limit();
+        Command::perform(
+            async move {
+                services
+                    .history
+                    .query(&filter, None, limit)
+                    .map_err(|e| e.to_string())
+            },
+            Message::HistoryLoaded,
+        )
+    }
+
+    fn save_revision(&mut self, id: i64, text: String) -> Command<Message> {
+        self.error = None;
+        let services = self.services.clone();
+        Command::perform(
+            workflow::save_revision(services, id, text),
+            Message::HistoryEdited,
+        )
+    }
+
+    /// Reloads the open revisions list, if any.
+    fn load_revisions(&self) -> Command<Message> {
+        let Some((id, _)) = self.revisions_open else {
+            return Command::none();
+        };
+        let services = self.services.clone();
+        Command::perform(
+            async move { services.history.revisions(id).map_err(|e| e.to_string()) },
+            move |result| Message::RevisionsLoaded(id, result),
+        )
+    }
+
+    fn load_tags(&self) -> Command<Message> {
+        let services = self.services.clone();
+        Command::perform(
+            async move { services.history.tags().map_err(|e| e.to_string()) },
+            Message::TagsLoaded,
+        )
+    }
+
+    fn history_reload_limit(&self) -> usize {
+        self.history.len().max(HISTORY_PAGE_SIZE)
+    }
+
+    fn set_history(&mut self, page: HistoryPage) {
+        self.history = page.entries;
+        self.history_next = page.next;
+    }
+
+    /// Runs the history search for the current query, if there is one.
+    fn search_command(&self) -> Command<Message> {
+        let query = self.search_query.trim().to_string();
+        if query.is_empty() {
+            return Command::none();
+        }
+        let services = self.services.clone();
+        let current = self.search_query.clone();
+        Command::perform(
+            async move {
+                services
+                    .history
+                    .search(&query, SEARCH_RESULT_LIMIT)
+                    .map_err(|e| e.to_string())
+            },
+            move |result| Message::SearchResults(current, result),
+        )
+    }
+
+    fn load_ai_usage(&self) -> Command<Message> {
+        let services = self.services.clone();
+        Command::perform(
+            async move {
+                let today = services.history.ai_usage_today().map_err(|e| e.to_string())?;
+                let month = services
+                    .history
+                    .ai_usage_this_month()
+                    .map_err(|e| e.to_string())?;
+                Ok((today, month))
+            },
+            Message::AiUsageLoaded,
+        )
+    }
+
+    /// Summary of today's and this month's AI spend, or `None` when nothing
+    /// has been used this month.
+    fn ai_usage_line(&self) -> Option<String> {
+        let (today, month) = self.ai_usage?;
+        if month.prompt_tokens + month.completion_tokens == 0 {
+            return None;
+        }
+
+        let mut line = format!(
+            "AI usage: today ${:.2} ({} tokens) · this month ${:.2} ({} tokens)",
+            today.cost_usd,
+            today.prompt_tokens + today.completion_tokens,
+            month.cost_usd,
+            month.prompt_tokens + month.completion_tokens,
+        );
+        if let Some(budget) = self.settings.as_ref().and_then(|s| s.monthly_ai_budget_usd) {
+            line.push_str(&format!(" of ${:.2} budget", budget));
+            if month.cost_usd >= budget {
+                line.push_str(" (reached, AI paused)");
+            }
+        }
+        Some(line)
+    }
+
+    /// Re-registers global hotkeys from the current settings: the main hotkey
+    /// (unless it is Fn/Globe, which has its own monitor) and each mode's hotkey.
+    fn register_hotkeys(&mut self) {
+        let Some(settings) = &self.settings else {
+            return;
+        };
+
+        let manager = HOTKEY_MANAGER.lock().unwrap();
+        for (hotkey, _) in self.hotkey_bindings.drain(..) {
+            let _ = manager.unregister(hotkey);
+        }
+
+        let main = settings.hotkey.to_lowercase();
+        let mut wanted: Vec<(&str, Option<String>)> = Vec::new();
+        if main != "fn" && main != "globe" {
+            wanted.push((settings.hotkey.as_str(), None));
+        }
+        for mode in &settings.modes {
+            if let Some(hotkey) = mode.hotkey.as_deref().filter(|h| !h.trim().is_empty()) {
+                wanted.push((hotkey, Some(mode.id.clone())));
+            }
+        }
+
+        for (spec, mode) in wanted {
+            match spec.parse::<HotKey>() {
+                Ok(hotkey) => match manager.register(hotkey) {
+                    Ok(()) => self.hotkey_bindings.push((hotkey, mode)),
+                    Err(e) => log::warn!("Failed to register hotkey '{}': {}", spec, e),
+                },
+                Err(e) => log::warn!("Invalid hotkey '{}': {}", spec, e),
+            }
+        }
+    }
+
     fn handle_hotkey_trigger(&mut self) -> Command<Message> {
         if self.is_processing {
             Command::none()
         } else if self.is_recording {
             self.stop_recording_command()
         } else {
-            self.start_recording_command()
+            self.start_recording_command(None)
         }
     }
 
@@ -654,7 +2007,146 @@ impl App {
 
 // Removed tab_button - no longer using tabs
 
+// Period covered by the stats view.
+const STATS_DAYS: i64 = 30;
+
+// Entries loaded per history page.
+const HISTORY_PAGE_SIZE: usize = 20;
+
+// Best matches shown for a history search.
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+// Characters per line for word-by-word rendering. The UI font is monospaced,
+// so a fixed count approximates the card width.
+const HISTORY_LINE_CHARS: usize = 80;
+
+// iced 0.12 has no rich text, so lay coloured words out as rows of text
+// widgets, wrapping at HISTORY_LINE_CHARS.
+fn wrapped_words(words: impl Iterator<Item = (String, Color)>) -> Element<'static, Message> {
+    let mut lines: Vec<Element<'static, Message>> = Vec::new();
+    let mut line: Vec<Element<'static, Message>> = Vec::new();
+    let mut line_len = 0;
+
+    for (word, color) in words {
+        let word_len = word.chars().count() + 1;
+        if line_len + word_len > HISTORY_LINE_CHARS && !line.is_empty() {
+            lines.push(row(std::mem::take(&mut line)).into());
+            line_len = 0;
+        }
+        line.push(text(format!("{} ", word)).size(15).style(color).into());
+        line_len += word_len;
+    }
+    if !line.is_empty() {
+        lines.push(row(line).into());
+    }
+
+    column(lines).spacing(2).into()
+}
+
+// Colour the words whisper was unsure about.
+fn confidence_highlighted_text(segments: &[Segment], threshold: f32) -> Element<'static, Message> {
+    wrapped_words(segments.iter().flat_map(|s| s.words.iter()).map(|word| {
+        let color = if word.confidence < threshold {
+            WillowDark::WARNING
+        } else {
+            WillowDark::TEXT_SECONDARY
+        };
+        (word.text.clone(), color)
+    }))
+}
+
+fn stats_heading(label: &str) -> Element<'static, Message> {
+    text(label.to_string())
+        .size(14)
+        .style(WillowDark::TEXT_PRIMARY)
+        .into()
+}
+
+fn stats_row(label: String, stage: StageStats, note: Option<String>) -> Element<'static, Message> {
+    row![
+        text(label)
+            .size(14)
+            .style(WillowDark::TEXT_SECONDARY)
+            .width(Length::FillPortion(2)),
+        text(format!(
+            "{} / {}",
+            format_duration_ms(stage.median_ms),
+            format_duration_ms(stage.p95_ms)
+        ))
+        .size(14)
+        .style(WillowDark::TEXT_PRIMARY)
+        .width(Length::FillPortion(2)),
+        text(format!("{}×", stage.count))
+            .size(12)
+            .style(WillowDark::TEXT_MUTED)
+            .width(Length::FillPortion(1)),
+        text(note.unwrap_or_default())
+            .size(12)
+            .style(WillowDark::TEXT_MUTED)
+            .width(Length::FillPortion(2)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+fn format_duration_ms(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    }
+}
+
+// Search terms stand out from the rest of the snippet.
+fn search_snippet_text(words: Vec<(String, bool)>) -> Element<'static, Message> {
+    wrapped_words(words.into_iter().map(|(word, matched)| {
+        let color = if matched {
+            WillowDark::ACCENT
+        } else {
+            WillowDark::TEXT_SECONDARY
+        };
+        (word, color)
+    }))
+}
+
+// Raw vs. processed: removed words in red, added words in green.
+fn diff_text(raw: &str, processed: &str) -> Element<'static, Message> {
+    wrapped_words(word_diff(raw, processed).into_iter().map(|op| match op {
+        DiffOp::Equal(word) => (word, WillowDark::TEXT_SECONDARY),
+        DiffOp::Removed(word) => (format!("-{}", word), WillowDark::ERROR),
+        DiffOp::Added(word) => (format!("+{}", word), WillowDark::SUCCESS),
+    }))
+}
+
 // Helper function to format timestamp in a user-friendly way
+/// An entry's saved versions, newest first, ending with the text Convey
+/// produced. Every version but the current one can be restored.
+fn revisions_list<'a>(item: &Transcription, revisions: &'a [Revision]) -> Element<'a, Message> {
+    let current = item.current_text();
+    let version = |label: String, body: &str| {
+        let mut header = row![text(label).size(12).style(WillowDark::TEXT_MUTED)]
+            .spacing(10)
+            .align_items(Alignment::Center);
+        if body != current {
+            header = header.push(
+                button(text("Restore").size(12))
+                    .padding([4, 8])
+                    .style(subtle_button_style())
+                    .on_press(Message::HistoryRestore(item.id, body.to_string())),
+            );
+        }
+        column![header, text(body.to_string()).size(13).style(WillowDark::TEXT_SECONDARY)].spacing(4)
+    };
+
+    let mut list = column![].spacing(10);
+    for revision in revisions {
+        list = list.push(version(format_timestamp(&revision.created_at), &revision.text));
+    }
+    list = list.push(version("Original".to_string(), item.produced_text()));
+    list.into()
+}
+
 fn format_timestamp(timestamp_str: &str) -> String {
     // Parse the RFC3339 timestamp from the database
     if let Ok(dt) = DateTime::parse_from_rfc3339(timestamp_str) {
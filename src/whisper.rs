@@ -1,15 +1,91 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::process::Command;
-use which::which;
+
+use crate::binaries::resolve_binary;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WhisperConfig {
     pub model: String,
     pub language: Option<String>,
     pub cli_path: Option<String>,
+    /// Names and terms to bias recognition towards, passed as the initial prompt.
+    pub vocabulary: Vec<String>,
+}
+
+/// A single word with its timing and the mean probability of its tokens.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub confidence: f32,
+}
+
+/// A whisper segment (roughly one sentence) with its words.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Segment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    #[serde(default)]
+    pub words: Vec<WordTiming>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<Segment>,
+}
+
+impl Transcript {
+    /// Mean word confidence, or `None` when whisper gave us no token data.
+    pub fn average_confidence(&self) -> Option<f32> {
+        average_confidence(&self.segments)
+    }
+}
+
+pub fn average_confidence(segments: &[Segment]) -> Option<f32> {
+    let (sum, count) = segments
+        .iter()
+        .flat_map(|s| s.words.iter())
+        .fold((0.0f32, 0usize), |(sum, count), w| (sum + w.confidence, count + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f32)
+    }
+}
+
+// Shape of whisper-cli's `-ojf` (full JSON) output; only the fields we use.
+#[derive(Debug, Deserialize)]
+struct FullJson {
+    #[serde(default)]
+    transcription: Vec<JsonSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonSegment {
+    offsets: JsonOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<JsonToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonToken {
+    text: String,
+    offsets: JsonOffsets,
+    #[serde(default)]
+    p: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonOffsets {
+    from: i64,
+    to: i64,
 }
 
 pub struct WhisperClient {
@@ -21,14 +97,20 @@ impl WhisperClient {
         Self { config }
     }
 
-    pub async fn transcribe(&self, audio_path: &Path) -> Result<String> {
+    pub async fn transcribe(&self, audio_path: &Path) -> Result<Transcript> {
         // Run transcription using whisper-cli
         let audio_path = audio_path.to_path_buf();
         let language = self.config.language.clone();
         let cli_override = self.config.cli_path.clone();
+        let prompt = (!self.config.vocabulary.is_empty()).then(|| self.config.vocabulary.join(", "));
 
         tokio::task::spawn_blocking(move || {
-            Self::transcribe_with_cli(&audio_path, language.as_deref(), cli_override.as_deref())
+            Self::transcribe_with_cli(
+                &audio_path,
+                language.as_deref(),
+                cli_override.as_deref(),
+                prompt.as_deref(),
+            )
         })
         .await
         .context("Failed to spawn blocking task")?
@@ -38,7 +120,8 @@ impl WhisperClient {
         audio_path: &Path,
         language: Option<&str>,
         cli_override: Option<&str>,
-    ) -> Result<String> {
+        prompt: Option<&str>,
+    ) -> Result<Transcript> {
         log::info!("transcribe_with_cli called for: {:?}", audio_path);
 
         let mut candidates = Vec::new();
@@ -82,7 +165,7 @@ impl WhisperClient {
 
         log::info!("Using model path: {:?}", model_path);
 
-        let cli_binary = resolve_whisper_cli(cli_override)?;
+        let cli_binary = resolve_binary("whisper-cli", cli_override)?;
         log::info!("Resolved whisper-cli path: {:?}", cli_binary);
 
         // Build whisper-cli command
@@ -96,8 +179,14 @@ impl WhisperClient {
             log::info!("Language set to: {}", lang);
         }
 
-        // Output to text format
-        cmd.arg("-otxt");
+        // Whisper conditions on the prompt as if it were preceding speech,
+        // which makes it far more likely to spell these terms as given
+        if let Some(prompt) = prompt {
+            cmd.arg("--prompt").arg(prompt);
+        }
+
+        // Output plain text plus full JSON (token probabilities and offsets)
+        cmd.arg("-otxt").arg("-ojf");
 
         log::info!("Executing whisper-cli command: {:?}", cmd);
 
@@ -115,6 +204,8 @@ impl WhisperClient {
             ));
         }
 
+        let segments = read_full_json(&audio_path.with_extension("wav.json"));
+
         // Read the output file
         let output_txt = audio_path.with_extension("wav.txt");
         log::info!("Looking for output file: {:?}", output_txt);
@@ -129,7 +220,10 @@ impl WhisperClient {
             // Clean up the output file
             let _ = std::fs::remove_file(output_txt);
 
-            Ok(transcription.trim().to_string())
+            Ok(Transcript {
+                text: transcription.trim().to_string(),
+                segments,
+            })
         } else {
             log::warn!("Output file not found, trying stdout");
             // If no file, try to parse stdout
@@ -137,59 +231,199 @@ impl WhisperClient {
             let stderr = String::from_utf8_lossy(&output.stderr);
             log::info!("stdout: {}", stdout);
             log::info!("stderr: {}", stderr);
-            Ok(stdout.trim().to_string())
+            Ok(Transcript {
+                text: stdout.trim().to_string(),
+                segments,
+            })
         }
     }
 }
 
-fn resolve_whisper_cli(cli_override: Option<&str>) -> Result<PathBuf> {
-    if let Some(value) = cli_override {
-        let candidate = expand_home(value.trim());
-        if candidate.exists() {
-            return Ok(candidate);
+/// Reads and removes the `-ojf` output. Missing or malformed JSON is not fatal;
+/// we just lose the per-word confidence data. Whisper writes each token's raw
+/// bytes, so a character split across tokens leaves invalid UTF-8; those
+/// pieces become U+FFFD rather than failing the whole file.
+fn read_full_json(path: &Path) -> Vec<Segment> {
+    let json = match std::fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => {
+            log::warn!("Whisper JSON output not available at {:?}: {}", path, e);
+            return Vec::new();
         }
-        if !value.contains(std::path::MAIN_SEPARATOR) && !value.contains('/') {
-            if let Ok(found) = which(value) {
-                return Ok(found);
-            }
+    };
+    let _ = std::fs::remove_file(path);
+
+    match parse_full_json(&json) {
+        Ok(segments) => segments,
+        Err(e) => {
+            log::warn!("Failed to parse whisper JSON output: {}", e);
+            Vec::new()
         }
-        return Err(anyhow!(
-            "Configured whisper-cli path not found: {}",
-            candidate.display()
-        ));
     }
+}
 
-    if let Ok(found) = which("whisper-cli") {
-        return Ok(found);
-    }
+fn parse_full_json(json: &str) -> Result<Vec<Segment>> {
+    let parsed: FullJson = serde_json::from_str(json).context("Invalid whisper JSON")?;
+
+    Ok(parsed
+        .transcription
+        .into_iter()
+        .map(|segment| Segment {
+            start_ms: segment.offsets.from,
+            end_ms: segment.offsets.to,
+            text: segment.text.trim().to_string(),
+            words: group_tokens_into_words(&segment.tokens),
+        })
+        .collect())
+}
 
-    let fallbacks = [
-        "/opt/homebrew/bin/whisper-cli",
-        "/usr/local/bin/whisper-cli",
-        "/usr/bin/whisper-cli",
-    ];
+/// Whisper tokens are sub-word pieces; a token starting with whitespace begins
+/// a new word. Special tokens such as `[_BEG_]` or `[_TT_150]` are skipped.
+fn group_tokens_into_words(tokens: &[JsonToken]) -> Vec<WordTiming> {
+    let mut words = Vec::new();
+    let mut current: Option<(WordTiming, usize)> = None;
 
-    for path in fallbacks {
-        let candidate = PathBuf::from(path);
-        if candidate.exists() {
-            return Ok(candidate);
+    for token in tokens {
+        if token.text.starts_with("[_") && token.text.ends_with(']') {
+            continue;
+        }
+
+        let starts_word = token.text.starts_with(char::is_whitespace);
+        let piece = token.text.trim();
+        if piece.is_empty() {
+            continue;
+        }
+
+        match current.as_mut() {
+            Some((word, count)) if !starts_word => {
+                word.text.push_str(piece);
+                word.end_ms = token.offsets.to;
+                word.confidence += token.p;
+                *count += 1;
+            }
+            _ => {
+                if let Some((word, count)) = current.take() {
+                    words.push(finish_word(word, count));
+                }
+                current = Some((
+                    WordTiming {
+                        text: piece.to_string(),
+                        start_ms: token.offsets.from,
+                        end_ms: token.offsets.to,
+                        confidence: token.p,
+                    },
+                    1,
+                ));
+            }
         }
     }
 
-    Err(anyhow!(
-        "Unable to locate whisper-cli. Please install it or set the binary path in Settings."
-    ))
+    if let Some((word, count)) = current {
+        words.push(finish_word(word, count));
+    }
+
+    words
 }
 
-fn expand_home(path: &str) -> PathBuf {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        if let Ok(home) = std::env::var("HOME") {
-            return PathBuf::from(home).join(stripped);
-        }
-    } else if path == "~" {
-        if let Ok(home) = std::env::var("HOME") {
-            return PathBuf::from(home);
-        }
+fn finish_word(mut word: WordTiming, token_count: usize) -> WordTiming {
+    word.confidence /= token_count as f32;
+    word
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `-ojf` output: "Café ok." with "é" split over two tokens, as
+    /// whisper writes it.
+    fn fixture() -> Vec<u8> {
+        let json = r#"{
+  "systeminfo": "AVX = 1",
+  "transcription": [
+    {
+      "timestamps": {"from": "00:00:00,000", "to": "00:00:01,500"},
+      "offsets": {"from": 0, "to": 1500},
+      "text": " Caf\u00e9 ok.",
+      "tokens": [
+        {"text": "[_BEG_]", "offsets": {"from": 0, "to": 0}, "id": 50364, "p": 0.98},
+        {"text": " Caf", "offsets": {"from": 0, "to": 400}, "id": 7301, "p": 0.9},
+        {"text": "{C3}", "offsets": {"from": 400, "to": 500}, "id": 2559, "p": 0.6},
+        {"text": "{A9}", "offsets": {"from": 500, "to": 600}, "id": 2560, "p": 0.9},
+        {"text": " ok", "offsets": {"from": 700, "to": 1200}, "id": 3133, "p": 0.8},
+        {"text": ".", "offsets": {"from": 1200, "to": 1300}, "id": 13, "p": 0.4},
+        {"text": "[_TT_75]", "offsets": {"from": 1500, "to": 1500}, "id": 50439, "p": 0.1}
+      ]
+    },
+    {
+      "offsets": {"from": 1500, "to": 2000},
+      "text": " Bye."
+    }
+  ]
+}"#;
+        // "é" is C3 A9; each half alone is invalid UTF-8
+        let (head, rest) = json.split_once("{C3}").unwrap();
+        let (middle, tail) = rest.split_once("{A9}").unwrap();
+        [head.as_bytes(), &[0xC3], middle.as_bytes(), &[0xA9], tail.as_bytes()].concat()
+    }
+
+    fn temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("convey-whisper-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn split_characters_keep_the_confidence_data() {
+        let path = temp_file("split-utf8", &fixture());
+        let segments = read_full_json(&path);
+        assert!(!path.exists());
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Café ok.");
+        assert_eq!((segments[0].start_ms, segments[0].end_ms), (0, 1500));
+        let words: Vec<&str> = segments[0].words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, ["Caf\u{FFFD}\u{FFFD}", "ok."]);
+
+        let cafe = &segments[0].words[0];
+        assert_eq!((cafe.start_ms, cafe.end_ms), (0, 600));
+        assert!((cafe.confidence - 0.8).abs() < 1e-6);
+        assert!((segments[0].words[1].confidence - 0.6).abs() < 1e-6);
+
+        // A segment without tokens has no word data
+        assert_eq!(segments[1].text, "Bye.");
+        assert!(segments[1].words.is_empty());
+        let average = average_confidence(&segments).unwrap();
+        assert!((average - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tokens_group_into_words() {
+        let token = |text: &str, from, to, p| JsonToken {
+            text: text.to_string(),
+            offsets: JsonOffsets { from, to },
+            p,
+        };
+        let words = group_tokens_into_words(&[
+            token(" un", 0, 100, 0.5),
+            token("believ", 100, 200, 0.7),
+            token("able", 200, 300, 0.9),
+            token("[_TT_15]", 300, 300, 0.0),
+            token(" ", 300, 300, 0.0),
+            token(" news", 300, 500, 1.0),
+        ]);
+        let summary: Vec<(&str, i64, i64)> =
+            words.iter().map(|w| (w.text.as_str(), w.start_ms, w.end_ms)).collect();
+        assert_eq!(summary, [("unbelievable", 0, 300), ("news", 300, 500)]);
+        assert!((words[0].confidence - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unreadable_output_means_no_word_data() {
+        let missing = std::env::temp_dir().join("convey-whisper-missing.json");
+        assert!(read_full_json(&missing).is_empty());
+        assert!(read_full_json(&temp_file("malformed", b"{\"transcription\": [")).is_empty());
+        assert!(parse_full_json("{}").unwrap().is_empty());
     }
-    PathBuf::from(path)
 }
@@ -1,62 +1,229 @@
 use crate::{
-    ai::{AIClient, AIConfig},
+    ai::{AIClient, AIConfig, ModelPrice},
+    audio,
+    commands::{CommandKey, Dictation, Step},
+    formatting,
+    database::{AiStatus, NewAiUsage, NewTranscription, Timings},
+    disfluency,
+    frontmost::{self, FrontmostApp},
+    modes::{self, ModeKind, ProcessingMode},
+    normalize,
+    redaction::{Placeholders, RedactionSettings, Redactor},
+    replacements::{self, AppliedReplacement, TemplateContext},
     services::AppServices,
     storage::AppSettings,
-    whisper::{WhisperClient, WhisperConfig},
+    whisper::{Transcript, WhisperClient, WhisperConfig},
 };
-use chrono::Utc;
+use chrono::{Local, Utc};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use log::{error, info, warn};
 
-/// Detects if the user said "and press enter" or similar at the end of the transcription
-/// Returns (cleaned_text, should_press_enter)
-fn detect_and_strip_enter_command(text: &str) -> (String, bool) {
-    let patterns = [
-        // Correct transcriptions - only full phrases with "and/then" + "enter"
-        "and press enter",
-        "and hit enter",
-        "and press return",
-        "and hit return",
-        "then press enter",
-        "then hit enter",
-        // Common misrecognitions - but only with "and/then" prefix
-        "and present enter",
-        "and presence enter",
-        "and pressing enter",
-        "and president enter",
-        "then present enter",
-        "then pressing enter",
-    ];
-
-    for pattern in &patterns {
-        // Check if the pattern appears at the end (with optional punctuation)
-        let trimmed = text.trim_end_matches(&['.', '!', '?', ',', ';', ' '][..]);
-        let trimmed_lower = trimmed.to_lowercase();
-
-        if trimmed_lower.ends_with(pattern) {
-            // Remove the pattern from the end
-            let pattern_start = trimmed.len() - pattern.len();
-            let cleaned = trimmed[..pattern_start].trim_end().to_string();
-            info!("Detected enter command: '{}', cleaned text: '{}'", pattern, cleaned);
-            return (cleaned, true);
-        }
-    }
-
-    (text.to_string(), false)
+/// A spoken command that acts on the previous dictation instead of being
+/// inserted itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EditCommand {
+    /// "Scratch that": delete the last inserted text.
+    Undo,
+    /// "Make that more formal": rewrite the last entry with the instruction.
+    Rewrite(String),
+}
+
+/// Recognizes an edit command when it is the whole dictation, ignoring case
+/// and punctuation. Rewrites need a short comparative instruction ("make that
+/// shorter", "make that more formal") so that dictating "Make that call
+/// tomorrow" stays a dictation.
+fn detect_edit_command(text: &str) -> Option<EditCommand> {
+    let normalized = text
+        .to_lowercase()
+        .replace(|c: char| !c.is_alphanumeric() && !c.is_whitespace(), " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if ["scratch that", "undo that", "delete that"].contains(&normalized.as_str()) {
+        return Some(EditCommand::Undo);
+    }
+
+    normalized
+        .strip_prefix("make that ")
+        .filter(|instruction| is_rewrite_instruction(instruction))
+        .map(|instruction| EditCommand::Rewrite(instruction.to_string()))
+}
+
+/// "more formal" or "less wordy" (up to three words), or a single
+/// comparative such as "shorter" or "friendlier".
+fn is_rewrite_instruction(instruction: &str) -> bool {
+    let words: Vec<&str> = instruction.split_whitespace().collect();
+    match words.as_slice() {
+        ["more" | "less", rest @ ..] => (1..=2).contains(&rest.len()),
+        [word] => word.len() > 4 && word.ends_with("er"),
+        _ => false,
+    }
+}
+
+/// Text Convey last put into the focused app. Cleared whenever a dictation
+/// isn't inserted or is submitted with Enter, since it can no longer be
+/// removed with backspaces.
+struct LastInsertion {
+    entry_id: i64,
+    text: String,
+    /// Where the text went; backspaces anywhere else would delete the wrong
+    /// characters.
+    app: Option<FrontmostApp>,
+}
+
+static LAST_INSERTION: Mutex<Option<LastInsertion>> = Mutex::new(None);
+
+fn remember_insertion(entry_id: i64, text: &str, app: Option<&FrontmostApp>) {
+    *LAST_INSERTION.lock().expect("last insertion poisoned") = (!text.is_empty()).then(|| {
+        LastInsertion {
+            entry_id,
+            text: text.to_string(),
+            app: app.cloned(),
+        }
+    });
+}
+
+fn forget_insertion() {
+    LAST_INSERTION.lock().expect("last insertion poisoned").take();
+}
+
+/// The last insertion, if it went into `app`. Once focus has moved to
+/// another app it is forgotten instead.
+fn take_last_insertion(app: Option<&FrontmostApp>) -> Option<LastInsertion> {
+    let insertion = LAST_INSERTION.lock().expect("last insertion poisoned").take()?;
+    if insertion.app.as_ref() != app {
+        info!(
+            "Focus moved from {:?} since the last dictation; forgetting it",
+            insertion.app.as_ref().map(|app| &app.name)
+        );
+        return None;
+    }
+    Some(insertion)
+}
+
+/// Result of a finished dictation, reported back to the UI.
+#[derive(Debug, Clone)]
+pub struct TranscriptionOutcome {
+    pub text: String,
+    /// Set when auto-paste was skipped because the average word confidence
+    /// fell below `AppSettings::min_paste_confidence`.
+    pub held_for_review: Option<f32>,
+    /// AI processing failed; the raw transcript was used instead.
+    pub ai_error: Option<String>,
+    /// Set when the dictation was an edit command, describing what it did.
+    pub command: Option<String>,
+}
+
+struct PipelineOutput {
+    id: i64,
+    text: String,
+    avg_confidence: Option<f32>,
+    ai_error: Option<String>,
+    /// Present when streamed AI output was typed into the focused app.
+    live_typer: Option<LiveTyper>,
+    ai_ms: Option<i64>,
+}
+
+// Long enough to cover the longest "... press enter" phrase plus punctuation.
+const LIVE_TYPING_HOLDBACK: usize = 32;
+
+// Lets the target app take a paste before the next key press or paste.
+const PASTE_SETTLE: Duration = Duration::from_millis(100);
+
+/// Types streamed AI output into the focused app as it arrives. The last
+/// `LIVE_TYPING_HOLDBACK` characters are held back until the stream ends so
+/// a trailing voice command can still be stripped before it is typed.
+struct LiveTyper {
+    pending: String,
+    typed: String,
+    failed: bool,
+}
+
+impl LiveTyper {
+    fn new() -> Self {
+        Self {
+            pending: String::new(),
+            typed: String::new(),
+            failed: false,
+        }
+    }
+
+    fn push(&mut self, services: &AppServices, delta: &str) {
+        self.pending.push_str(delta);
+        let excess = self.pending.chars().count().saturating_sub(LIVE_TYPING_HOLDBACK);
+        if excess == 0 || self.failed {
+            return;
+        }
+        let split = self
+            .pending
+            .char_indices()
+            .nth(excess)
+            .map(|(i, _)| i)
+            .unwrap_or(self.pending.len());
+        let ready: String = self.pending.drain(..split).collect();
+        self.type_out(services, &ready);
+    }
+
+    /// Types whatever of `final_text` has not been typed yet.
+    fn finish(&mut self, services: &AppServices, final_text: &str) {
+        self.pending.clear();
+        match final_text.strip_prefix(self.typed.as_str()) {
+            Some(rest) => self.type_out(services, rest),
+            None => warn!("Final text no longer matches what was typed; leaving it as is"),
+        }
+    }
+
+    fn has_typed(&self) -> bool {
+        !self.typed.is_empty()
+    }
+
+    fn type_out(&mut self, services: &AppServices, text: &str) {
+        if self.failed || text.is_empty() {
+            return;
+        }
+        match services.clipboard.type_text(text) {
+            Ok(()) => self.typed.push_str(text),
+            Err(e) => {
+                warn!("Live typing failed, stopping: {}", e);
+                self.failed = true;
+            }
+        }
+    }
+}
+
+fn held_for_review(settings: &AppSettings, avg_confidence: Option<f32>) -> Option<f32> {
+    avg_confidence.filter(|confidence| {
+        settings.hold_low_confidence_paste && *confidence < settings.min_paste_confidence
+    })
 }
 
 pub async fn start_recording(services: AppServices) -> Result<(), String> {
     let temp_dir = std::env::temp_dir();
     let audio_path = temp_dir.join(format!("recording_{}.wav", Utc::now().timestamp()));
 
+    // Capture before the start sound, while the target app still has focus
+    let target_app = frontmost::frontmost_app();
+    info!("Recording into {:?}", target_app.as_ref().map(|app| &app.name));
+
     crate::sound::play_start();
     services
         .recorder
-        .start(audio_path)
+        .start(audio_path, target_app)
         .map_err(|e| e.to_string())
 }
 
-pub async fn stop_recording_and_transcribe(services: AppServices) -> Result<String, String> {
+/// Stops the recording and runs the pipeline. `mode_id` selects a processing
+/// mode for this dictation (e.g. from a per-mode hotkey); `None` uses the
+/// mode configured for the target application, or else the active mode.
+pub async fn stop_recording_and_transcribe(
+    services: AppServices,
+    mode_id: Option<String>,
+) -> Result<TranscriptionOutcome, String> {
     info!("Stop recording workflow started");
+    // Key-up: the end-to-end time is measured from here
+    let stopped_at = Instant::now();
 
     let audio_path = services.recorder.stop().map_err(|e| {
         error!("Failed to stop recording: {}", e);
@@ -70,50 +237,317 @@ pub async fn stop_recording_and_transcribe(services: AppServices) -> Result<Stri
         e.to_string()
     })?;
 
-    let transcribed_text = transcribe_audio(&services, &settings, &audio_path).await?;
+    let target_app = services.recorder.take_target_app();
+    let mode = match mode_id.as_deref() {
+        Some(id) => settings.mode(id),
+        None => settings.mode_for_target(target_app.as_ref()),
+    };
+    info!("Using processing mode: {:?}", mode.map(|m| &m.name));
+
+    let duration_ms = audio::wav_duration_ms(&audio_path)
+        .map_err(|e| warn!("Failed to read the recording's duration: {}", e))
+        .ok();
+    let whisper_started = Instant::now();
+    let transcript = run_whisper(&settings, &audio_path).await?;
+    let whisper_ms = elapsed_ms(whisper_started);
+    if settings.recognize_edit_commands {
+        let command = detect_edit_command(&transcript.text).filter(|command| match command {
+            EditCommand::Undo => true,
+            EditCommand::Rewrite(_) => can_rewrite(&services, &settings),
+        });
+        if let Some(command) = command {
+            info!("Detected edit command: {:?}", command);
+            let _ = std::fs::remove_file(&audio_path);
+            return run_edit_command(&services, &settings, command, target_app.as_ref()).await;
+        }
+    }
 
-    // Check if the user said "and press enter" or similar phrases at the end (if enabled)
-    let (final_text, should_press_enter) = if settings.recognize_press_enter {
-        detect_and_strip_enter_command(&transcribed_text)
+    let PipelineOutput {
+        id,
+        text: transcribed_text,
+        avg_confidence,
+        ai_error,
+        live_typer,
+        ai_ms,
+    } = transcribe_audio(&services, &settings, mode, target_app.as_ref(), transcript, duration_ms)
+        .await?;
+    let transcribed_text = if settings.redaction.before_paste {
+        redactor(&settings).mask(&transcribed_text)
     } else {
-        (transcribed_text.clone(), false)
+        transcribed_text
     };
 
-    if settings.auto_paste || settings.auto_paste_and_enter || !final_text.is_empty() {
+    let held_for_review = held_for_review(&settings, avg_confidence);
+    if let Some(confidence) = held_for_review {
+        info!(
+            "Average confidence {:.2} below {:.2}, holding auto-paste for review",
+            confidence, settings.min_paste_confidence
+        );
+    }
+    let auto_paste = settings.auto_paste && held_for_review.is_none();
+    let auto_paste_and_enter = settings.auto_paste_and_enter && held_for_review.is_none();
+
+    // Split out spoken commands ("new line", "and press enter", ...) if enabled
+    let mut dictation = if settings.recognize_press_enter {
+        match services.settings.load_grammar() {
+            Ok(grammar) => grammar.parse(&transcribed_text),
+            Err(e) => {
+                warn!("Failed to load command grammar, ignoring voice commands: {}", e);
+                Dictation::plain(&transcribed_text)
+            }
+        }
+    } else {
+        Dictation::plain(&transcribed_text)
+    };
+    if auto_paste_and_enter && !dictation.ends_with(CommandKey::Enter) {
+        dictation.push_key(CommandKey::Enter);
+    }
+    let final_text = dictation.text();
+
+    let paste_started = Instant::now();
+    if let Some(mut typer) = live_typer.filter(|t| t.has_typed()) {
+        // The text is already (mostly) in the focused app, so only commands
+        // after it can still run. If AI failed part way, don't append the
+        // raw transcript to the half-typed rewrite.
+        let mut pressed_keys = false;
+        if ai_error.is_none() {
+            typer.finish(&services, &final_text);
+            for key in dictation.trailing_keys() {
+                if let Err(e) = services.clipboard.press_key(key) {
+                    warn!("Failed to press {:?} after live typing: {}", key, e);
+                    break;
+                }
+                pressed_keys = true;
+            }
+        }
+        if pressed_keys {
+            forget_insertion();
+        } else {
+            remember_insertion(id, &typer.typed, target_app.as_ref());
+        }
+        if let Err(e) = services.clipboard.copy_text(&final_text) {
+            error!("Failed to copy text to clipboard: {}", e);
+        }
+    } else if settings.auto_paste || settings.auto_paste_and_enter || !final_text.is_empty() {
+        forget_insertion();
         if let Err(e) = services.clipboard.copy_text(&final_text) {
             error!("Failed to copy text to clipboard: {}", e);
         } else {
             info!("Text copied to clipboard");
-            // Press enter if:
-            // 1. auto_paste_and_enter is enabled, OR
-            // 2. user said "and press enter" AND auto_paste is enabled
-            if settings.auto_paste_and_enter || (should_press_enter && settings.auto_paste) {
-                if let Err(e) = services.clipboard.paste_text_and_enter(&final_text) {
-                    warn!("Failed to auto-paste-and-enter (text is copied to clipboard): {}", e);
-                } else {
-                    info!("Text pasted and Enter pressed successfully");
+            // Voice commands only press keys when pasting is enabled
+            if auto_paste || auto_paste_and_enter {
+                match insert_dictation(&services, &dictation) {
+                    Ok(()) => {
+                        info!("Text pasted successfully");
+                        if !dictation.has_keys() {
+                            remember_insertion(id, &final_text, target_app.as_ref());
+                        }
+                    }
+                    Err(e) => warn!("Failed to auto-paste (text is copied to clipboard): {}", e),
                 }
-            } else if settings.auto_paste {
-                if let Err(e) = services.clipboard.paste_text(&final_text) {
-                    warn!("Failed to auto-paste (text is copied to clipboard): {}", e);
-                } else {
-                    info!("Text pasted successfully");
+                // Each pasted piece replaced the clipboard; leave the whole text there
+                if dictation.steps.len() > 2 {
+                    if let Err(e) = services.clipboard.copy_text(&final_text) {
+                        error!("Failed to copy text to clipboard: {}", e);
+                    }
                 }
             }
         }
     }
 
+    let timings = Timings {
+        whisper_ms: Some(whisper_ms),
+        ai_ms,
+        paste_ms: Some(elapsed_ms(paste_started)),
+        total_ms: Some(elapsed_ms(stopped_at)),
+    };
+    info!("Pipeline timings: {:?}", timings);
+    if let Err(e) = services.history.record_timings(id, &timings) {
+        error!("Failed to save timings: {}", e);
+    }
+
     let _ = std::fs::remove_file(&audio_path);
     info!("Workflow completed successfully");
 
-    Ok(transcribed_text)
+    Ok(TranscriptionOutcome {
+        text: transcribed_text,
+        held_for_review,
+        ai_error,
+        command: None,
+    })
 }
 
-async fn transcribe_audio(
+fn elapsed_ms(since: Instant) -> i64 {
+    since.elapsed().as_millis() as i64
+}
+
+/// Pastes the text pieces and presses the command keys in order.
+fn insert_dictation(services: &AppServices, dictation: &Dictation) -> anyhow::Result<()> {
+    match dictation.steps.as_slice() {
+        [Step::Text(text)] => return services.clipboard.paste_text(text),
+        // Pasting and pressing Enter in one script is more reliable than a
+        // separate key press right after the paste.
+        [Step::Text(text), Step::Key(CommandKey::Enter)] => {
+            return services.clipboard.paste_text_and_enter(text)
+        }
+        _ => {}
+    }
+
+    for step in &dictation.steps {
+        match step {
+            Step::Text(text) => {
+                services.clipboard.paste_text(text)?;
+                std::thread::sleep(PASTE_SETTLE);
+            }
+            Step::Key(key) => services.clipboard.press_key(*key)?,
+        }
+    }
+    Ok(())
+}
+
+/// A rewrite sends the previous dictation to the AI provider, so it only
+/// happens when AI processing is on and ready and there is something to
+/// rewrite. Otherwise "make that ..." is dictated like anything else.
+fn can_rewrite(services: &AppServices, settings: &AppSettings) -> bool {
+    if !settings.ai_processing_enabled {
+        info!("AI processing is off; treating the rewrite command as dictation");
+        return false;
+    }
+    let has_key = match settings.active_provider_config() {
+        Some(provider) if provider.requires_api_key => services
+            .settings
+            .has_api_key(&provider.api_key_name())
+            .unwrap_or(false),
+        Some(_) => true,
+        None => false,
+    };
+    if !has_key {
+        info!("The AI provider isn't set up; treating the rewrite command as dictation");
+        return false;
+    }
+    match services.history.recent(1) {
+        Ok(entries) if !entries.is_empty() => true,
+        Ok(_) => {
+            info!("Nothing to rewrite; treating the rewrite command as dictation");
+            false
+        }
+        Err(e) => {
+            warn!("Failed to read history, treating the rewrite command as dictation: {}", e);
+            false
+        }
+    }
+}
+
+async fn run_edit_command(
     services: &AppServices,
+    settings: &AppSettings,
+    command: EditCommand,
+    target_app: Option<&FrontmostApp>,
+) -> Result<TranscriptionOutcome, String> {
+    match command {
+        EditCommand::Undo => {
+            let insertion = take_last_insertion(target_app).ok_or_else(|| {
+                "Nothing to scratch: the last dictation wasn't typed into an app".to_string()
+            })?;
+            services
+                .clipboard
+                .press_backspace(insertion.text.chars().count())
+                .map_err(|e| {
+                    error!("Failed to remove the last dictation: {}", e);
+                    e.to_string()
+                })?;
+            info!("Removed last insertion from entry {}", insertion.entry_id);
+
+            Ok(TranscriptionOutcome {
+                text: String::new(),
+                held_for_review: None,
+                ai_error: None,
+                command: Some("Removed the last dictation".to_string()),
+            })
+        }
+        EditCommand::Rewrite(instruction) => {
+            let entry = services
+                .history
+                .recent(1)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| "There is no previous dictation to rewrite".to_string())?;
+            let source = entry.current_text();
+
+            let rewrite = ProcessingMode {
+                id: "rewrite".to_string(),
+                name: "Rewrite".to_string(),
+                prompt: format!(
+                    "Rewrite the user's text to make it {}. Preserve the meaning and reply with \
+                     only the rewritten text.",
+                    instruction
+                ),
+                model: None,
+                temperature: 0.3,
+                hotkey: None,
+                spoken_formatting: None,
+                inverse_text_normalization: None,
+                kind: ModeKind::Ai,
+            };
+            info!("Rewriting transcription {} ({})", entry.id, instruction);
+            let rewritten = process_with_ai(
+                services,
+                settings,
+                Some(&rewrite),
+                entry.id,
+                entry.app.as_deref(),
+                source,
+                |_| {},
+            )
+            .await?;
+
+            // Keep the entry's own mode so a later re-run still uses it
+            if let Err(e) = services.history.update_processed_text(
+                entry.id,
+                &redact_for_storage(settings, &rewritten),
+                entry.mode.as_deref(),
+            ) {
+                error!("Failed to save rewritten text: {}", e);
+            }
+            let rewritten = if settings.redaction.before_paste {
+                redactor(settings).mask(&rewritten)
+            } else {
+                rewritten
+            };
+
+            if let Err(e) = services.clipboard.copy_text(&rewritten) {
+                error!("Failed to copy text to clipboard: {}", e);
+            }
+            // Swap the text in place if it is still the last thing we typed
+            if let Some(insertion) = take_last_insertion(target_app).filter(|i| i.entry_id == entry.id) {
+                let replaced = services
+                    .clipboard
+                    .press_backspace(insertion.text.chars().count())
+                    .and_then(|()| services.clipboard.paste_text(&rewritten));
+                match replaced {
+                    Ok(()) => remember_insertion(entry.id, &rewritten, target_app),
+                    Err(e) => warn!(
+                        "Failed to replace the last dictation (text is copied to clipboard): {}",
+                        e
+                    ),
+                }
+            }
+
+            Ok(TranscriptionOutcome {
+                text: rewritten,
+                held_for_review: None,
+                ai_error: None,
+                command: Some(format!("Rewrote the last dictation to make it {}", instruction)),
+            })
+        }
+    }
+}
+
+async fn run_whisper(
     settings: &AppSettings,
     audio_path: &std::path::Path,
-) -> Result<String, String> {
+) -> Result<Transcript, String> {
     info!("Preparing Whisper transcription...");
     let whisper_config = WhisperConfig {
         model: settings.whisper_model.clone(),
@@ -123,63 +557,461 @@ async fn transcribe_audio(
             .as_ref()
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty()),
+        vocabulary: settings.vocabulary.clone(),
     };
 
     let whisper_client = WhisperClient::new(whisper_config);
-    let mut transcribed_text = whisper_client.transcribe(audio_path).await.map_err(|e| {
+    whisper_client.transcribe(audio_path).await.map_err(|e| {
         error!("Whisper transcription failed: {}", e);
         e.to_string()
-    })?;
-    info!("Transcription completed: {}", transcribed_text);
+    })
+}
+
+/// Detectors from the redaction settings. If a custom pattern doesn't
+/// compile, the built-in detectors still run rather than none at all.
+fn redactor(settings: &AppSettings) -> Redactor {
+    Redactor::new(&settings.redaction).unwrap_or_else(|e| {
+        warn!("{:#}; redacting with the built-in detectors only", e);
+        Redactor::new(&RedactionSettings {
+            custom_patterns: Vec::new(),
+            ..settings.redaction.clone()
+        })
+        .expect("built-in detectors need no custom patterns")
+    })
+}
 
-    let mut processed_text = None;
+/// Masks personal data in text about to be saved, if configured.
+fn redact_for_storage(settings: &AppSettings, text: &str) -> String {
+    if settings.redaction.before_storage {
+        redactor(settings).mask(text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Applies the user's replacement dictionary. If the rules can't be loaded
+/// the dictation still goes through unchanged.
+fn apply_replacements(services: &AppServices, text: &str) -> (String, Vec<AppliedReplacement>) {
+    let rules = match services.history.replacements() {
+        Ok(rules) => rules,
+        Err(e) => {
+            warn!("Failed to load replacements: {}", e);
+            return (text.to_string(), Vec::new());
+        }
+    };
 
-    if settings.ai_processing_enabled {
-        info!("AI processing is enabled, retrieving API key...");
-        let ai_key = services
+    let clipboard = if replacements::uses_clipboard(&rules) {
+        services.clipboard.read_text().ok()
+    } else {
+        None
+    };
+    let context = TemplateContext {
+        now: Local::now(),
+        clipboard,
+    };
+
+    let (text, applied) = replacements::apply(text, &rules, &context);
+    if !applied.is_empty() {
+        info!("Applied {} replacement(s)", applied.len());
+    }
+    (text, applied)
+}
+
+/// Whisper's words after the local steps for `mode`: filler removal, spoken
+/// formatting, number normalization and replacements.
+fn prepare_transcript(
+    services: &AppServices,
+    settings: &AppSettings,
+    mode: Option<&ProcessingMode>,
+    whisper_text: &str,
+) -> (String, Vec<AppliedReplacement>) {
+    // Cleanup runs on whisper's own words, before anything is rewritten
+    let cleaned = if settings.remove_fillers {
+        disfluency::remove_disfluencies(whisper_text, settings.fillers())
+    } else {
+        whisper_text.to_string()
+    };
+    // Spoken punctuation becomes literal before AI sees the text, so a
+    // dictated "open paren" isn't paraphrased away. Code modes are formatted
+    // entirely locally and never sent to AI.
+    let mut formatted = if mode.is_some_and(ProcessingMode::is_code) {
+        formatting::apply_code_formatting(&cleaned)
+    } else if settings.spoken_formatting_for(mode) {
+        formatting::apply_spoken_formatting(&cleaned)
+    } else {
+        cleaned
+    };
+    if settings.inverse_text_normalization_for(mode) {
+        formatted = normalize::inverse_normalize(&formatted, settings.language.as_deref());
+    }
+    apply_replacements(services, &formatted)
+}
+
+async fn transcribe_audio(
+    services: &AppServices,
+    settings: &AppSettings,
+    mode: Option<&ProcessingMode>,
+    target_app: Option<&FrontmostApp>,
+    transcript: Transcript,
+    duration_ms: Option<i64>,
+) -> Result<PipelineOutput, String> {
+    let avg_confidence = transcript.average_confidence();
+    let code = mode.is_some_and(ProcessingMode::is_code);
+    let use_ai = settings.ai_processing_enabled && !code;
+    let (raw_text, applied_replacements) =
+        prepare_transcript(services, settings, mode, &transcript.text);
+    info!(
+        "Transcription completed (confidence {:?}): {}",
+        avg_confidence, raw_text
+    );
+
+    // `text` holds the transcript after the local steps, which is what AI
+    // is given; whisper's own words are kept in `pre_cleanup_text` whenever
+    // those steps changed them. The AI result is stored alongside once it
+    // arrives. Word segments can't be masked, so they are dropped when they
+    // contain anything to redact.
+    let whisper_text = (raw_text != transcript.text).then(|| transcript.text.clone());
+    let segments = if settings.redaction.before_storage
+        && redactor(settings).mask(&transcript.text) != transcript.text
+    {
+        Vec::new()
+    } else {
+        transcript.segments
+    };
+    let id = services
+        .history
+        .insert_transcription(&NewTranscription {
+            text: redact_for_storage(settings, &raw_text),
+            processed_text: None,
+            language: settings.language.clone(),
+            duration_ms,
+            avg_confidence,
+            segments,
+            mode: mode.map(|m| m.id.clone()),
+            ai_status: use_ai.then_some(AiStatus::Pending),
+            app: target_app.map(|app| app.name.clone()),
+            applied_replacements,
+            pre_cleanup_text: whisper_text.map(|text| redact_for_storage(settings, &text)),
+            whisper_model: Some(settings.whisper_model.clone()),
+        })
+        .map_err(|e| {
+            error!("Failed to save to database: {}", e);
+            e.to_string()
+        })?;
+
+    if !use_ai {
+        return Ok(PipelineOutput {
+            id,
+            text: raw_text,
+            avg_confidence,
+            ai_error: None,
+            live_typer: None,
+            ai_ms: None,
+        });
+    }
+
+    // Streamed output can't be masked before it is typed
+    let mut live_typer = (settings.ai_stream
+        && settings.type_streamed_output
+        && settings.auto_paste
+        && !settings.redaction.before_paste
+        && held_for_review(settings, avg_confidence).is_none())
+    .then(LiveTyper::new);
+
+    // AI failures are not fatal: the raw transcript is still pasted and the
+    // entry is marked failed so it can be retried from history.
+    let app_name = target_app.map(|app| app.name.as_str());
+    let ai_started = Instant::now();
+    let result = process_with_ai(services, settings, mode, id, app_name, &raw_text, |delta| {
+        services.preview.push(delta);
+        if let Some(typer) = live_typer.as_mut() {
+            typer.push(services, delta);
+        }
+    })
+    .await;
+    let ai_ms = Some(elapsed_ms(ai_started));
+    services.preview.clear();
+
+    match result {
+        Ok(processed) => {
+            if let Err(e) = services.history.update_processed_text(
+                id,
+                &redact_for_storage(settings, &processed),
+                mode.map(|m| m.id.as_str()),
+            ) {
+                error!("Failed to save processed text: {}", e);
+            }
+            Ok(PipelineOutput {
+                id,
+                text: processed,
+                avg_confidence,
+                ai_error: None,
+                live_typer,
+                ai_ms,
+            })
+        }
+        Err(e) => {
+            warn!("AI processing failed, falling back to raw transcript: {}", e);
+            if let Err(db_err) = services.history.mark_ai_failed(id, &e) {
+                error!("Failed to mark AI failure: {}", db_err);
+            }
+            Ok(PipelineOutput {
+                id,
+                text: raw_text,
+                avg_confidence,
+                ai_error: Some(e),
+                live_typer,
+                ai_ms,
+            })
+        }
+    }
+}
+
+/// Re-runs AI processing on a saved entry with the given mode (or the
+/// active mode) and stores the new result on the entry. Whisper's words go
+/// through the local steps again for that mode; entries that only kept the
+/// locally processed text are sent as they are. Code modes are formatted
+/// locally, as in a live dictation, and never sent to AI.
+pub async fn reprocess_entry(
+    services: AppServices,
+    id: i64,
+    mode_id: Option<String>,
+) -> Result<(), String> {
+    let settings = services.settings.load().map_err(|e| e.to_string())?;
+    let mode = match mode_id.as_deref() {
+        Some(id) => settings.mode(id),
+        None => settings.active_mode(),
+    };
+
+    let entry = services
+        .history
+        .get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Transcription {} no longer exists", id))?;
+
+    info!("Re-processing transcription {} with mode {:?}", id, mode.map(|m| &m.name));
+    let code = mode.is_some_and(ProcessingMode::is_code);
+    let input = match &entry.pre_cleanup_text {
+        Some(whisper_text) => prepare_transcript(&services, &settings, mode, whisper_text).0,
+        None if code => formatting::apply_code_formatting(&entry.text),
+        None => entry.text.clone(),
+    };
+    let app_name = entry.app.as_deref();
+    let result = if code {
+        Ok(input)
+    } else {
+        process_with_ai(&services, &settings, mode, id, app_name, &input, |_| {}).await
+    };
+    let processed = match result {
+        Ok(processed) => processed,
+        Err(e) => {
+            // Only an entry without a usable result is marked failed; a
+            // failed re-run keeps the earlier processed text.
+            if entry.processed_text.is_none() {
+                if let Err(db_err) = services.history.mark_ai_failed(id, &e) {
+                    error!("Failed to mark AI failure: {}", db_err);
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    services
+        .history
+        .update_processed_text(
+            id,
+            &redact_for_storage(&settings, &processed),
+            mode.map(|m| m.id.as_str()),
+        )
+        .map_err(|e| {
+            error!("Failed to save re-processed text: {}", e);
+            e.to_string()
+        })
+}
+
+/// Saves the user's edit of an entry as its newest revision, masked like
+/// everything else stored in history.
+pub async fn save_revision(services: AppServices, id: i64, text: String) -> Result<(), String> {
+    let settings = services.settings.load().map_err(|e| e.to_string())?;
+    services
+        .history
+        .add_revision(id, &redact_for_storage(&settings, text.trim_end()))
+        .map_err(|e| e.to_string())
+}
+
+/// Runs the text through the active provider. With `ai_stream` enabled the
+/// response is streamed and `on_delta` sees each piece as it arrives. Token
+/// usage is recorded against `transcription_id`. `app` fills the prompt's
+/// `{app}` variable. With redaction before AI, personal data is replaced
+/// first; reversible placeholders are put back into the reply (and into
+/// each delta) locally.
+async fn process_with_ai(
+    services: &AppServices,
+    settings: &AppSettings,
+    mode: Option<&ProcessingMode>,
+    transcription_id: i64,
+    app: Option<&str>,
+    text: &str,
+    mut on_delta: impl FnMut(&str) + Send,
+) -> Result<String, String> {
+    if let Some(budget) = settings.monthly_ai_budget_usd {
+        let spent = services
+            .history
+            .ai_usage_this_month()
+            .map(|totals| totals.cost_usd)
+            .unwrap_or_else(|e| {
+                warn!("Failed to read AI usage, not enforcing budget: {}", e);
+                0.0
+            });
+        if spent >= budget {
+            info!("Monthly AI budget ${:.2} reached (${:.2} spent)", budget, spent);
+            return Err(format!(
+                "Monthly AI budget of ${:.2} reached; AI processing is paused until next month",
+                budget
+            ));
+        }
+    }
+
+    let provider = settings.active_provider_config().cloned().ok_or_else(|| {
+        error!("Unknown AI provider: {}", settings.active_provider);
+        format!("AI provider '{}' is not configured", settings.active_provider)
+    })?;
+
+    info!("AI processing is enabled, retrieving API key for {}...", provider.name);
+    let ai_key = if provider.requires_api_key {
+        let key = services
             .settings
-            .get_api_key("openai_api_key")
+            .get_api_key(&provider.api_key_name())
             .map_err(|e| {
                 error!("Failed to get API key: {}", e);
                 format!(
-                    "Please set your OpenAI API key in settings for AI processing: {}",
-                    e
+                    "Please set your {} API key in settings for AI processing: {}",
+                    provider.name, e
                 )
             })?;
+        Some(key)
+    } else {
+        None
+    };
 
-        info!("Processing text with AI...");
-        let ai_config = AIConfig {
-            api_key: ai_key,
-            model: settings.ai_model.clone(),
-            system_prompt: settings.system_prompt.clone(),
-        };
+    let (text, placeholders) = if !settings.redaction.before_ai {
+        (text.to_string(), Placeholders::default())
+    } else if settings.redaction.reversible {
+        redactor(settings).redact(text)
+    } else {
+        (redactor(settings).mask(text), Placeholders::default())
+    };
+    if !placeholders.is_empty() {
+        info!("Redacted personal data before AI processing");
+    }
+
+    info!("Processing text with AI...");
+    let provider_id = provider.id.clone();
+    let model = mode
+        .and_then(|m| m.model.clone())
+        .unwrap_or_else(|| settings.ai_model.clone());
+    let ai_config = AIConfig {
+        provider,
+        api_key: ai_key,
+        model: model.clone(),
+        system_prompt: mode
+            .map(|m| m.prompt.as_str())
+            .or(settings.system_prompt.as_deref())
+            .map(|prompt| modes::render_prompt(prompt, app))
+            .map(|prompt| {
+                if placeholders.is_empty() {
+                    prompt
+                } else {
+                    format!("{}\n\nKeep placeholders such as <EMAIL_1> exactly as written.", prompt)
+                }
+            }),
+        temperature: mode.map(|m| m.temperature).unwrap_or(0.3),
+        timeout: Duration::from_secs(settings.ai_timeout_secs),
+        max_retries: settings.ai_max_retries,
+    };
 
-        let ai_client = AIClient::new(ai_config);
-        let processed = ai_client
-            .process_text(&transcribed_text)
+    let ai_client = AIClient::new(ai_config);
+    let mut restorer = placeholders.streaming();
+    let result = if settings.ai_stream {
+        ai_client
+            .process_text_streaming(&text, |delta| {
+                let ready = restorer.push(delta);
+                if !ready.is_empty() {
+                    on_delta(&ready);
+                }
+            })
             .await
-            .map_err(|e| {
-                error!("AI processing failed: {}", e);
-                e.to_string()
-            })?;
+    } else {
+        ai_client.process_text(&text).await
+    };
+    let rest = restorer.finish();
+    if !rest.is_empty() {
+        on_delta(&rest);
+    }
+    let completion = result.map_err(|e| {
+        error!("AI processing failed: {}", e);
+        e.to_string()
+    })?;
 
-        processed_text = Some(processed.clone());
-        transcribed_text = processed;
-        info!("AI processing completed");
+    info!("AI processing completed");
+    if let Some(usage) = completion.usage {
+        let cost_usd = ModelPrice::find(&settings.model_prices, &model).map(|p| p.cost(&usage));
+        if let Err(e) = services.history.record_ai_usage(&NewAiUsage {
+            transcription_id: Some(transcription_id),
+            provider: &provider_id,
+            model: &model,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            cost_usd,
+        }) {
+            error!("Failed to record AI usage: {}", e);
+        }
     }
+    Ok(placeholders.restore(&completion.text))
+}
 
-    services
-        .history
-        .insert_transcription(
-            &transcribed_text,
-            processed_text.as_deref(),
-            settings.language.as_deref(),
-            None,
-        )
-        .map_err(|e| {
-            error!("Failed to save to database: {}", e);
-            e.to_string()
-        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_edit_commands() {
+        let rewrite = |instruction: &str| Some(EditCommand::Rewrite(instruction.to_string()));
+        let cases = [
+            ("Scratch that.", Some(EditCommand::Undo)),
+            ("undo that", Some(EditCommand::Undo)),
+            ("Make that more formal.", rewrite("more formal")),
+            ("Make that less wordy", rewrite("less wordy")),
+            ("make that shorter!", rewrite("shorter")),
+            ("Make that call tomorrow.", None),
+            ("Make that a double.", None),
+            ("Make that more or less polite please", None),
+            ("Make that", None),
+            ("Scratch that idea, let's start over.", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(detect_edit_command(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn last_insertion_is_only_taken_in_the_same_app() {
+        let app = |name: &str| FrontmostApp {
+            name: name.to_string(),
+            bundle_id: Some(format!("com.example.{}", name.to_lowercase())),
+        };
+        let (notes, mail) = (app("Notes"), app("Mail"));
 
-    Ok(transcribed_text)
+        remember_insertion(1, "hello", Some(&notes));
+        assert_eq!(take_last_insertion(Some(&notes)).map(|i| i.text), Some("hello".to_string()));
+        assert!(take_last_insertion(Some(&notes)).is_none());
+
+        // Asking from another app forgets it rather than keeping it around
+        remember_insertion(2, "hello", Some(&notes));
+        assert!(take_last_insertion(Some(&mail)).is_none());
+        assert!(take_last_insertion(Some(&notes)).is_none());
+
+        remember_insertion(3, "", Some(&notes));
+        assert!(take_last_insertion(Some(&notes)).is_none());
+    }
 }